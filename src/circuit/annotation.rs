@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+
+use super::{ComponentId, WireId};
+
+/// Font size of new annotations in design coordinates
+pub const DEFAULT_FONT_SIZE: u8 = 14;
+pub const MIN_FONT_SIZE: u8 = 6;
+pub const MAX_FONT_SIZE: u8 = 96;
+/// Color of new annotations
+pub const DEFAULT_TEXT_COLOR: &str = "#000000";
+/// Distance between two lines of text relative to the font size
+pub const LINE_HEIGHT: f64 = 1.2;
+/// Average width of a character relative to the font size, the extent of a text is estimated with it
+const CHARACTER_WIDTH: f64 = 0.6;
+
+/// Stable identity of a text annotation.
+/// Ids are never reused within a [`Circuit`](super::Circuit), even after the annotation was removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AnnotationId(pub(super) u32);
+
+/// Which side of the lines of an annotation is aligned at its position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TextAlignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlignment {
+    pub const ALL: [Self; 3] = [Self::Left, Self::Center, Self::Right];
+
+    /// Returns the name of the alignment, as used by the `text-align` of CSS and canvases
+    pub const fn get_name(&self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Center => "center",
+            Self::Right => "right",
+        }
+    }
+}
+
+/// What an annotation is attached to, it moves along with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationAnchor {
+    Component(ComponentId),
+    /// Labels the net of the wire, the annotation is placed relative to the wire's first segment
+    Wire(WireId),
+}
+
+/// A text on the canvas, either placed freely or attached to a component or net as its label
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub(super) id: AnnotationId,
+    text: String,
+    /// The point the lines are aligned at, relative to the anchor if there is one
+    position: (f64, f64),
+    font_size: u8,
+    alignment: TextAlignment,
+    /// CSS color as `#rrggbb`
+    color: String,
+    /// Quarter turns clockwise around the position
+    rotation: u8,
+    anchor: Option<AnnotationAnchor>,
+}
+
+impl Annotation {
+    /// Creates a free annotation, its id is assigned when it is added to a circuit
+    pub fn new(text: String, position: (f64, f64)) -> Self {
+        Self {
+            id: AnnotationId(0),
+            text,
+            position,
+            font_size: DEFAULT_FONT_SIZE,
+            alignment: TextAlignment::default(),
+            color: DEFAULT_TEXT_COLOR.to_owned(),
+            rotation: 0,
+            anchor: None,
+        }
+    }
+    pub fn with_text(self, text: String) -> Self {
+        Self { text, ..self }
+    }
+    pub fn with_font_size(self, font_size: u8) -> Self {
+        Self {
+            font_size: font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE),
+            ..self
+        }
+    }
+    pub fn with_alignment(self, alignment: TextAlignment) -> Self {
+        Self { alignment, ..self }
+    }
+    pub fn with_color(self, color: String) -> Self {
+        Self { color, ..self }
+    }
+    pub fn with_rotation(self, rotation: u8) -> Self {
+        Self {
+            rotation: rotation % 4,
+            ..self
+        }
+    }
+    /// Attaches the annotation, its position becomes relative to the anchor
+    pub fn with_anchor(self, anchor: Option<AnnotationAnchor>) -> Self {
+        Self { anchor, ..self }
+    }
+
+    pub const fn get_id(&self) -> AnnotationId {
+        self.id
+    }
+    pub fn get_text(&self) -> &str {
+        &self.text
+    }
+    pub const fn get_position(&self) -> (f64, f64) {
+        self.position
+    }
+    pub fn set_position(&mut self, position: (f64, f64)) {
+        self.position = position;
+    }
+    pub const fn get_font_size(&self) -> u8 {
+        self.font_size
+    }
+    pub const fn get_alignment(&self) -> TextAlignment {
+        self.alignment
+    }
+    pub fn get_color(&self) -> &str {
+        &self.color
+    }
+    pub const fn get_rotation(&self) -> u8 {
+        self.rotation
+    }
+    pub const fn get_anchor(&self) -> Option<AnnotationAnchor> {
+        self.anchor
+    }
+    /// Returns whether the annotation moves along with the component
+    pub fn is_attached_to(&self, id: ComponentId) -> bool {
+        self.anchor == Some(AnnotationAnchor::Component(id))
+    }
+
+    /// Returns the lines of the text, an empty text still takes up one line
+    pub fn get_lines(&self) -> Vec<&str> {
+        let lines: Vec<_> = self.text.lines().collect();
+        if lines.is_empty() {
+            vec![""]
+        } else {
+            lines
+        }
+    }
+    /// Estimates the width and height of the text before it is rotated
+    #[allow(clippy::cast_precision_loss)]
+    pub fn get_size(&self) -> (f64, f64) {
+        let lines = self.get_lines();
+        let characters = lines
+            .iter()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or_default();
+        let font_size = f64::from(self.font_size);
+        (
+            characters.max(1) as f64 * font_size * CHARACTER_WIDTH,
+            lines.len() as f64 * font_size * LINE_HEIGHT,
+        )
+    }
+    /// Returns the top left and bottom right corner of the box around the text drawn at the absolute position
+    pub fn get_extent(&self, origin: (f64, f64)) -> ((f64, f64), (f64, f64)) {
+        let (width, height) = self.get_size();
+        let left = match self.alignment {
+            TextAlignment::Left => 0.0,
+            TextAlignment::Center => -width / 2.0,
+            TextAlignment::Right => -width,
+        };
+        let rotate = |(x, y): (f64, f64)| match self.rotation {
+            1 => (-y, x),
+            2 => (-x, -y),
+            3 => (y, -x),
+            _ => (x, y),
+        };
+        let (a, b) = (rotate((left, 0.0)), rotate((left + width, height)));
+        (
+            (origin.0 + a.0.min(b.0), origin.1 + a.1.min(b.1)),
+            (origin.0 + a.0.max(b.0), origin.1 + a.1.max(b.1)),
+        )
+    }
+}
@@ -0,0 +1,880 @@
+use std::collections::HashMap;
+
+use super::{
+    Annotation, AnnotationAnchor, AnnotationId, ComponentType, ConnectionPoint, Orientation, Timing,
+};
+
+/// Stable identity of a placed component.
+/// Ids are never reused within a [`Circuit`], even after the component was removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ComponentId(u32);
+
+/// Stable identity of a wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WireId(u32);
+
+/// Identity of a net. Nets are derived from the wires, so a [`NetId`] is only valid until the wires change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NetId(u32);
+
+/// Reference to a single port (connection point) of a placed component
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PortRef {
+    pub component: ComponentId,
+    pub port: usize,
+}
+
+impl PortRef {
+    pub const fn new(component: ComponentId, port: usize) -> Self {
+        Self { component, port }
+    }
+}
+
+/// A component placed in a [`Circuit`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    id: ComponentId,
+    component_type: ComponentType,
+    position: (f64, f64),
+    /// width and height of the component as it is oriented
+    size: (f64, f64),
+    /// The ports of the component relative to its position, as it is oriented
+    ports: Vec<ConnectionPoint>,
+    orientation: Orientation,
+    /// Which of the input ports are inverted, in the order of the input ports
+    inverted_inputs: Vec<bool>,
+    timing: Timing,
+    /// The name shown next to the component, e.g. the name of a pin
+    label: String,
+}
+
+impl Component {
+    /// Creates a new unplaced component in its default orientation. The id is assigned by [`Circuit::add_component`].
+    pub fn new(
+        component_type: ComponentType,
+        position: (f64, f64),
+        size: (f64, f64),
+        ports: Vec<ConnectionPoint>,
+    ) -> Self {
+        Self {
+            id: ComponentId(0),
+            component_type,
+            position,
+            size,
+            ports,
+            orientation: Orientation::IDENTITY,
+            inverted_inputs: Vec::new(),
+            timing: Timing::INSTANT,
+            label: String::new(),
+        }
+    }
+    pub fn with_timing(self, timing: Timing) -> Self {
+        Self { timing, ..self }
+    }
+    pub fn with_orientation(mut self, orientation: Orientation) -> Self {
+        self.set_orientation(orientation);
+        self
+    }
+    /// Changes the type of the component, e.g. the delay of a repeater. The component has to be rebuilt afterwards.
+    pub fn with_component_type(self, component_type: ComponentType) -> Self {
+        Self {
+            component_type,
+            ..self
+        }
+    }
+    pub fn with_inverted_inputs(self, inverted_inputs: Vec<bool>) -> Self {
+        Self {
+            inverted_inputs,
+            ..self
+        }
+    }
+    pub fn with_label(self, label: String) -> Self {
+        Self { label, ..self }
+    }
+    /// Takes the size, ports and timing of the component recreated from the component library, keeping the id
+    pub fn rebuilt_as(self, rebuilt: Self) -> Self {
+        Self {
+            id: self.id,
+            ..rebuilt
+        }
+    }
+    pub const fn get_id(&self) -> ComponentId {
+        self.id
+    }
+    pub const fn get_component_type(&self) -> ComponentType {
+        self.component_type
+    }
+    pub fn get_label(&self) -> &str {
+        &self.label
+    }
+    pub const fn get_position(&self) -> (f64, f64) {
+        self.position
+    }
+    pub fn set_position(&mut self, position: (f64, f64)) {
+        self.position = position;
+    }
+    pub const fn get_orientation(&self) -> Orientation {
+        self.orientation
+    }
+    /// Turns the component into the orientation, keeping the top left corner of its bounding box in place
+    pub fn set_orientation(&mut self, orientation: Orientation) {
+        // back to the default orientation first
+        let inverse = self.orientation.get_inverse();
+        let size = inverse.transform_size(self.size);
+        for port in &mut self.ports {
+            *port = port
+                .oriented(inverse, self.size)
+                .oriented(orientation, size);
+        }
+        self.size = orientation.transform_size(size);
+        self.orientation = orientation;
+    }
+    pub const fn get_width(&self) -> f64 {
+        self.size.0
+    }
+    pub const fn get_height(&self) -> f64 {
+        self.size.1
+    }
+    pub fn get_ports(&self) -> &[ConnectionPoint] {
+        &self.ports
+    }
+    /// Returns for every port the port of the reconfigured component taking its place, `None` if there is none.
+    /// While the number of ports stays the same every port keeps its index, otherwise the n-th port of a kind becomes
+    /// the n-th port of that kind, e.g. the output of a gate stays its output when inputs are added or removed.
+    pub fn map_ports_to(&self, other: &Self) -> Vec<Option<usize>> {
+        if self.ports.len() == other.ports.len() {
+            return (0..self.ports.len()).map(Some).collect();
+        }
+        self.ports
+            .iter()
+            .enumerate()
+            .map(|(index, port)| {
+                let kind = port.get_kind();
+                let nth = self.ports[..index]
+                    .iter()
+                    .filter(|p| p.get_kind() == kind)
+                    .count();
+                other
+                    .ports
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, p)| p.get_kind() == kind)
+                    .nth(nth)
+                    .map(|(other, _)| other)
+            })
+            .collect()
+    }
+    /// Returns whether the n-th input port is inverted
+    pub fn is_input_inverted(&self, input: usize) -> bool {
+        self.inverted_inputs.get(input).copied().unwrap_or(false)
+    }
+    pub fn get_inverted_inputs(&self) -> &[bool] {
+        &self.inverted_inputs
+    }
+    pub const fn get_timing(&self) -> Timing {
+        self.timing
+    }
+    /// Returns whether the point lies within the bounds of the component
+    pub fn contains(&self, point: (f64, f64)) -> bool {
+        point.0 >= self.position.0
+            && point.0 <= self.position.0 + self.size.0
+            && point.1 >= self.position.1
+            && point.1 <= self.position.1 + self.size.1
+    }
+    /// Returns the port with the given index in absolute coordinates
+    pub fn get_absolute_port(&self, port: usize) -> Option<ConnectionPoint> {
+        self.ports
+            .get(port)
+            .map(|p| p.get_absolute_at_position(self.position))
+    }
+    /// Returns all ports in absolute coordinates
+    pub fn get_absolute_ports(&self) -> impl Iterator<Item = ConnectionPoint> + '_ {
+        self.ports
+            .iter()
+            .map(|p| p.get_absolute_at_position(self.position))
+    }
+}
+
+/// An explicit connection between two ports
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wire {
+    id: WireId,
+    from: PortRef,
+    to: PortRef,
+    /// The corners of the wire between the two ports, empty for a straight wire
+    path: Vec<(f64, f64)>,
+}
+
+impl Wire {
+    pub const fn get_id(&self) -> WireId {
+        self.id
+    }
+    pub const fn get_from(&self) -> PortRef {
+        self.from
+    }
+    pub const fn get_to(&self) -> PortRef {
+        self.to
+    }
+    pub fn get_path(&self) -> &[(f64, f64)] {
+        &self.path
+    }
+    pub fn with_path(self, path: Vec<(f64, f64)>) -> Self {
+        Self { path, ..self }
+    }
+    pub fn with_ports(self, from: PortRef, to: PortRef) -> Self {
+        Self { from, to, ..self }
+    }
+}
+
+/// A set of ports that are electrically connected through wires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Net {
+    id: NetId,
+    ports: Vec<PortRef>,
+    wires: Vec<WireId>,
+}
+
+impl Net {
+    pub const fn get_id(&self) -> NetId {
+        self.id
+    }
+    pub fn get_ports(&self) -> &[PortRef] {
+        &self.ports
+    }
+    pub fn get_wires(&self) -> &[WireId] {
+        &self.wires
+    }
+}
+
+/// The netlist of a design: placed components, the wires between their ports and the nets formed by them,
+/// together with the text annotations drawn over it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Circuit {
+    components: Vec<Component>,
+    wires: Vec<Wire>,
+    nets: Vec<Net>,
+    annotations: Vec<Annotation>,
+    next_component_id: u32,
+    next_wire_id: u32,
+    next_annotation_id: u32,
+}
+
+impl Circuit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the component to the circuit and returns its newly assigned id
+    pub fn add_component(&mut self, mut component: Component) -> ComponentId {
+        let id = ComponentId(self.next_component_id);
+        self.next_component_id += 1;
+        component.id = id;
+        self.components.push(component);
+        id
+    }
+
+    /// Removes the component and every wire attached to it
+    pub fn remove_component(&mut self, id: ComponentId) -> Option<Component> {
+        let index = self.components.iter().position(|c| c.id == id)?;
+        let component = self.components.remove(index);
+        let wire_count = self.wires.len();
+        self.wires
+            .retain(|w| w.from.component != id && w.to.component != id);
+        if wire_count != self.wires.len() {
+            self.rebuild_nets();
+        }
+        Some(component)
+    }
+
+    /// Puts a component back under the id it was removed with, e.g. when an edit is undone
+    pub fn restore_component(&mut self, component: Component) {
+        self.components.retain(|c| c.id != component.id);
+        self.next_component_id = self.next_component_id.max(component.id.0 + 1);
+        self.components.push(component);
+    }
+    /// Replaces the component with the same id and returns the replaced one
+    pub fn replace_component(&mut self, component: Component) -> Option<Component> {
+        let existing = self.get_component_mut(component.id)?;
+        Some(std::mem::replace(existing, component))
+    }
+
+    pub fn get_component(&self, id: ComponentId) -> Option<&Component> {
+        self.components.iter().find(|c| c.id == id)
+    }
+    pub fn get_component_mut(&mut self, id: ComponentId) -> Option<&mut Component> {
+        self.components.iter_mut().find(|c| c.id == id)
+    }
+    pub fn get_components(&self) -> &[Component] {
+        &self.components
+    }
+    /// Returns the top left and bottom right corner of the box around every component
+    pub fn get_bounds(&self) -> Option<((f64, f64), (f64, f64))> {
+        self.components
+            .iter()
+            .map(|c| {
+                let (x, y) = c.position;
+                ((x, y), (x + c.size.0, y + c.size.1))
+            })
+            .reduce(|(min, max), (a, b)| {
+                (
+                    (min.0.min(a.0), min.1.min(a.1)),
+                    (max.0.max(b.0), max.1.max(b.1)),
+                )
+            })
+    }
+
+    /// Returns the absolute position of a port
+    pub fn get_port(&self, port: PortRef) -> Option<ConnectionPoint> {
+        self.get_component(port.component)?
+            .get_absolute_port(port.port)
+    }
+
+    /// Connects two ports with a straight wire. Returns `None` if one of the ports does not exist.
+    pub fn add_wire(&mut self, from: PortRef, to: PortRef) -> Option<WireId> {
+        self.add_routed_wire(from, to, Vec::new())
+    }
+    /// Connects two ports with a wire running through the given corners
+    pub fn add_routed_wire(
+        &mut self,
+        from: PortRef,
+        to: PortRef,
+        path: Vec<(f64, f64)>,
+    ) -> Option<WireId> {
+        self.get_port(from)?;
+        self.get_port(to)?;
+        let id = WireId(self.next_wire_id);
+        self.next_wire_id += 1;
+        self.wires.push(Wire { id, from, to, path });
+        self.rebuild_nets();
+        Some(id)
+    }
+    /// Replaces the wire with the same id and returns the replaced one
+    pub fn replace_wire(&mut self, wire: Wire) -> Option<Wire> {
+        let existing = self.wires.iter_mut().find(|w| w.id == wire.id)?;
+        let replaced = std::mem::replace(existing, wire);
+        self.rebuild_nets();
+        Some(replaced)
+    }
+
+    /// Returns the points the wire runs through, from the first port over its corners to the second port
+    pub fn get_wire_points(&self, id: WireId) -> Option<Vec<(f64, f64)>> {
+        let wire = self.get_wire(id)?;
+        let from = self.get_port(wire.from)?;
+        let to = self.get_port(wire.to)?;
+        let mut points = vec![(from.get_position_x(), from.get_position_y())];
+        points.extend_from_slice(&wire.path);
+        points.push((to.get_position_x(), to.get_position_y()));
+        Some(points)
+    }
+
+    /// Returns the length of the wire in circuit coordinates
+    pub fn get_wire_length(&self, id: WireId) -> Option<f64> {
+        let points = self.get_wire_points(id)?;
+        Some(
+            points
+                .windows(2)
+                .map(|p| (p[0].0 - p[1].0).abs() + (p[0].1 - p[1].1).abs())
+                .sum(),
+        )
+    }
+
+    /// Puts a wire back under the id it was removed with. Returns `None` if one of its ports does not exist.
+    pub fn restore_wire(&mut self, wire: Wire) -> Option<WireId> {
+        self.get_port(wire.from)?;
+        self.get_port(wire.to)?;
+        let id = wire.id;
+        self.wires.retain(|w| w.id != id);
+        self.next_wire_id = self.next_wire_id.max(id.0 + 1);
+        self.wires.push(wire);
+        self.rebuild_nets();
+        Some(id)
+    }
+
+    pub fn remove_wire(&mut self, id: WireId) -> Option<Wire> {
+        let index = self.wires.iter().position(|w| w.id == id)?;
+        let wire = self.wires.remove(index);
+        self.rebuild_nets();
+        Some(wire)
+    }
+
+    pub fn get_wire(&self, id: WireId) -> Option<&Wire> {
+        self.wires.iter().find(|w| w.id == id)
+    }
+    pub fn get_wires(&self) -> &[Wire] {
+        &self.wires
+    }
+    /// Returns the number of bits the wire carries, the width of the port it starts at
+    pub fn get_wire_width(&self, id: WireId) -> Option<u8> {
+        let wire = self.get_wire(id)?;
+        self.get_port(wire.from).map(|p| p.get_width())
+    }
+    /// Returns the wires connecting ports of different widths, e.g. a bus to a single bit
+    pub fn get_width_mismatches(&self) -> Vec<WireId> {
+        self.wires
+            .iter()
+            .filter(|w| {
+                let width = |port| self.get_port(port).map(|p| p.get_width());
+                width(w.from) != width(w.to)
+            })
+            .map(|w| w.id)
+            .collect()
+    }
+
+    pub fn get_nets(&self) -> &[Net] {
+        &self.nets
+    }
+
+    /// Adds the annotation to the circuit and returns its newly assigned id
+    pub fn add_annotation(&mut self, mut annotation: Annotation) -> AnnotationId {
+        let id = AnnotationId(self.next_annotation_id);
+        self.next_annotation_id += 1;
+        annotation.id = id;
+        self.annotations.push(annotation);
+        id
+    }
+    /// Puts an annotation back under the id it was removed with, e.g. when an edit is undone
+    pub fn restore_annotation(&mut self, annotation: Annotation) {
+        self.annotations.retain(|a| a.id != annotation.id);
+        self.next_annotation_id = self.next_annotation_id.max(annotation.id.0 + 1);
+        self.annotations.push(annotation);
+    }
+    /// Replaces the annotation with the same id and returns the replaced one
+    pub fn replace_annotation(&mut self, annotation: Annotation) -> Option<Annotation> {
+        let existing = self.get_annotation_mut(annotation.id)?;
+        Some(std::mem::replace(existing, annotation))
+    }
+    pub fn remove_annotation(&mut self, id: AnnotationId) -> Option<Annotation> {
+        let index = self.annotations.iter().position(|a| a.id == id)?;
+        Some(self.annotations.remove(index))
+    }
+    pub fn get_annotation(&self, id: AnnotationId) -> Option<&Annotation> {
+        self.annotations.iter().find(|a| a.id == id)
+    }
+    pub fn get_annotation_mut(&mut self, id: AnnotationId) -> Option<&mut Annotation> {
+        self.annotations.iter_mut().find(|a| a.id == id)
+    }
+    pub fn get_annotations(&self) -> &[Annotation] {
+        &self.annotations
+    }
+    /// Returns the point annotations attached to the anchor are placed relative to: the position of the component
+    /// or the middle of the first segment of the wire
+    pub fn get_anchor_position(&self, anchor: AnnotationAnchor) -> Option<(f64, f64)> {
+        match anchor {
+            AnnotationAnchor::Component(id) => self.get_component(id).map(|c| c.position),
+            AnnotationAnchor::Wire(id) => {
+                let points = self.get_wire_points(id)?;
+                Some((
+                    (points[0].0 + points[1].0) / 2.0,
+                    (points[0].1 + points[1].1) / 2.0,
+                ))
+            }
+        }
+    }
+    /// Returns the absolute position of the annotation, `None` if what it is attached to no longer exists
+    pub fn get_annotation_position(&self, annotation: &Annotation) -> Option<(f64, f64)> {
+        let (x, y) = annotation.get_position();
+        match annotation.get_anchor() {
+            Some(anchor) => {
+                let (ax, ay) = self.get_anchor_position(anchor)?;
+                Some((ax + x, ay + y))
+            }
+            None => Some((x, y)),
+        }
+    }
+    /// Returns the net the port is part of, if it is connected at all
+    pub fn get_net_of(&self, port: PortRef) -> Option<&Net> {
+        self.nets.iter().find(|n| n.ports.contains(&port))
+    }
+
+    /// Copies the components, the wires running between them and the given annotations into a new circuit,
+    /// moved so the top left corner of the components is at the origin.
+    /// Annotations attached to something that is not copied are left out.
+    pub fn get_fragment(&self, ids: &[ComponentId], annotations: &[AnnotationId]) -> Self {
+        let origin = ids
+            .iter()
+            .filter_map(|id| self.get_component(*id))
+            .map(Component::get_position)
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1)))
+            .unwrap_or_default();
+        let mut fragment = Self::new();
+        fragment.add_copies(self, ids, annotations, (-origin.0, -origin.1));
+        fragment
+    }
+    /// Adds the components, wires and annotations of a fragment moved by the offset.
+    /// Returns the ids assigned to the added components, wires and annotations.
+    pub fn insert_fragment(
+        &mut self,
+        fragment: &Self,
+        offset: (f64, f64),
+    ) -> (Vec<ComponentId>, Vec<WireId>, Vec<AnnotationId>) {
+        let ids: Vec<_> = fragment.components.iter().map(|c| c.id).collect();
+        let annotations: Vec<_> = fragment.annotations.iter().map(|a| a.id).collect();
+        self.add_copies(fragment, &ids, &annotations, offset)
+    }
+    /// Returns the annotations attached to the components or to the wires running between them
+    pub fn get_attached_annotations(&self, ids: &[ComponentId]) -> Vec<AnnotationId> {
+        self.annotations
+            .iter()
+            .filter(|a| match a.get_anchor() {
+                Some(AnnotationAnchor::Component(id)) => ids.contains(&id),
+                Some(AnnotationAnchor::Wire(id)) => self.get_wire(id).is_some_and(|w| {
+                    ids.contains(&w.from.component) && ids.contains(&w.to.component)
+                }),
+                None => false,
+            })
+            .map(|a| a.id)
+            .collect()
+    }
+
+    /// Replaces a component by the content of a subcircuit placed at its position.
+    /// The pins of the content are left out, the wires of the n-th port of the component and the ports that were
+    /// connected to the n-th pin are joined instead. Pins wired only to each other join the outer wires directly.
+    pub fn inline(&mut self, id: ComponentId, content: &Self, pins: &[ComponentId]) {
+        let attached: Vec<_> = self
+            .wires
+            .iter()
+            .filter(|w| w.from.component == id || w.to.component == id)
+            .cloned()
+            .collect();
+        let Some(component) = self.remove_component(id) else {
+            return;
+        };
+        let ids: Vec<_> = content
+            .components
+            .iter()
+            .map(|c| c.id)
+            .filter(|id| !pins.contains(id))
+            .collect();
+        let (copies, ..) = self.add_copies(content, &ids, &[], component.position);
+        let copies: HashMap<_, _> = ids.into_iter().zip(copies).collect();
+
+        // pins connected to each other inside form one group, their outer nets are joined
+        let groups: Vec<usize> = pins
+            .iter()
+            .enumerate()
+            .map(|(index, pin)| {
+                let net = content.get_net_of(PortRef::new(*pin, 0)).map(|n| n.id);
+                pins.iter()
+                    .position(|p| {
+                        net.is_some()
+                            && content.get_net_of(PortRef::new(*p, 0)).map(|n| n.id) == net
+                    })
+                    .unwrap_or(index)
+            })
+            .collect();
+        // the copied ports each pin was connected to
+        let joined: Vec<Vec<PortRef>> = pins
+            .iter()
+            .map(|pin| {
+                content
+                    .get_net_of(PortRef::new(*pin, 0))
+                    .map(|net| {
+                        net.ports
+                            .iter()
+                            .filter_map(|p| {
+                                copies.get(&p.component).map(|c| PortRef::new(*c, p.port))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            })
+            .collect();
+        for ports in &joined {
+            for port in ports.iter().skip(1) {
+                self.add_wire(ports[0], *port);
+            }
+        }
+        // the outer ports connected to each group, they are joined directly if nothing is inside
+        let mut outer: HashMap<usize, Vec<PortRef>> = HashMap::new();
+        for wire in &attached {
+            for (port, other) in [(wire.from, wire.to), (wire.to, wire.from)] {
+                if port.component == id && other.component != id {
+                    if let Some(group) = groups.get(port.port) {
+                        outer.entry(*group).or_default().push(other);
+                    }
+                }
+            }
+        }
+        // the wires keep their ids, so they can still be found in the flattened circuit
+        for mut wire in attached {
+            let reconnect = |port: PortRef, other: PortRef| {
+                if port.component != id {
+                    return Some(port);
+                }
+                let group = *groups.get(port.port)?;
+                joined[group]
+                    .first()
+                    .copied()
+                    .or_else(|| outer.get(&group)?.iter().find(|p| **p != other).copied())
+            };
+            let (Some(from), Some(to)) =
+                (reconnect(wire.from, wire.to), reconnect(wire.to, wire.from))
+            else {
+                continue;
+            };
+            wire.from = from;
+            wire.to = to;
+            self.restore_wire(wire);
+        }
+    }
+
+    /// Adds moved copies of the components of another circuit, of the wires between them and of the annotations
+    /// that are free or attached to one of the copies
+    fn add_copies(
+        &mut self,
+        source: &Self,
+        ids: &[ComponentId],
+        annotations: &[AnnotationId],
+        offset: (f64, f64),
+    ) -> (Vec<ComponentId>, Vec<WireId>, Vec<AnnotationId>) {
+        let mut copies = HashMap::new();
+        for component in source.components.iter().filter(|c| ids.contains(&c.id)) {
+            let mut copy = component.clone();
+            copy.position = (copy.position.0 + offset.0, copy.position.1 + offset.1);
+            copies.insert(component.id, self.add_component(copy));
+        }
+        let mut wires = HashMap::new();
+        for wire in &source.wires {
+            let (Some(from), Some(to)) = (
+                copies.get(&wire.from.component),
+                copies.get(&wire.to.component),
+            ) else {
+                continue;
+            };
+            let path = wire
+                .path
+                .iter()
+                .map(|(x, y)| (x + offset.0, y + offset.1))
+                .collect();
+            if let Some(copy) = self.add_routed_wire(
+                PortRef::new(*from, wire.from.port),
+                PortRef::new(*to, wire.to.port),
+                path,
+            ) {
+                wires.insert(wire.id, copy);
+            }
+        }
+        let mut annotation_copies = Vec::new();
+        for annotation in source
+            .annotations
+            .iter()
+            .filter(|a| annotations.contains(&a.id))
+        {
+            // attached annotations are placed relative to their anchor
+            let (anchor, position) = match annotation.get_anchor() {
+                Some(AnnotationAnchor::Component(id)) => match copies.get(&id) {
+                    Some(copy) => (
+                        Some(AnnotationAnchor::Component(*copy)),
+                        annotation.get_position(),
+                    ),
+                    None => continue,
+                },
+                Some(AnnotationAnchor::Wire(id)) => match wires.get(&id) {
+                    Some(copy) => (
+                        Some(AnnotationAnchor::Wire(*copy)),
+                        annotation.get_position(),
+                    ),
+                    None => continue,
+                },
+                None => {
+                    let (x, y) = annotation.get_position();
+                    (None, (x + offset.0, y + offset.1))
+                }
+            };
+            let mut copy = annotation.clone().with_anchor(anchor);
+            copy.set_position(position);
+            annotation_copies.push(self.add_annotation(copy));
+        }
+        let components = ids
+            .iter()
+            .filter_map(|id| copies.get(id))
+            .copied()
+            .collect();
+        let wires = source
+            .wires
+            .iter()
+            .filter_map(|w| wires.get(&w.id))
+            .copied()
+            .collect();
+        (components, wires, annotation_copies)
+    }
+
+    /// Recomputes the nets as the connected sets of ports formed by the wires
+    fn rebuild_nets(&mut self) {
+        let mut parent: HashMap<PortRef, PortRef> = HashMap::new();
+        fn find(parent: &mut HashMap<PortRef, PortRef>, port: PortRef) -> PortRef {
+            let p = *parent.entry(port).or_insert(port);
+            if p == port {
+                return port;
+            }
+            let root = find(parent, p);
+            parent.insert(port, root);
+            root
+        }
+        for wire in &self.wires {
+            let a = find(&mut parent, wire.from);
+            let b = find(&mut parent, wire.to);
+            if a != b {
+                parent.insert(a, b);
+            }
+        }
+
+        let mut roots: Vec<PortRef> = Vec::new();
+        let mut nets: Vec<Net> = Vec::new();
+        let mut ports: Vec<PortRef> = parent.keys().copied().collect();
+        ports.sort();
+        for port in ports {
+            let root = find(&mut parent, port);
+            let index = roots.iter().position(|r| *r == root).unwrap_or_else(|| {
+                roots.push(root);
+                nets.push(Net {
+                    id: NetId(0),
+                    ports: Vec::new(),
+                    wires: Vec::new(),
+                });
+                nets.len() - 1
+            });
+            nets[index].ports.push(port);
+        }
+        for wire in &self.wires {
+            let root = find(&mut parent, wire.from);
+            if let Some(index) = roots.iter().position(|r| *r == root) {
+                nets[index].wires.push(wire.id);
+            }
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        for (index, net) in nets.iter_mut().enumerate() {
+            net.id = NetId(index as u32);
+        }
+        self.nets = nets;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{ConnectionKind, PinDirection, Subcircuit, SubcircuitLibrary};
+
+    fn component(
+        component_type: ComponentType,
+        position: (f64, f64),
+        ports: &[ConnectionKind],
+    ) -> Component {
+        #[allow(clippy::cast_precision_loss)]
+        let ports = ports
+            .iter()
+            .enumerate()
+            .map(|(index, kind)| ConnectionPoint::new(0.0, index as f64 * 25.0, [true; 4], *kind))
+            .collect();
+        Component::new(component_type, position, (25.0, 50.0), ports)
+    }
+    fn pin(direction: PinDirection, y: f64) -> Component {
+        let kind = match direction {
+            PinDirection::Input => ConnectionKind::Output,
+            _ => ConnectionKind::Input,
+        };
+        component(ComponentType::Pin(direction, 1), (0.0, y), &[kind])
+    }
+    fn lever_at(x: f64) -> Component {
+        component(ComponentType::Lever, (x, 0.0), &[ConnectionKind::Output])
+    }
+    fn lamp_at(x: f64) -> Component {
+        component(ComponentType::Lamp, (x, 0.0), &[ConnectionKind::Input])
+    }
+
+    #[test]
+    fn removed_components_take_their_wires_and_come_back_under_their_id() {
+        let mut circuit = Circuit::new();
+        let lever = circuit.add_component(lever_at(0.0));
+        let lamp = circuit.add_component(lamp_at(100.0));
+        let wire = circuit
+            .add_wire(PortRef::new(lever, 0), PortRef::new(lamp, 0))
+            .unwrap();
+        let removed_wire = circuit.get_wire(wire).unwrap().clone();
+
+        let removed = circuit.remove_component(lamp).unwrap();
+        assert!(circuit.get_wire(wire).is_none());
+        assert!(circuit.get_nets().is_empty());
+        assert!(circuit.restore_wire(removed_wire.clone()).is_none());
+
+        circuit.restore_component(removed);
+        assert_eq!(circuit.restore_wire(removed_wire), Some(wire));
+        assert_eq!(circuit.get_component(lamp).unwrap().position, (100.0, 0.0));
+        assert_eq!(circuit.get_nets().len(), 1);
+
+        // restored ids are never handed out again
+        let other = circuit.add_component(lamp_at(200.0));
+        assert_ne!(other, lamp);
+        let other_wire = circuit
+            .add_wire(PortRef::new(lever, 0), PortRef::new(other, 0))
+            .unwrap();
+        assert_ne!(other_wire, wire);
+    }
+
+    #[test]
+    fn nets_are_the_ports_connected_through_wires() {
+        let mut circuit = Circuit::new();
+        let lever = circuit.add_component(lever_at(0.0));
+        let lamps: Vec<_> = (1..=3)
+            .map(|i| circuit.add_component(lamp_at(f64::from(i) * 100.0)))
+            .collect();
+        let other = circuit.add_component(lever_at(0.0));
+        let last = circuit.add_component(lamp_at(400.0));
+        let first = circuit
+            .add_wire(PortRef::new(lever, 0), PortRef::new(lamps[0], 0))
+            .unwrap();
+        circuit.add_wire(PortRef::new(lamps[1], 0), PortRef::new(lamps[2], 0));
+        circuit.add_wire(PortRef::new(lever, 0), PortRef::new(lamps[2], 0));
+        circuit.add_wire(PortRef::new(other, 0), PortRef::new(last, 0));
+
+        assert_eq!(circuit.get_nets().len(), 2);
+        let net = circuit.get_net_of(PortRef::new(lamps[1], 0)).unwrap();
+        assert_eq!(net.get_ports().len(), 4);
+        assert_eq!(net.get_wires().len(), 3);
+        assert!(net.get_ports().contains(&PortRef::new(lamps[0], 0)));
+        assert!(!net.get_ports().contains(&PortRef::new(last, 0)));
+
+        circuit.remove_wire(first);
+        assert_eq!(circuit.get_nets().len(), 2);
+        assert!(circuit.get_net_of(PortRef::new(lamps[0], 0)).is_none());
+        let net = circuit.get_net_of(PortRef::new(lever, 0)).unwrap();
+        assert_eq!(net.get_ports().len(), 3);
+    }
+
+    #[test]
+    fn inlining_joins_the_outer_wires_of_pins_wired_to_each_other() {
+        let mut content = Circuit::new();
+        let input = content.add_component(pin(PinDirection::Input, 0.0));
+        let output = content.add_component(pin(PinDirection::Output, 50.0));
+        content.add_wire(PortRef::new(input, 0), PortRef::new(output, 0));
+
+        let id =
+            SubcircuitLibrary::new().add(Subcircuit::new("Passthrough".into(), content.clone()));
+
+        let mut circuit = Circuit::new();
+        let lever = circuit.add_component(component(
+            ComponentType::Lever,
+            (0.0, 0.0),
+            &[ConnectionKind::Output],
+        ));
+        let lamp = circuit.add_component(component(
+            ComponentType::Lamp,
+            (200.0, 0.0),
+            &[ConnectionKind::Input],
+        ));
+        let instance = circuit.add_component(component(
+            ComponentType::Subcircuit(id),
+            (100.0, 0.0),
+            &[ConnectionKind::Input, ConnectionKind::Output],
+        ));
+        circuit.add_wire(PortRef::new(lever, 0), PortRef::new(instance, 0));
+        circuit.add_wire(PortRef::new(instance, 1), PortRef::new(lamp, 0));
+
+        circuit.inline(instance, &content, &[input, output]);
+        assert!(circuit.get_component(instance).is_none());
+        assert_eq!(circuit.get_components().len(), 2);
+        let net = circuit.get_net_of(PortRef::new(lever, 0)).unwrap();
+        assert!(net.get_ports().contains(&PortRef::new(lamp, 0)));
+        assert_eq!(circuit.get_wires().len(), 2);
+    }
+}
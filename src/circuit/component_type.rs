@@ -0,0 +1,312 @@
+use std::{fmt::Display, ops::RangeInclusive};
+
+use serde::{Deserialize, Serialize};
+
+use super::{ConnectionKind, SubcircuitId};
+
+/// Number of inputs a gate combining several inputs has by default
+pub const DEFAULT_GATE_INPUTS: usize = 2;
+/// Largest number of inputs of a gate
+pub const MAX_GATE_INPUTS: usize = 8;
+
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LogicGateType {
+    And,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+    Xnor,
+    Not,
+    Buffer,
+}
+
+impl LogicGateType {
+    /// Computes the output of the gate for the given (already inverted) inputs
+    pub fn evaluate(&self, inputs: &[bool]) -> bool {
+        use LogicGateType::{And, Buffer, Nand, Nor, Not, Or, Xnor, Xor};
+        match self {
+            And => inputs.iter().all(|i| *i),
+            Or | Buffer => inputs.iter().any(|i| *i),
+            Xor => inputs.iter().filter(|i| **i).count() % 2 == 1,
+            Xnor => inputs.iter().filter(|i| **i).count() % 2 == 0,
+            Nand => !inputs.iter().all(|i| *i),
+            Nor | Not => !inputs.iter().any(|i| *i),
+        }
+    }
+
+    /// Returns the numbers of inputs the gate can have
+    pub const fn get_input_range(&self) -> RangeInclusive<usize> {
+        match self {
+            Self::Not | Self::Buffer => 1..=1,
+            _ => DEFAULT_GATE_INPUTS..=MAX_GATE_INPUTS,
+        }
+    }
+    /// Returns whether the output of the gate is inverted, drawn as a circle
+    pub const fn has_inverted_output(&self) -> bool {
+        matches!(self, Self::Nand | Self::Nor | Self::Xnor | Self::Not)
+    }
+
+    /// Delay in redstone ticks of the torch based realisation of the gate
+    pub const fn get_delay(&self) -> u8 {
+        use LogicGateType::{And, Buffer, Nand, Nor, Not, Or, Xnor, Xor};
+        match self {
+            // the inputs are joined by dust
+            Or => 0,
+            // a single torch or repeater
+            Nor | Nand | Not | Buffer => 1,
+            // inverted inputs followed by a torch
+            And => 2,
+            Xor => 3,
+            // a torch behind the xor
+            Xnor => 4,
+        }
+    }
+}
+
+impl Display for LogicGateType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?} Gate")
+    }
+}
+
+/// Slowest delay of a repeater in redstone ticks
+pub const MAX_REPEATER_DELAY: u8 = 4;
+/// Shortest period of a clock in redstone ticks, faster clocks burn out their torch
+pub const MIN_CLOCK_PERIOD: u8 = 4;
+/// Longest period of a clock in redstone ticks
+pub const MAX_CLOCK_PERIOD: u8 = 60;
+/// Longest pulse of a monostable in redstone ticks
+pub const MAX_PULSE_LENGTH: u8 = 16;
+
+/// A latch or flip-flop storing a single bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SequentialType {
+    SrLatch,
+    DLatch,
+    DFlipFlop,
+    TFlipFlop,
+    JkFlipFlop,
+}
+
+impl SequentialType {
+    pub const ALL: [Self; 5] = [
+        Self::SrLatch,
+        Self::DLatch,
+        Self::DFlipFlop,
+        Self::TFlipFlop,
+        Self::JkFlipFlop,
+    ];
+
+    /// Returns the names of the inputs in the order of the ports, the clock is named `>`
+    pub const fn get_inputs(&self) -> &'static [&'static str] {
+        match self {
+            Self::SrLatch => &["S", "R"],
+            Self::DLatch => &["D", "E"],
+            Self::DFlipFlop => &["D", ">"],
+            Self::TFlipFlop => &[">"],
+            Self::JkFlipFlop => &["J", ">", "K"],
+        }
+    }
+    /// Returns the input whose rising edge stores the next value, `None` for latches
+    pub const fn get_clock_input(&self) -> Option<usize> {
+        match self {
+            Self::SrLatch | Self::DLatch => None,
+            Self::DFlipFlop | Self::JkFlipFlop => Some(1),
+            Self::TFlipFlop => Some(0),
+        }
+    }
+    /// Delay in redstone ticks of the realisation in the game
+    pub const fn get_delay(&self) -> u8 {
+        match self {
+            // a torch and the torch of the output
+            Self::SrLatch => 2,
+            // a locked repeater
+            Self::DLatch => 1,
+            // two locked repeaters in a row
+            Self::DFlipFlop | Self::TFlipFlop => 2,
+            // comparators and dust in front of a d flip-flop
+            Self::JkFlipFlop => 3,
+        }
+    }
+    /// Computes the stored value from the inputs, `rising` is set if the clock rose since the last evaluation.
+    /// Setting and resetting a latch at the same time keeps its value.
+    pub fn next_value(&self, stored: bool, inputs: &[bool], rising: bool) -> bool {
+        let input = |input: usize| inputs.get(input).copied().unwrap_or(false);
+        match self {
+            Self::SrLatch => match (input(0), input(1)) {
+                (true, false) => true,
+                (false, true) => false,
+                _ => stored,
+            },
+            Self::DLatch if input(1) => input(0),
+            Self::DFlipFlop if rising => input(0),
+            Self::TFlipFlop if rising => !stored,
+            Self::JkFlipFlop if rising => match (input(0), input(2)) {
+                (false, false) => stored,
+                (true, false) => true,
+                (false, true) => false,
+                (true, true) => !stored,
+            },
+            _ => stored,
+        }
+    }
+}
+
+impl Display for SequentialType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SrLatch => write!(f, "SR Latch"),
+            Self::DLatch => write!(f, "D Latch"),
+            Self::DFlipFlop => write!(f, "D Flip-Flop"),
+            Self::TFlipFlop => write!(f, "T Flip-Flop"),
+            Self::JkFlipFlop => write!(f, "JK Flip-Flop"),
+        }
+    }
+}
+
+/// Widest bus a pin can carry, in bits
+pub const MAX_BUS_WIDTH: u8 = 32;
+
+/// Whether a pin brings a signal into the circuit, takes one out of it or both
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PinDirection {
+    Input,
+    Output,
+    /// The pin drives its net unless something else in the circuit does
+    Bidirectional,
+}
+
+impl PinDirection {
+    pub const ALL: [Self; 3] = [Self::Input, Self::Output, Self::Bidirectional];
+
+    /// Returns the kind of the port of the pin, an input pin drives the circuit
+    pub const fn get_connection_kind(&self) -> ConnectionKind {
+        match self {
+            Self::Input => ConnectionKind::Output,
+            Self::Output => ConnectionKind::Input,
+            Self::Bidirectional => ConnectionKind::Bidirectional,
+        }
+    }
+}
+
+impl Display for PinDirection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Input => write!(f, "Input Pin"),
+            Self::Output => write!(f, "Output Pin"),
+            Self::Bidirectional => write!(f, "Pin"),
+        }
+    }
+}
+
+/// How a comparator combines the signals on its rear and its side
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComparatorMode {
+    /// The rear signal is passed on if it is at least as strong as the side signal
+    Compare,
+    /// The side signal is subtracted from the rear signal
+    Subtract,
+}
+
+impl ComparatorMode {
+    pub const ALL: [Self; 2] = [Self::Compare, Self::Subtract];
+
+    /// Computes the output strength for the signals on the rear and the side
+    pub const fn evaluate(&self, rear: u8, side: u8) -> u8 {
+        match self {
+            Self::Compare if rear >= side => rear,
+            Self::Compare => 0,
+            Self::Subtract => rear.saturating_sub(side),
+        }
+    }
+}
+
+/// The material of a button, which decides how long it stays pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ButtonType {
+    Stone,
+    Wood,
+}
+
+impl ButtonType {
+    pub const ALL: [Self; 2] = [Self::Stone, Self::Wood];
+
+    /// Duration of the pulse in redstone ticks
+    pub const fn get_pulse_length(&self) -> u8 {
+        match self {
+            Self::Stone => 10,
+            Self::Wood => 15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComponentType {
+    LogicGate(LogicGateType),
+    /// A named port of the circuit with its direction and width in bits
+    Pin(PinDirection, u8),
+    Torch,
+    /// A repeater with its delay in redstone ticks
+    Repeater(u8),
+    Comparator(ComparatorMode),
+    Lever,
+    Button(ButtonType),
+    PressurePlate,
+    Lamp,
+    Observer,
+    Piston,
+    StickyPiston,
+    NoteBlock,
+    Sequential(SequentialType),
+    /// A clock source with its period in redstone ticks, it is on for the first half of the period
+    Clock(u8),
+    /// Emits a pulse of the given number of redstone ticks on every rising edge of its input
+    Monostable(u8),
+    /// Fans a bus of the given width out into its bits
+    Splitter(u8),
+    /// Joins the given number of bits into a bus
+    Merger(u8),
+    /// An instance of a user-defined component
+    Subcircuit(SubcircuitId),
+}
+
+impl ComponentType {
+    /// Returns whether the user sets the signal of the component during simulation, e.g. by flipping a lever
+    pub const fn is_user_input(&self) -> bool {
+        matches!(
+            self,
+            Self::Pin(..) | Self::Lever | Self::Button(_) | Self::PressurePlate
+        )
+    }
+}
+
+impl Display for ComponentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LogicGate(gate_type) => write!(f, "{gate_type}"),
+            Self::Pin(direction, 1) => write!(f, "{direction}"),
+            Self::Pin(direction, width) => write!(f, "{direction} ({width} bits)"),
+            Self::Torch => write!(f, "Redstone Torch"),
+            Self::Repeater(delay) => write!(f, "Repeater ({delay})"),
+            Self::Comparator(ComparatorMode::Compare) => write!(f, "Comparator"),
+            Self::Comparator(ComparatorMode::Subtract) => write!(f, "Comparator (subtract)"),
+            Self::Lever => write!(f, "Lever"),
+            Self::Button(ButtonType::Stone) => write!(f, "Stone Button"),
+            Self::Button(ButtonType::Wood) => write!(f, "Wooden Button"),
+            Self::PressurePlate => write!(f, "Pressure Plate"),
+            Self::Lamp => write!(f, "Redstone Lamp"),
+            Self::Observer => write!(f, "Observer"),
+            Self::Piston => write!(f, "Piston"),
+            Self::StickyPiston => write!(f, "Sticky Piston"),
+            Self::NoteBlock => write!(f, "Note Block"),
+            Self::Sequential(sequential_type) => write!(f, "{sequential_type}"),
+            Self::Clock(period) => write!(f, "Clock ({period})"),
+            Self::Monostable(length) => write!(f, "Monostable ({length})"),
+            Self::Splitter(width) => write!(f, "Splitter ({width})"),
+            Self::Merger(width) => write!(f, "Merger ({width})"),
+            Self::Subcircuit(id) => write!(f, "Subcircuit {id}"),
+        }
+    }
+}
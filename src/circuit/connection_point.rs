@@ -0,0 +1,84 @@
+use super::Orientation;
+
+/// Whether a connection point reads or drives the net it is connected to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConnectionKind {
+    Input,
+    Output,
+    /// The direction is decided by the rest of the circuit, e.g. a pin driving or reading a net
+    Bidirectional,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct ConnectionPoint {
+    /// relative position to element origin
+    x: f64,
+    /// relative position to element origin
+    y: f64,
+    directions: [bool; 4],
+    kind: ConnectionKind,
+    /// The number of bits carried through the point, more than one for a bus
+    width: u8,
+}
+
+impl ConnectionPoint {
+    pub const fn new(x: f64, y: f64, directions: [bool; 4], kind: ConnectionKind) -> Self {
+        Self {
+            x,
+            y,
+            directions,
+            kind,
+            width: 1,
+        }
+    }
+    pub const fn with_width(self, width: u8) -> Self {
+        Self { width, ..self }
+    }
+    // pub fn get_position(&self) -> (f64, f64) {
+    //     (self.x, self.y)
+    // }
+    pub const fn get_position_x(&self) -> f64 {
+        self.x
+    }
+    pub const fn get_position_y(&self) -> f64 {
+        self.y
+    }
+    // pub fn get_directions(&self) -> &[bool; 4] {
+    //     &self.directions
+    // }
+    pub const fn get_direction_x_pos(&self) -> bool {
+        self.directions[1]
+    }
+    pub const fn get_direction_x_neg(&self) -> bool {
+        self.directions[3]
+    }
+    pub const fn get_direction_y_pos(&self) -> bool {
+        self.directions[2]
+    }
+    pub const fn get_direction_y_neg(&self) -> bool {
+        self.directions[0]
+    }
+    pub const fn get_kind(&self) -> ConnectionKind {
+        self.kind
+    }
+    pub const fn get_width(&self) -> u8 {
+        self.width
+    }
+    /// Returns the point after turning the bounding box of the given size it lies in
+    pub fn oriented(&self, orientation: Orientation, size: (f64, f64)) -> Self {
+        let (x, y) = orientation.transform_point((self.x, self.y), size);
+        Self {
+            x,
+            y,
+            directions: orientation.transform_directions(self.directions),
+            ..*self
+        }
+    }
+    pub fn get_absolute_at_position(&self, position: (f64, f64)) -> Self {
+        Self {
+            x: self.x + position.0,
+            y: self.y + position.1,
+            ..*self
+        }
+    }
+}
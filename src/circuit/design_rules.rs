@@ -0,0 +1,634 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Display,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Circuit, ComponentId, ComponentType, ConnectionKind, PortRef, WireId};
+
+/// How serious a problem found by a design rule is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "Error"),
+            Self::Warning => write!(f, "Warning"),
+        }
+    }
+}
+
+/// A check of the design that reports problems before the design is built in the game
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum DesignRule {
+    /// An input that nothing drives
+    FloatingInput,
+    /// Several outputs driving the same net
+    ShortedOutputs,
+    /// A pin that is not connected to the circuit
+    UnconnectedPin,
+    /// A wire between ports of different widths
+    WidthMismatch,
+    /// Components feeding back into themselves without a latch or flip-flop in between
+    CombinationalLoop,
+    /// Dust carrying a signal further than its strength reaches without a repeater
+    LongDustRun,
+    /// A torch toggling so fast that the game burns it out
+    TorchBurnout,
+    /// A piston the game also switches through the block above it
+    QuasiConnectivity,
+    /// A comparator comparing signals that lost different amounts of strength on the way
+    WeakSignal,
+}
+
+impl DesignRule {
+    pub const ALL: [Self; 9] = [
+        Self::FloatingInput,
+        Self::ShortedOutputs,
+        Self::UnconnectedPin,
+        Self::WidthMismatch,
+        Self::CombinationalLoop,
+        Self::LongDustRun,
+        Self::TorchBurnout,
+        Self::QuasiConnectivity,
+        Self::WeakSignal,
+    ];
+
+    /// Returns the severity of the rule's problems unless the project configures it differently
+    pub const fn get_default_severity(&self) -> Severity {
+        match self {
+            Self::ShortedOutputs | Self::WidthMismatch | Self::LongDustRun | Self::TorchBurnout => {
+                Severity::Error
+            }
+            Self::FloatingInput
+            | Self::UnconnectedPin
+            | Self::CombinationalLoop
+            | Self::QuasiConnectivity
+            | Self::WeakSignal => Severity::Warning,
+        }
+    }
+    /// Returns what the rule checks
+    pub const fn get_scope(&self) -> RuleScope {
+        match self {
+            Self::FloatingInput
+            | Self::ShortedOutputs
+            | Self::UnconnectedPin
+            | Self::WidthMismatch
+            | Self::CombinationalLoop => RuleScope::Circuit,
+            Self::LongDustRun | Self::TorchBurnout | Self::QuasiConnectivity | Self::WeakSignal => {
+                RuleScope::Layout
+            }
+        }
+    }
+}
+
+impl Display for DesignRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FloatingInput => write!(f, "Floating inputs"),
+            Self::ShortedOutputs => write!(f, "Shorted outputs"),
+            Self::UnconnectedPin => write!(f, "Unconnected pins"),
+            Self::WidthMismatch => write!(f, "Width mismatches"),
+            Self::CombinationalLoop => write!(f, "Combinational loops"),
+            Self::LongDustRun => write!(f, "Long dust runs"),
+            Self::TorchBurnout => write!(f, "Torch burnout"),
+            Self::QuasiConnectivity => write!(f, "Quasi-connectivity"),
+            Self::WeakSignal => write!(f, "Weak comparator signals"),
+        }
+    }
+}
+
+/// What a design rule checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuleScope {
+    /// The components and wires of the circuit
+    Circuit,
+    /// The redstone blocks the circuit is exported as
+    Layout,
+}
+
+impl RuleScope {
+    pub const ALL: [Self; 2] = [Self::Circuit, Self::Layout];
+}
+
+impl Display for RuleScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Circuit => write!(f, "Circuit"),
+            Self::Layout => write!(f, "Redstone"),
+        }
+    }
+}
+
+/// Which design rules a project checks and how serious their problems are.
+/// Rules that are not configured are checked with their default severity.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RuleSettings(BTreeMap<DesignRule, Option<Severity>>);
+
+impl RuleSettings {
+    /// Returns the severity of the rule's problems, `None` if the rule is turned off
+    pub fn get_severity(&self, rule: DesignRule) -> Option<Severity> {
+        self.0
+            .get(&rule)
+            .copied()
+            .unwrap_or(Some(rule.get_default_severity()))
+    }
+    /// Sets the severity of the rule's problems, `None` turns the rule off
+    pub fn set_severity(&mut self, rule: DesignRule, severity: Option<Severity>) {
+        if severity == Some(rule.get_default_severity()) {
+            self.0.remove(&rule);
+        } else {
+            self.0.insert(rule, severity);
+        }
+    }
+}
+
+/// A violation of a design rule
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    rule: DesignRule,
+    severity: Severity,
+    message: String,
+    /// Where the problem is in design coordinates
+    location: (f64, f64),
+    /// The components causing the problem
+    components: Vec<ComponentId>,
+    /// The wires causing the problem
+    wires: Vec<WireId>,
+}
+
+impl Problem {
+    pub const fn new(
+        rule: DesignRule,
+        severity: Severity,
+        message: String,
+        location: (f64, f64),
+    ) -> Self {
+        Self {
+            rule,
+            severity,
+            message,
+            location,
+            components: Vec::new(),
+            wires: Vec::new(),
+        }
+    }
+    pub fn with_components(mut self, components: Vec<ComponentId>) -> Self {
+        self.components = components;
+        self
+    }
+    pub fn with_wires(mut self, wires: Vec<WireId>) -> Self {
+        self.wires = wires;
+        self
+    }
+
+    pub const fn get_rule(&self) -> DesignRule {
+        self.rule
+    }
+    pub const fn get_severity(&self) -> Severity {
+        self.severity
+    }
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+    pub const fn get_location(&self) -> (f64, f64) {
+        self.location
+    }
+    pub fn get_components(&self) -> &[ComponentId] {
+        &self.components
+    }
+    pub fn get_wires(&self) -> &[WireId] {
+        &self.wires
+    }
+}
+
+/// Collects the problems of the rules that are turned on
+struct Report<'a> {
+    settings: &'a RuleSettings,
+    problems: Vec<Problem>,
+}
+
+impl Report<'_> {
+    fn is_checked(&self, rule: DesignRule) -> bool {
+        self.settings.get_severity(rule).is_some()
+    }
+    fn add(
+        &mut self,
+        rule: DesignRule,
+        message: String,
+        location: (f64, f64),
+        components: Vec<ComponentId>,
+        wires: Vec<WireId>,
+    ) {
+        if let Some(severity) = self.settings.get_severity(rule) {
+            self.problems.push(
+                Problem::new(rule, severity, message, location)
+                    .with_components(components)
+                    .with_wires(wires),
+            );
+        }
+    }
+}
+
+/// Checks the placed components and their connections against the circuit rules that are turned on.
+/// The problems are sorted by severity, errors first.
+pub fn check_circuit(circuit: &Circuit, settings: &RuleSettings) -> Vec<Problem> {
+    let mut report = Report {
+        settings,
+        problems: Vec::new(),
+    };
+    check_floating_inputs(circuit, &mut report);
+    check_shorted_outputs(circuit, &mut report);
+    check_unconnected_pins(circuit, &mut report);
+    check_width_mismatches(circuit, &mut report);
+    check_combinational_loops(circuit, &mut report);
+    let mut problems = report.problems;
+    problems.sort_by_key(Problem::get_severity);
+    problems
+}
+
+/// Returns the kind of the port, `None` if it does not exist
+fn get_kind(circuit: &Circuit, port: PortRef) -> Option<ConnectionKind> {
+    circuit.get_port(port).map(|p| p.get_kind())
+}
+
+/// Returns the position of the port in design coordinates
+fn get_location(circuit: &Circuit, port: PortRef) -> (f64, f64) {
+    circuit
+        .get_component(port.component)
+        .and_then(|c| c.get_absolute_port(port.port))
+        .map_or((0.0, 0.0), |p| (p.get_position_x(), p.get_position_y()))
+}
+
+fn check_floating_inputs(circuit: &Circuit, report: &mut Report) {
+    if !report.is_checked(DesignRule::FloatingInput) {
+        return;
+    }
+    for component in circuit.get_components() {
+        // an output pin reads the circuit, it is reported if it is not connected at all
+        if matches!(component.get_component_type(), ComponentType::Pin(..)) {
+            continue;
+        }
+        for (index, port) in component.get_ports().iter().enumerate() {
+            if port.get_kind() != ConnectionKind::Input {
+                continue;
+            }
+            let port_ref = PortRef::new(component.get_id(), index);
+            let net = circuit.get_net_of(port_ref);
+            let driven = net.is_some_and(|net| {
+                net.get_ports()
+                    .iter()
+                    .any(|p| get_kind(circuit, *p).is_some_and(|k| k != ConnectionKind::Input))
+            });
+            if driven {
+                continue;
+            }
+            report.add(
+                DesignRule::FloatingInput,
+                format!(
+                    "Input {} of the {} is not driven",
+                    index + 1,
+                    component.get_component_type()
+                ),
+                get_location(circuit, port_ref),
+                vec![component.get_id()],
+                net.map(|n| n.get_wires().to_vec()).unwrap_or_default(),
+            );
+        }
+    }
+}
+
+fn check_shorted_outputs(circuit: &Circuit, report: &mut Report) {
+    if !report.is_checked(DesignRule::ShortedOutputs) {
+        return;
+    }
+    for net in circuit.get_nets() {
+        let outputs: Vec<_> = net
+            .get_ports()
+            .iter()
+            .filter(|p| get_kind(circuit, **p) == Some(ConnectionKind::Output))
+            .collect();
+        let [first, _, ..] = outputs.as_slice() else {
+            continue;
+        };
+        report.add(
+            DesignRule::ShortedOutputs,
+            format!("{} outputs drive the same net", outputs.len()),
+            get_location(circuit, **first),
+            outputs.iter().map(|p| p.component).collect(),
+            net.get_wires().to_vec(),
+        );
+    }
+}
+
+fn check_unconnected_pins(circuit: &Circuit, report: &mut Report) {
+    if !report.is_checked(DesignRule::UnconnectedPin) {
+        return;
+    }
+    for component in circuit.get_components() {
+        if !matches!(component.get_component_type(), ComponentType::Pin(..)) {
+            continue;
+        }
+        let port = PortRef::new(component.get_id(), 0);
+        if circuit.get_net_of(port).is_some() {
+            continue;
+        }
+        let message = if component.get_label().is_empty() {
+            format!("The {} is not connected", component.get_component_type())
+        } else {
+            format!("Pin {} is not connected", component.get_label())
+        };
+        report.add(
+            DesignRule::UnconnectedPin,
+            message,
+            get_location(circuit, port),
+            vec![component.get_id()],
+            Vec::new(),
+        );
+    }
+}
+
+fn check_width_mismatches(circuit: &Circuit, report: &mut Report) {
+    if !report.is_checked(DesignRule::WidthMismatch) {
+        return;
+    }
+    for id in circuit.get_width_mismatches() {
+        let Some(wire) = circuit.get_wire(id) else {
+            continue;
+        };
+        let width = |port| circuit.get_port(port).map_or(0, |p| p.get_width());
+        report.add(
+            DesignRule::WidthMismatch,
+            format!(
+                "A wire connects a port of {} bits to a port of {} bits",
+                width(wire.get_from()),
+                width(wire.get_to())
+            ),
+            get_location(circuit, wire.get_from()),
+            vec![wire.get_from().component, wire.get_to().component],
+            vec![id],
+        );
+    }
+}
+
+/// Returns whether the component stores its state, which breaks a loop through it
+const fn is_sequential(component_type: ComponentType) -> bool {
+    matches!(
+        component_type,
+        ComponentType::Sequential(_) | ComponentType::Clock(_) | ComponentType::Monostable(_)
+    )
+}
+
+fn check_combinational_loops(circuit: &Circuit, report: &mut Report) {
+    if !report.is_checked(DesignRule::CombinationalLoop) {
+        return;
+    }
+    // a component drives the components reading a net it drives
+    let mut successors: HashMap<ComponentId, BTreeSet<ComponentId>> = HashMap::new();
+    let mut loop_wires: HashMap<(ComponentId, ComponentId), Vec<WireId>> = HashMap::new();
+    let combinational = |port: &PortRef| {
+        circuit
+            .get_component(port.component)
+            .is_some_and(|c| !is_sequential(c.get_component_type()))
+    };
+    for net in circuit.get_nets() {
+        let ports: Vec<_> = net
+            .get_ports()
+            .iter()
+            .filter(|p| combinational(p))
+            .collect();
+        for driver in ports
+            .iter()
+            .filter(|p| get_kind(circuit, ***p) == Some(ConnectionKind::Output))
+        {
+            for reader in ports
+                .iter()
+                .filter(|p| get_kind(circuit, ***p) == Some(ConnectionKind::Input))
+            {
+                successors
+                    .entry(driver.component)
+                    .or_default()
+                    .insert(reader.component);
+                loop_wires
+                    .entry((driver.component, reader.component))
+                    .or_default()
+                    .extend_from_slice(net.get_wires());
+            }
+        }
+    }
+    let reachable = |start: ComponentId| {
+        let mut reached = BTreeSet::new();
+        let mut stack = vec![start];
+        while let Some(component) = stack.pop() {
+            for next in successors.get(&component).into_iter().flatten() {
+                if reached.insert(*next) {
+                    stack.push(*next);
+                }
+            }
+        }
+        reached
+    };
+    let reached: BTreeMap<_, _> = successors.keys().map(|c| (*c, reachable(*c))).collect();
+    let mut reported = BTreeSet::new();
+    for (component, reaches) in &reached {
+        if reported.contains(component) || !reaches.contains(component) {
+            continue;
+        }
+        // the components on a loop through this one reach each other
+        let members: Vec<_> = reaches
+            .iter()
+            .filter(|other| reached.get(other).is_some_and(|r| r.contains(component)))
+            .copied()
+            .collect();
+        reported.extend(members.iter().copied());
+        let mut wires: Vec<_> = loop_wires
+            .iter()
+            .filter(|((from, to), _)| members.contains(from) && members.contains(to))
+            .flat_map(|(_, wires)| wires.iter().copied())
+            .collect();
+        wires.sort();
+        wires.dedup();
+        let location = circuit
+            .get_component(*component)
+            .map_or((0.0, 0.0), |c| c.get_position());
+        let message = if members.len() == 1 {
+            "A component feeds back into itself".to_owned()
+        } else {
+            format!(
+                "{} components form a loop without a latch or flip-flop",
+                members.len()
+            )
+        };
+        report.add(
+            DesignRule::CombinationalLoop,
+            message,
+            location,
+            members,
+            wires,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{Component, ConnectionPoint, LogicGateType, PinDirection, SequentialType};
+
+    /// A component with ports of the given kinds
+    fn component(component_type: ComponentType, kinds: &[ConnectionKind]) -> Component {
+        let ports = kinds
+            .iter()
+            .map(|kind| ConnectionPoint::new(0.0, 0.0, [true; 4], *kind))
+            .collect();
+        Component::new(component_type, (0.0, 0.0), (25.0, 25.0), ports)
+    }
+    fn add(
+        circuit: &mut Circuit,
+        component_type: ComponentType,
+        kinds: &[ConnectionKind],
+    ) -> ComponentId {
+        circuit.add_component(component(component_type, kinds))
+    }
+    fn not(circuit: &mut Circuit) -> ComponentId {
+        add(
+            circuit,
+            ComponentType::LogicGate(LogicGateType::Not),
+            &[ConnectionKind::Input, ConnectionKind::Output],
+        )
+    }
+    fn lever(circuit: &mut Circuit) -> ComponentId {
+        add(circuit, ComponentType::Lever, &[ConnectionKind::Output])
+    }
+    fn connect(circuit: &mut Circuit, from: (ComponentId, usize), to: (ComponentId, usize)) {
+        circuit.add_wire(PortRef::new(from.0, from.1), PortRef::new(to.0, to.1));
+    }
+    fn check(circuit: &Circuit, rule: DesignRule) -> Vec<Problem> {
+        check_circuit(circuit, &RuleSettings::default())
+            .into_iter()
+            .filter(|p| p.get_rule() == rule)
+            .collect()
+    }
+
+    #[test]
+    fn inputs_nothing_drives_are_floating() {
+        let mut circuit = Circuit::new();
+        let floating = not(&mut circuit);
+        let driven = not(&mut circuit);
+        let lever = lever(&mut circuit);
+        connect(&mut circuit, (lever, 0), (driven, 0));
+
+        let problems = check(&circuit, DesignRule::FloatingInput);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].get_components(), [floating]);
+        assert_eq!(problems[0].get_severity(), Severity::Warning);
+    }
+
+    #[test]
+    fn outputs_driving_the_same_net_are_shorted() {
+        let mut circuit = Circuit::new();
+        let (a, b) = (lever(&mut circuit), lever(&mut circuit));
+        let lamp = add(&mut circuit, ComponentType::Lamp, &[ConnectionKind::Input]);
+        connect(&mut circuit, (a, 0), (lamp, 0));
+        connect(&mut circuit, (b, 0), (lamp, 0));
+
+        let problems = check(&circuit, DesignRule::ShortedOutputs);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].get_components(), [a, b]);
+        assert_eq!(problems[0].get_wires().len(), 2);
+    }
+
+    #[test]
+    fn pins_without_wires_are_unconnected() {
+        let mut circuit = Circuit::new();
+        circuit.add_component(
+            component(
+                ComponentType::Pin(PinDirection::Output, 1),
+                &[ConnectionKind::Input],
+            )
+            .with_label("sum".to_owned()),
+        );
+
+        let problems = check(&circuit, DesignRule::UnconnectedPin);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].get_message(), "Pin sum is not connected");
+        // the output pin is not reported as a floating input as well
+        assert!(check(&circuit, DesignRule::FloatingInput).is_empty());
+    }
+
+    #[test]
+    fn wires_between_ports_of_different_widths_are_mismatched() {
+        let mut circuit = Circuit::new();
+        let bus = circuit.add_component(Component::new(
+            ComponentType::Pin(PinDirection::Input, 4),
+            (0.0, 0.0),
+            (25.0, 25.0),
+            vec![ConnectionPoint::new(0.0, 0.0, [true; 4], ConnectionKind::Output).with_width(4)],
+        ));
+        let lamp = add(&mut circuit, ComponentType::Lamp, &[ConnectionKind::Input]);
+        connect(&mut circuit, (bus, 0), (lamp, 0));
+
+        let problems = check(&circuit, DesignRule::WidthMismatch);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].get_message(),
+            "A wire connects a port of 4 bits to a port of 1 bits"
+        );
+        assert_eq!(problems[0].get_severity(), Severity::Error);
+    }
+
+    #[test]
+    fn loops_through_gates_are_reported_but_not_through_flip_flops() {
+        let mut circuit = Circuit::new();
+        let (a, b) = (not(&mut circuit), not(&mut circuit));
+        connect(&mut circuit, (a, 1), (b, 0));
+        connect(&mut circuit, (b, 1), (a, 0));
+        let problems = check(&circuit, DesignRule::CombinationalLoop);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].get_components(), [a, b]);
+
+        let mut circuit = Circuit::new();
+        let flip_flop = add(
+            &mut circuit,
+            ComponentType::Sequential(SequentialType::DFlipFlop),
+            &[
+                ConnectionKind::Input,
+                ConnectionKind::Input,
+                ConnectionKind::Output,
+                ConnectionKind::Output,
+            ],
+        );
+        let inverter = not(&mut circuit);
+        connect(&mut circuit, (flip_flop, 2), (inverter, 0));
+        connect(&mut circuit, (inverter, 1), (flip_flop, 0));
+        assert!(check(&circuit, DesignRule::CombinationalLoop).is_empty());
+    }
+
+    #[test]
+    fn rules_turned_off_report_nothing_and_others_use_their_configured_severity() {
+        let mut circuit = Circuit::new();
+        not(&mut circuit);
+        add(
+            &mut circuit,
+            ComponentType::Pin(PinDirection::Input, 1),
+            &[ConnectionKind::Output],
+        );
+        let mut settings = RuleSettings::default();
+        settings.set_severity(DesignRule::FloatingInput, None);
+        settings.set_severity(DesignRule::UnconnectedPin, Some(Severity::Error));
+
+        let problems = check_circuit(&circuit, &settings);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].get_rule(), DesignRule::UnconnectedPin);
+        assert_eq!(problems[0].get_severity(), Severity::Error);
+        // the default severity is not stored
+        settings.set_severity(DesignRule::UnconnectedPin, Some(Severity::Warning));
+        settings.set_severity(DesignRule::FloatingInput, Some(Severity::Warning));
+        assert_eq!(settings, RuleSettings::default());
+    }
+}
@@ -0,0 +1,443 @@
+use super::{Annotation, AnnotationAnchor, Circuit, Component, ComponentId, PortRef, Wire, WireId};
+
+/// Number of edits that can be undone if nothing else is configured
+pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+
+/// A single reversible change of a [`Circuit`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// The component was placed, with the id it got assigned
+    AddComponent(Component),
+    RemoveComponent(Component),
+    /// The component was moved, rotated, mirrored or one of its properties changed
+    ReplaceComponent {
+        before: Component,
+        after: Component,
+    },
+    AddWire(Wire),
+    RemoveWire(Wire),
+    /// The wire was routed along another path
+    ReplaceWire {
+        before: Wire,
+        after: Wire,
+    },
+    AddAnnotation(Annotation),
+    RemoveAnnotation(Annotation),
+    /// The annotation was moved or its text or style changed
+    ReplaceAnnotation {
+        before: Annotation,
+        after: Annotation,
+    },
+}
+
+impl Edit {
+    /// Returns the edits removing the wires, the components and every wire and annotation attached to them
+    pub fn remove_components(
+        circuit: &Circuit,
+        ids: &[ComponentId],
+        wire_ids: &[WireId],
+    ) -> Vec<Self> {
+        let wires: Vec<_> = circuit
+            .get_wires()
+            .iter()
+            .filter(|w| {
+                wire_ids.contains(&w.get_id())
+                    || ids.contains(&w.get_from().component)
+                    || ids.contains(&w.get_to().component)
+            })
+            .collect();
+        // the annotations go first, so undoing restores them after what they are attached to
+        let annotations = circuit
+            .get_annotations()
+            .iter()
+            .filter(|a| match a.get_anchor() {
+                Some(AnnotationAnchor::Component(id)) => ids.contains(&id),
+                Some(AnnotationAnchor::Wire(id)) => wires.iter().any(|w| w.get_id() == id),
+                None => false,
+            })
+            .map(|a| Self::RemoveAnnotation(a.clone()));
+        let components = ids
+            .iter()
+            .filter_map(|id| circuit.get_component(*id))
+            .map(|c| Self::RemoveComponent(c.clone()));
+        annotations
+            .chain(wires.iter().map(|w| Self::RemoveWire((*w).clone())))
+            .chain(components)
+            .collect()
+    }
+
+    /// Returns the edits replacing a component by one with other ports, e.g. after one of its properties changed.
+    /// `ports` gives for every port of the component the port of the replacement its wires move to,
+    /// the wires of ports without one are removed together with their annotations.
+    pub fn replace_ports(
+        circuit: &Circuit,
+        before: Component,
+        after: Component,
+        ports: &[Option<usize>],
+    ) -> Vec<Self> {
+        let id = before.get_id();
+        let moved = |port: PortRef| {
+            if port.component != id {
+                return Some(port);
+            }
+            let moved = ports.get(port.port).copied().flatten()?;
+            Some(PortRef::new(id, moved))
+        };
+        let mut removed = Vec::new();
+        let mut moved_wires = Vec::new();
+        for wire in circuit
+            .get_wires()
+            .iter()
+            .filter(|w| w.get_from().component == id || w.get_to().component == id)
+        {
+            match (moved(wire.get_from()), moved(wire.get_to())) {
+                (Some(from), Some(to)) if (from, to) != (wire.get_from(), wire.get_to()) => {
+                    moved_wires.push(Self::ReplaceWire {
+                        before: wire.clone(),
+                        after: wire.clone().with_ports(from, to),
+                    });
+                }
+                (Some(_), Some(_)) => {}
+                _ => removed.push(wire.get_id()),
+            }
+        }
+        let mut edits = Self::remove_components(circuit, &[], &removed);
+        edits.push(Self::ReplaceComponent { before, after });
+        edits.extend(moved_wires);
+        edits
+    }
+
+    pub fn apply(&self, circuit: &mut Circuit) {
+        match self {
+            Self::AddComponent(component) => circuit.restore_component(component.clone()),
+            Self::RemoveComponent(component) => {
+                circuit.remove_component(component.get_id());
+            }
+            Self::ReplaceComponent { after, .. } => {
+                circuit.replace_component(after.clone());
+            }
+            Self::AddWire(wire) => {
+                circuit.restore_wire(wire.clone());
+            }
+            Self::RemoveWire(wire) => {
+                circuit.remove_wire(wire.get_id());
+            }
+            Self::ReplaceWire { after, .. } => {
+                circuit.replace_wire(after.clone());
+            }
+            Self::AddAnnotation(annotation) => circuit.restore_annotation(annotation.clone()),
+            Self::RemoveAnnotation(annotation) => {
+                circuit.remove_annotation(annotation.get_id());
+            }
+            Self::ReplaceAnnotation { after, .. } => {
+                circuit.replace_annotation(after.clone());
+            }
+        }
+    }
+    pub fn revert(&self, circuit: &mut Circuit) {
+        self.get_inverse().apply(circuit);
+    }
+    /// Returns the edit undoing this edit
+    pub fn get_inverse(&self) -> Self {
+        match self {
+            Self::AddComponent(component) => Self::RemoveComponent(component.clone()),
+            Self::RemoveComponent(component) => Self::AddComponent(component.clone()),
+            Self::ReplaceComponent { before, after } => Self::ReplaceComponent {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Self::AddWire(wire) => Self::RemoveWire(wire.clone()),
+            Self::RemoveWire(wire) => Self::AddWire(wire.clone()),
+            Self::ReplaceWire { before, after } => Self::ReplaceWire {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Self::AddAnnotation(annotation) => Self::RemoveAnnotation(annotation.clone()),
+            Self::RemoveAnnotation(annotation) => Self::AddAnnotation(annotation.clone()),
+            Self::ReplaceAnnotation { before, after } => Self::ReplaceAnnotation {
+                before: after.clone(),
+                after: before.clone(),
+            },
+        }
+    }
+}
+
+/// The edits of a circuit that can be undone and redone.
+///
+/// Edits recorded between [`EditHistory::begin_group`] and [`EditHistory::end_group`] are undone as one step,
+/// e.g. a placed component together with the wires connecting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EditHistory {
+    undo_stack: Vec<Vec<Edit>>,
+    redo_stack: Vec<Vec<Edit>>,
+    /// The compound edit currently being recorded
+    group: Option<Vec<Edit>>,
+    /// Maximal number of steps that can be undone
+    depth: usize,
+}
+
+impl Default for EditHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_DEPTH)
+    }
+}
+
+impl EditHistory {
+    pub const fn new(depth: usize) -> Self {
+        Self {
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            group: None,
+            depth,
+        }
+    }
+
+    /// Applies the edit to the circuit and records it
+    pub fn apply(&mut self, circuit: &mut Circuit, edit: Edit) {
+        edit.apply(circuit);
+        self.record(edit);
+    }
+    /// Records an edit that was already applied to the circuit. Recording an edit clears the redo steps.
+    pub fn record(&mut self, edit: Edit) {
+        self.redo_stack.clear();
+        match self.group.as_mut() {
+            Some(group) => group.push(edit),
+            None => self.push_step(vec![edit]),
+        }
+    }
+
+    /// Starts recording a compound edit, every edit until [`EditHistory::end_group`] is undone at once
+    pub fn begin_group(&mut self) {
+        self.end_group();
+        self.group = Some(Vec::new());
+    }
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.group.take() {
+            if !group.is_empty() {
+                self.push_step(group);
+            }
+        }
+    }
+
+    /// Reverts the last step, returns whether there was a step to undo
+    pub fn undo(&mut self, circuit: &mut Circuit) -> bool {
+        self.end_group();
+        let Some(step) = self.undo_stack.pop() else {
+            return false;
+        };
+        step.iter().rev().for_each(|edit| edit.revert(circuit));
+        self.redo_stack.push(step);
+        true
+    }
+    /// Applies the last undone step again, returns whether there was a step to redo
+    pub fn redo(&mut self, circuit: &mut Circuit) -> bool {
+        self.end_group();
+        let Some(step) = self.redo_stack.pop() else {
+            return false;
+        };
+        step.iter().for_each(|edit| edit.apply(circuit));
+        self.undo_stack.push(step);
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty() || self.group.as_ref().is_some_and(|g| !g.is_empty())
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    pub const fn get_depth(&self) -> usize {
+        self.depth
+    }
+    /// Changes how many steps can be undone, dropping the oldest steps beyond it
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+        self.trim();
+    }
+    /// Forgets every step, e.g. after another design was opened
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.group = None;
+    }
+
+    fn push_step(&mut self, step: Vec<Edit>) {
+        self.undo_stack.push(step);
+        self.trim();
+    }
+    fn trim(&mut self) {
+        let excess = self.undo_stack.len().saturating_sub(self.depth);
+        self.undo_stack.drain(..excess);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{ComponentType, ConnectionKind, ConnectionPoint, LogicGateType};
+
+    /// A gate laid out like the one of the component library, its inputs first and its output last
+    fn gate(inputs: usize) -> Component {
+        let port = |y, kind| ConnectionPoint::new(0.0, y, [true; 4], kind);
+        #[allow(clippy::cast_precision_loss)]
+        let mut ports: Vec<_> = (0..inputs)
+            .map(|input| port(input as f64 * 25.0, ConnectionKind::Input))
+            .collect();
+        ports.push(port(0.0, ConnectionKind::Output));
+        Component::new(
+            ComponentType::LogicGate(LogicGateType::And),
+            (100.0, 0.0),
+            (50.0, 100.0),
+            ports,
+        )
+    }
+    fn pin(kind: ConnectionKind) -> Component {
+        let port = ConnectionPoint::new(0.0, 0.0, [true; 4], kind);
+        Component::new(ComponentType::Lever, (0.0, 0.0), (25.0, 25.0), vec![port])
+    }
+    /// Returns the gate port every pin is connected to
+    fn connections(circuit: &Circuit, pins: &[ComponentId]) -> Vec<Option<usize>> {
+        pins.iter()
+            .map(|pin| {
+                circuit
+                    .get_wires()
+                    .iter()
+                    .find(|w| w.get_from().component == *pin)
+                    .map(|w| w.get_to().port)
+            })
+            .collect()
+    }
+    /// Adds a lever to the circuit like the workarea does, recording it
+    fn place(circuit: &mut Circuit, history: &mut EditHistory) -> ComponentId {
+        let id = circuit.add_component(pin(ConnectionKind::Output));
+        history.record(Edit::AddComponent(
+            circuit.get_component(id).cloned().unwrap(),
+        ));
+        id
+    }
+    /// Changes the number of inputs of the gate like the property inspector does
+    fn set_inputs(
+        circuit: &mut Circuit,
+        history: &mut EditHistory,
+        id: ComponentId,
+        inputs: usize,
+    ) {
+        let before = circuit.get_component(id).cloned().unwrap();
+        let after = before.clone().rebuilt_as(gate(inputs));
+        let ports = before.map_ports_to(&after);
+        history.begin_group();
+        for edit in Edit::replace_ports(circuit, before, after, &ports) {
+            history.apply(circuit, edit);
+        }
+        history.end_group();
+    }
+
+    #[test]
+    fn changing_the_input_count_keeps_the_output_wire() {
+        let mut circuit = Circuit::new();
+        let mut history = EditHistory::default();
+        let id = circuit.add_component(gate(3));
+        let pins: Vec<_> = [ConnectionKind::Output; 3]
+            .into_iter()
+            .chain([ConnectionKind::Input])
+            .map(|kind| circuit.add_component(pin(kind)))
+            .collect();
+        for (port, pin) in pins.iter().enumerate() {
+            circuit.add_wire(PortRef::new(*pin, 0), PortRef::new(id, port));
+        }
+
+        set_inputs(&mut circuit, &mut history, id, 2);
+        assert_eq!(
+            connections(&circuit, &pins),
+            [Some(0), Some(1), None, Some(2)]
+        );
+        set_inputs(&mut circuit, &mut history, id, 4);
+        assert_eq!(
+            connections(&circuit, &pins),
+            [Some(0), Some(1), None, Some(4)]
+        );
+
+        history.undo(&mut circuit);
+        assert_eq!(
+            connections(&circuit, &pins),
+            [Some(0), Some(1), None, Some(2)]
+        );
+        history.undo(&mut circuit);
+        assert_eq!(
+            connections(&circuit, &pins),
+            [Some(0), Some(1), Some(2), Some(3)]
+        );
+        assert_eq!(circuit.get_nets().len(), 4);
+    }
+
+    #[test]
+    fn ports_keep_their_index_while_their_number_stays() {
+        let inverted = gate(2).with_inverted_inputs(vec![true, false]);
+        assert_eq!(gate(2).map_ports_to(&inverted), [Some(0), Some(1), Some(2)]);
+        assert_eq!(gate(1).map_ports_to(&gate(3)), [Some(0), Some(3)]);
+    }
+
+    #[test]
+    fn a_group_is_undone_and_redone_as_one_step() {
+        let mut circuit = Circuit::new();
+        let mut history = EditHistory::default();
+        history.begin_group();
+        let lever = place(&mut circuit, &mut history);
+        let and = circuit.add_component(gate(1));
+        history.record(Edit::AddComponent(
+            circuit.get_component(and).cloned().unwrap(),
+        ));
+        let wire = circuit
+            .add_wire(PortRef::new(lever, 0), PortRef::new(and, 0))
+            .unwrap();
+        history.record(Edit::AddWire(circuit.get_wire(wire).cloned().unwrap()));
+        history.end_group();
+
+        assert!(history.undo(&mut circuit));
+        assert!(circuit.get_components().is_empty());
+        assert!(circuit.get_wires().is_empty());
+        assert!(!history.can_undo());
+
+        assert!(history.redo(&mut circuit));
+        assert_eq!(circuit.get_components().len(), 2);
+        assert_eq!(circuit.get_wires().len(), 1);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn recording_an_edit_clears_the_redo_steps() {
+        let mut circuit = Circuit::new();
+        let mut history = EditHistory::default();
+        place(&mut circuit, &mut history);
+        history.undo(&mut circuit);
+        assert!(history.can_redo());
+
+        place(&mut circuit, &mut history);
+        assert!(!history.can_redo());
+        assert!(!history.redo(&mut circuit));
+        assert_eq!(circuit.get_components().len(), 1);
+    }
+
+    #[test]
+    fn reducing_the_depth_drops_the_oldest_steps() {
+        let mut circuit = Circuit::new();
+        let mut history = EditHistory::default();
+        let first = place(&mut circuit, &mut history);
+        place(&mut circuit, &mut history);
+        place(&mut circuit, &mut history);
+
+        history.set_depth(2);
+        assert!(history.undo(&mut circuit));
+        assert!(history.undo(&mut circuit));
+        assert!(!history.undo(&mut circuit));
+        assert_eq!(
+            circuit
+                .get_components()
+                .iter()
+                .map(Component::get_id)
+                .collect::<Vec<_>>(),
+            [first]
+        );
+    }
+}
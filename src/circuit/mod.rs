@@ -0,0 +1,29 @@
+//! The headless circuit model.
+//!
+//! Nothing in here depends on `web_sys` or `yew`, so the model can be used (and tested) natively.
+//! The user interface is only a view over a [`Circuit`].
+mod annotation;
+pub use annotation::*;
+#[allow(clippy::module_inception)]
+mod circuit;
+pub use circuit::*;
+mod component_type;
+pub use component_type::*;
+mod connection_point;
+pub use connection_point::*;
+mod design_rules;
+pub use design_rules::*;
+mod edit_history;
+pub use edit_history::*;
+mod orientation;
+pub use orientation::*;
+mod property;
+pub use property::*;
+mod simulation;
+pub use simulation::*;
+mod subcircuit;
+pub use subcircuit::*;
+mod timing;
+pub use timing::*;
+mod wire_router;
+pub use wire_router::*;
@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+
+/// How a component is turned on the canvas: first mirrored left to right, then rotated clockwise in quarter turns.
+/// Positions are relative to the top left corner of the bounding box, y pointing down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Orientation {
+    /// Clockwise quarter turns, 0 to 3
+    rotation: u8,
+    mirrored: bool,
+}
+
+impl Orientation {
+    pub const IDENTITY: Self = Self {
+        rotation: 0,
+        mirrored: false,
+    };
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::IDENTITY
+    }
+    pub const fn get_rotation(&self) -> u8 {
+        self.rotation
+    }
+    pub const fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    pub const fn rotated_right(&self) -> Self {
+        Self {
+            rotation: (self.rotation + 1) % 4,
+            ..*self
+        }
+    }
+    pub const fn rotated_left(&self) -> Self {
+        Self {
+            rotation: (self.rotation + 3) % 4,
+            ..*self
+        }
+    }
+    /// Swaps left and right of the component as it is currently shown
+    pub const fn mirrored_horizontal(&self) -> Self {
+        Self {
+            rotation: (4 - self.rotation) % 4,
+            mirrored: !self.mirrored,
+        }
+    }
+    /// Swaps top and bottom of the component as it is currently shown
+    pub const fn mirrored_vertical(&self) -> Self {
+        Self {
+            rotation: (6 - self.rotation) % 4,
+            mirrored: !self.mirrored,
+        }
+    }
+    /// Returns the orientation turning the component back
+    pub const fn get_inverse(&self) -> Self {
+        Self {
+            rotation: if self.mirrored {
+                self.rotation
+            } else {
+                (4 - self.rotation) % 4
+            },
+            mirrored: self.mirrored,
+        }
+    }
+
+    /// Returns the size of the bounding box after turning it
+    pub const fn transform_size(&self, size: (f64, f64)) -> (f64, f64) {
+        if self.rotation.is_multiple_of(2) {
+            size
+        } else {
+            (size.1, size.0)
+        }
+    }
+    /// Returns where a point within a bounding box of the given size ends up after turning the box
+    pub fn transform_point(&self, point: (f64, f64), size: (f64, f64)) -> (f64, f64) {
+        let (mut x, mut y) = point;
+        let (mut width, mut height) = size;
+        if self.mirrored {
+            x = width - x;
+        }
+        for _ in 0..self.rotation {
+            (x, y) = (height - y, x);
+            (width, height) = (height, width);
+        }
+        (x, y)
+    }
+    /// Turns the directions ordered as up, right, down, left
+    pub fn transform_directions(&self, directions: [bool; 4]) -> [bool; 4] {
+        let mut directions = directions;
+        if self.mirrored {
+            directions.swap(1, 3);
+        }
+        directions.rotate_right(usize::from(self.rotation));
+        directions
+    }
+}
@@ -0,0 +1,83 @@
+use std::ops::RangeInclusive;
+
+/// The values a property of a component can take
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyKind {
+    Bool,
+    /// One of the named options
+    Enum(&'static [&'static str]),
+    Integer(RangeInclusive<u32>),
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyValue {
+    Bool(bool),
+    /// The index of the option
+    Enum(usize),
+    Integer(u32),
+    Text(String),
+}
+
+/// A setting of a placed component that can be edited, e.g. whether an input of a gate is inverted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Property {
+    name: String,
+    kind: PropertyKind,
+    value: PropertyValue,
+}
+
+impl Property {
+    pub fn new(name: &str, kind: PropertyKind, value: PropertyValue) -> Self {
+        Self {
+            name: name.to_owned(),
+            kind,
+            value,
+        }
+    }
+    pub fn bool(name: &str, value: bool) -> Self {
+        Self::new(name, PropertyKind::Bool, PropertyValue::Bool(value))
+    }
+    /// Creates an enum property with the option at `index` chosen
+    pub fn choice(name: &str, options: &'static [&'static str], index: usize) -> Self {
+        Self::new(
+            name,
+            PropertyKind::Enum(options),
+            PropertyValue::Enum(index),
+        )
+    }
+    pub fn integer(name: &str, range: RangeInclusive<u32>, value: u32) -> Self {
+        Self::new(
+            name,
+            PropertyKind::Integer(range),
+            PropertyValue::Integer(value),
+        )
+    }
+    pub fn text(name: &str, value: &str) -> Self {
+        Self::new(
+            name,
+            PropertyKind::Text,
+            PropertyValue::Text(value.to_owned()),
+        )
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub const fn get_kind(&self) -> &PropertyKind {
+        &self.kind
+    }
+    pub const fn get_value(&self) -> &PropertyValue {
+        &self.value
+    }
+    /// Returns whether the value can be given to the property
+    pub fn accepts(&self, value: &PropertyValue) -> bool {
+        match (&self.kind, value) {
+            (PropertyKind::Bool, PropertyValue::Bool(_))
+            | (PropertyKind::Text, PropertyValue::Text(_)) => true,
+            (PropertyKind::Enum(options), PropertyValue::Enum(index)) => *index < options.len(),
+            (PropertyKind::Integer(range), PropertyValue::Integer(value)) => range.contains(value),
+            _ => false,
+        }
+    }
+}
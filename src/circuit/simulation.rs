@@ -0,0 +1,1220 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::{
+    Circuit, ComparatorMode, ComponentId, ComponentType, ConnectionKind, LogicGateType,
+    PinDirection, PortRef, PulseBehaviour, SequentialType, Timing, WireId, BLOCK_SIZE,
+    GAME_TICKS_PER_REDSTONE_TICK, MAX_SIGNAL_STRENGTH,
+};
+
+/// Upper bound of steps for [`Simulation::run_until_change`], so oscillating circuits cannot hang the application
+const MAX_STEPS_UNTIL_CHANGE: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+enum ElementKind {
+    Gate {
+        gate_type: LogicGateType,
+        inverted_inputs: Vec<bool>,
+    },
+    /// On while its input is off
+    Torch,
+    /// Passes on any signal at full strength
+    Repeater,
+    /// Combines the signals on its rear (first input) and its side (second input)
+    Comparator(ComparatorMode),
+    /// Emits a pulse whenever the signal on its input changes
+    Observer,
+    /// A latch or flip-flop with its stored value and the level of its clock when it was last evaluated.
+    /// Its outputs are the stored value and its inverse.
+    Sequential {
+        sequential_type: SequentialType,
+        stored: bool,
+        clock: bool,
+    },
+    /// Toggles its output every given number of game ticks
+    Clock(u64),
+    /// Emits a pulse of the given number of game ticks on every rising edge, remembering the level of its input
+    Monostable { length: u64, input: bool },
+    /// A splitter or merger passing every input bit on to the output bit in the same place
+    Splitter,
+    /// A pin, lever or pressure plate driving its net with a value set by the user
+    Input,
+    /// A button driving its net for the given number of redstone ticks when pressed
+    Button(u8),
+    /// A pin, lamp, piston or note block showing the value of a net that is driven by the circuit
+    Output,
+}
+
+/// An input of an element and the drivers reaching it
+#[derive(Debug, Clone, PartialEq)]
+struct Input {
+    strength: u8,
+    /// The drivers of the net and their distance in blocks of dust
+    sources: Vec<(usize, u8)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    id: ComponentId,
+    kind: ElementKind,
+    timing: Timing,
+    inputs: Vec<Input>,
+    /// The drivers of the output nets in the order of the output ports
+    outputs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Driver {
+    strength: u8,
+    /// The strength the driver will have once all scheduled events are processed
+    projected: u8,
+    /// The inputs reached by the driver as (element, input)
+    readers: Vec<(usize, usize)>,
+    /// The last scheduled event that has not been processed yet
+    pending: Option<Event>,
+    /// The time of the last scheduled rising edge
+    last_rise: u64,
+    /// The element the driver is an output of
+    element: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Event {
+    /// game tick
+    time: u64,
+    /// Keeps events scheduled for the same time in the order they were scheduled
+    sequence: u64,
+    driver: usize,
+    strength: u8,
+}
+
+/// Where the state of a port is stored in the simulation.
+/// The bits of a bus are stored in consecutive inputs or drivers starting at the given one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PortState {
+    Input(usize, usize),
+    Driver(usize),
+}
+
+/// An event-driven simulation of a [`Circuit`] in game ticks.
+///
+/// Every component delays its output according to its [`Timing`], so the simulation behaves like the circuit built in
+/// the game. Wires are treated as redstone dust: the signal loses one level of strength per block, and a net driven by
+/// several outputs carries the strongest signal reaching each point.
+/// Output pins and bidirectional pins connected to a net driven by an output show its value, all other pins are
+/// inputs set by the user.
+/// Levers, buttons and pressure plates are set by the user as well, lamps, pistons and note blocks show the value
+/// of their input. Latches and flip-flops keep their value between evaluations, and clocks run on their own.
+/// Every bit of a bus is simulated like a separate net running along the same wires.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    elements: Vec<Element>,
+    drivers: Vec<Driver>,
+    ports: HashMap<PortRef, PortState>,
+    /// The width of every port carrying a bus, ports missing here carry a single bit
+    bus_widths: HashMap<PortRef, u8>,
+    /// The drivers of the net of a wire as (first driver, distance to the wire in blocks, number of bits)
+    wire_sources: HashMap<WireId, Vec<(usize, u8, u8)>>,
+    /// The width of every wire carrying a bus
+    wire_widths: HashMap<WireId, u8>,
+    /// Scheduled events ordered by time
+    queue: BTreeSet<Event>,
+    /// current game tick
+    time: u64,
+    sequence: u64,
+}
+
+impl Simulation {
+    /// Creates a simulation of the circuit in its initial state, with every input pin low
+    #[allow(clippy::too_many_lines)]
+    pub fn new(circuit: &Circuit) -> Self {
+        let is_driver = |port: PortRef| {
+            let Some(component) = circuit.get_component(port.component) else {
+                return false;
+            };
+            match component.get_component_type() {
+                // bidirectional pins drive their net if no other output does
+                ComponentType::Pin(PinDirection::Bidirectional, _) => {
+                    circuit.get_net_of(port).is_none_or(|net| {
+                        !net.get_ports().iter().any(|p| {
+                            circuit
+                                .get_component(p.component)
+                                .and_then(|c| c.get_ports().get(p.port))
+                                .is_some_and(|cp| cp.get_kind() == ConnectionKind::Output)
+                        })
+                    })
+                }
+                _ => component
+                    .get_ports()
+                    .get(port.port)
+                    .is_some_and(|cp| cp.get_kind() != ConnectionKind::Input),
+            }
+        };
+
+        let mut elements = Vec::new();
+        let mut drivers = Vec::new();
+        let mut ports = HashMap::new();
+        let mut bus_widths = HashMap::new();
+        for component in circuit.get_components() {
+            let id = component.get_id();
+            let mut inputs = Vec::new();
+            let mut outputs = Vec::new();
+            for (port, point) in component.get_ports().iter().enumerate() {
+                let port_ref = PortRef::new(id, port);
+                let width = point.get_width().max(1);
+                if width > 1 {
+                    bus_widths.insert(port_ref, width);
+                }
+                if is_driver(port_ref) {
+                    ports.insert(port_ref, PortState::Driver(drivers.len()));
+                    for _ in 0..width {
+                        drivers.push(Driver {
+                            strength: 0,
+                            projected: 0,
+                            readers: Vec::new(),
+                            pending: None,
+                            last_rise: 0,
+                            element: elements.len(),
+                        });
+                        outputs.push(drivers.len() - 1);
+                    }
+                } else {
+                    ports.insert(port_ref, PortState::Input(elements.len(), inputs.len()));
+                    for _ in 0..width {
+                        inputs.push(Input {
+                            strength: 0,
+                            sources: Vec::new(),
+                        });
+                    }
+                }
+            }
+            let kind = match component.get_component_type() {
+                ComponentType::LogicGate(gate_type) => ElementKind::Gate {
+                    gate_type,
+                    inverted_inputs: (0..inputs.len())
+                        .map(|input| component.is_input_inverted(input))
+                        .collect(),
+                },
+                ComponentType::Torch => ElementKind::Torch,
+                ComponentType::Repeater(_) => ElementKind::Repeater,
+                ComponentType::Comparator(mode) => ElementKind::Comparator(mode),
+                ComponentType::Observer => ElementKind::Observer,
+                ComponentType::Button(button_type) => {
+                    ElementKind::Button(button_type.get_pulse_length())
+                }
+                ComponentType::Sequential(sequential_type) => ElementKind::Sequential {
+                    sequential_type,
+                    stored: false,
+                    clock: false,
+                },
+                ComponentType::Clock(period) => ElementKind::Clock(
+                    u64::from((period / 2).max(1)) * GAME_TICKS_PER_REDSTONE_TICK,
+                ),
+                ComponentType::Monostable(length) => ElementKind::Monostable {
+                    length: u64::from(length) * GAME_TICKS_PER_REDSTONE_TICK,
+                    input: false,
+                },
+                ComponentType::Splitter(_) | ComponentType::Merger(_) => ElementKind::Splitter,
+                ComponentType::Pin(..) if !outputs.is_empty() => ElementKind::Input,
+                ComponentType::Lever | ComponentType::PressurePlate => ElementKind::Input,
+                ComponentType::Pin(..)
+                | ComponentType::Lamp
+                | ComponentType::Piston
+                | ComponentType::StickyPiston
+                | ComponentType::NoteBlock => ElementKind::Output,
+                // instances are flattened before they are simulated
+                ComponentType::Subcircuit(_) => ElementKind::Output,
+            };
+            elements.push(Element {
+                id,
+                kind,
+                timing: component.get_timing(),
+                inputs,
+                outputs,
+            });
+        }
+
+        // connect the drivers of every net to its inputs through the dust, bit by bit
+        let width_of = |port: &PortRef| bus_widths.get(port).copied().unwrap_or(1);
+        let mut wire_sources: HashMap<WireId, Vec<(usize, u8, u8)>> = HashMap::new();
+        let mut wire_widths = HashMap::new();
+        for net in circuit.get_nets() {
+            for port in net.get_ports() {
+                let Some(PortState::Driver(driver)) = ports.get(port).copied() else {
+                    continue;
+                };
+                let width = width_of(port);
+                let distances = Self::get_distances(circuit, *port, net.get_wires());
+                for (other, distance) in &distances.0 {
+                    if let Some(PortState::Input(element, input)) = ports.get(other).copied() {
+                        for bit in 0..usize::from(width.min(width_of(other))) {
+                            elements[element].inputs[input + bit]
+                                .sources
+                                .push((driver + bit, *distance));
+                            drivers[driver + bit].readers.push((element, input + bit));
+                        }
+                    }
+                }
+                for (wire, distance) in distances.1 {
+                    wire_sources
+                        .entry(wire)
+                        .or_default()
+                        .push((driver, distance, width));
+                    if width > 1 {
+                        let wire_width = wire_widths.entry(wire).or_insert(width);
+                        *wire_width = (*wire_width).max(width);
+                    }
+                }
+            }
+        }
+
+        let mut simulation = Self {
+            elements,
+            drivers,
+            ports,
+            bus_widths,
+            wire_sources,
+            wire_widths,
+            queue: BTreeSet::new(),
+            time: 0,
+            sequence: 0,
+        };
+        for element in 0..simulation.elements.len() {
+            simulation.evaluate(element);
+        }
+        simulation
+    }
+
+    /// Advances the simulation by the given number of game ticks
+    pub fn advance_ticks(&mut self, ticks: u64) {
+        let target = self.time + ticks;
+        while self.queue.first().is_some_and(|e| e.time <= target) {
+            self.step_elements();
+        }
+        self.time = target;
+    }
+
+    /// Steps the simulation until the value shown by an output changes or nothing is scheduled anymore.
+    /// Returns whether an output changed.
+    pub fn run_until_change(&mut self) -> bool {
+        for _ in 0..MAX_STEPS_UNTIL_CHANGE {
+            let Some(toggled) = self.step_elements() else {
+                return false;
+            };
+            if toggled
+                .iter()
+                .any(|e| self.elements[*e].kind == ElementKind::Output)
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Sets the value of an input pin, lever or pressure plate, it is applied with the next step
+    pub fn set_input(&mut self, id: ComponentId, value: bool) {
+        self.set_input_bits(id, u32::from(value));
+    }
+    /// Sets the bits of an input pin carrying a bus, the first bit is the least significant.
+    /// It is applied with the next step.
+    pub fn set_input_bits(&mut self, id: ComponentId, bits: u32) {
+        let Some(outputs) = self
+            .elements
+            .iter()
+            .find(|e| e.id == id && e.kind == ElementKind::Input)
+            .map(|e| e.outputs.clone())
+        else {
+            return;
+        };
+        for (bit, driver) in outputs.into_iter().enumerate() {
+            let strength = if bits >> bit & 1 == 1 {
+                MAX_SIGNAL_STRENGTH
+            } else {
+                0
+            };
+            if self.drivers[driver].projected != strength {
+                self.schedule(driver, strength, self.time);
+            }
+        }
+    }
+
+    /// Toggles the value of an input pin, lever or pressure plate, or presses a button.
+    /// Returns `false` if the component is not set by the user.
+    pub fn toggle_input(&mut self, id: ComponentId) -> bool {
+        match self.get_input(id) {
+            Some(value) => {
+                self.set_input(id, !value);
+                true
+            }
+            None => self.press_button(id),
+        }
+    }
+
+    /// Starts the pulse of a button. Returns `false` if the component is not a button.
+    pub fn press_button(&mut self, id: ComponentId) -> bool {
+        let button = self.elements.iter().find_map(|e| match e.kind {
+            ElementKind::Button(length) if e.id == id => Some((*e.outputs.first()?, length)),
+            _ => None,
+        });
+        let Some((driver, length)) = button else {
+            return false;
+        };
+        self.pulse(
+            driver,
+            self.time,
+            u64::from(length) * GAME_TICKS_PER_REDSTONE_TICK,
+        );
+        true
+    }
+
+    /// Returns the value an input pin, lever or pressure plate is set to, `None` for any other component
+    pub fn get_input(&self, id: ComponentId) -> Option<bool> {
+        self.elements
+            .iter()
+            .find(|e| e.id == id && e.kind == ElementKind::Input)
+            .and_then(|e| e.outputs.first())
+            .map(|driver| self.drivers[*driver].projected > 0)
+    }
+
+    /// Returns the signal strength at the port
+    pub fn get_port_strength(&self, port: PortRef) -> Option<u8> {
+        match self.ports.get(&port)? {
+            PortState::Input(element, input) => {
+                Some(self.elements[*element].inputs[*input].strength)
+            }
+            PortState::Driver(driver) => Some(self.drivers[*driver].strength),
+        }
+    }
+
+    /// Returns the bits of the bus at the port, the first bit is the least significant
+    pub fn get_port_bits(&self, port: PortRef) -> Option<u32> {
+        let width = self.bus_widths.get(&port).copied().unwrap_or(1);
+        let bits = (0..usize::from(width)).map(|bit| match self.ports.get(&port)? {
+            PortState::Input(element, input) => {
+                Some(self.elements[*element].inputs[input + bit].strength > 0)
+            }
+            PortState::Driver(driver) => Some(self.drivers[driver + bit].strength > 0),
+        });
+        Self::pack_bits(bits)
+    }
+
+    /// Returns the bits of the bus on the wire, the first bit is the least significant
+    pub fn get_wire_bits(&self, wire: WireId) -> Option<u32> {
+        let sources = self.wire_sources.get(&wire)?;
+        let width = self.wire_widths.get(&wire).copied().unwrap_or(1);
+        let bits = (0..usize::from(width)).map(|bit| {
+            // drivers narrower than the wire are an error of the design and only reach their bits
+            let sources: Vec<_> = sources
+                .iter()
+                .filter(|(_, _, width)| bit < usize::from(*width))
+                .map(|(driver, distance, _)| (driver + bit, *distance))
+                .collect();
+            Some(self.get_strength(&sources) > 0)
+        });
+        Self::pack_bits(bits)
+    }
+
+    fn pack_bits(bits: impl Iterator<Item = Option<bool>>) -> Option<u32> {
+        bits.enumerate()
+            .try_fold(0, |packed, (bit, on)| Some(packed | u32::from(on?) << bit))
+    }
+
+    /// Returns the strongest signal on the wire
+    pub fn get_wire_strength(&self, wire: WireId) -> Option<u8> {
+        let sources: Vec<_> = self
+            .wire_sources
+            .get(&wire)?
+            .iter()
+            .map(|(driver, distance, _)| (*driver, *distance))
+            .collect();
+        Some(self.get_strength(&sources))
+    }
+
+    /// Returns whether nothing is scheduled anymore
+    pub fn is_stable(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// The current game tick
+    pub const fn get_time(&self) -> u64 {
+        self.time
+    }
+
+    /// Processes all events of the next point in time.
+    /// Returns the elements of which an input toggled between on and off, `None` if nothing is scheduled.
+    fn step_elements(&mut self) -> Option<Vec<usize>> {
+        let time = self.queue.first()?.time;
+        self.time = time;
+
+        let mut touched = Vec::new();
+        while let Some(event) = self.queue.first().copied() {
+            if event.time != time {
+                break;
+            }
+            self.queue.pop_first();
+            let driver = &mut self.drivers[event.driver];
+            driver.strength = event.strength;
+            if driver.pending == Some(event) {
+                driver.pending = None;
+            }
+            touched.extend(driver.readers.iter().copied());
+            let element = driver.element;
+            // a clock schedules its next toggle whenever it toggles
+            if let ElementKind::Clock(half_period) = self.elements[element].kind {
+                let strength = MAX_SIGNAL_STRENGTH - event.strength;
+                self.schedule(event.driver, strength, time + half_period);
+            }
+        }
+
+        let mut toggled = Vec::new();
+        let mut evaluate = Vec::new();
+        for (element, input) in touched {
+            let strength = self.get_strength(&self.elements[element].inputs[input].sources);
+            let old = self.elements[element].inputs[input].strength;
+            if strength != old {
+                self.elements[element].inputs[input].strength = strength;
+                if (strength > 0) != (old > 0) && !toggled.contains(&element) {
+                    toggled.push(element);
+                }
+                if !evaluate.contains(&element) {
+                    evaluate.push(element);
+                }
+            }
+        }
+        for element in evaluate {
+            if self.elements[element].kind == ElementKind::Observer {
+                self.observe(element);
+            } else {
+                self.evaluate(element);
+            }
+        }
+        Some(toggled)
+    }
+
+    /// Evaluates the element with the current input signals and schedules the changes of its outputs
+    fn evaluate(&mut self, element: usize) {
+        let timing = self.elements[element].timing;
+        let outputs = self.elements[element].outputs.clone();
+        let level = self.elements[element]
+            .inputs
+            .first()
+            .is_some_and(|i| i.strength > 0);
+        match &mut self.elements[element].kind {
+            ElementKind::Monostable { length, input } => {
+                let rising = level && !*input;
+                *input = level;
+                let length = *length;
+                if let Some(driver) = outputs.first().copied().filter(|_| rising) {
+                    self.pulse(driver, self.time + timing.get_delay_game_ticks(), length);
+                }
+            }
+            // the clock starts when the simulation is created and keeps itself running
+            ElementKind::Clock(_) => {
+                if let Some(driver) = outputs.first() {
+                    self.schedule(*driver, MAX_SIGNAL_STRENGTH, self.time);
+                }
+            }
+            _ => {
+                let Some(strengths) = Self::get_output_strengths(&mut self.elements[element])
+                else {
+                    return;
+                };
+                for (driver, strength) in outputs.into_iter().zip(strengths) {
+                    self.drive(driver, strength, timing);
+                }
+            }
+        }
+    }
+
+    /// Computes the strength of every output from the inputs of the element, updating the value stored by a latch or
+    /// flip-flop. Returns `None` for elements whose outputs don't follow their inputs.
+    fn get_output_strengths(element: &mut Element) -> Option<Vec<u8>> {
+        let strengths: Vec<u8> = element.inputs.iter().map(|i| i.strength).collect();
+        let strength = |input: usize| strengths.get(input).copied().unwrap_or(0);
+        let full = |on: bool| if on { MAX_SIGNAL_STRENGTH } else { 0 };
+        let strengths = match &mut element.kind {
+            ElementKind::Gate {
+                gate_type,
+                inverted_inputs,
+            } => {
+                let inputs: Vec<bool> = strengths
+                    .iter()
+                    .zip(inverted_inputs.iter())
+                    .map(|(strength, inverted)| (*strength > 0) != *inverted)
+                    .collect();
+                vec![full(gate_type.evaluate(&inputs))]
+            }
+            ElementKind::Torch => vec![full(strength(0) == 0)],
+            ElementKind::Splitter => strengths.iter().map(|s| full(*s > 0)).collect(),
+            ElementKind::Repeater => vec![full(strength(0) > 0)],
+            ElementKind::Comparator(mode) => vec![mode.evaluate(strength(0), strength(1))],
+            ElementKind::Sequential {
+                sequential_type,
+                stored,
+                clock,
+            } => {
+                let levels: Vec<bool> = strengths.iter().map(|s| *s > 0).collect();
+                let level = sequential_type
+                    .get_clock_input()
+                    .is_some_and(|input| strength(input) > 0);
+                let rising = level && !*clock;
+                *clock = level;
+                *stored = sequential_type.next_value(*stored, &levels, rising);
+                vec![full(*stored), full(!*stored)]
+            }
+            ElementKind::Observer
+            | ElementKind::Clock(_)
+            | ElementKind::Monostable { .. }
+            | ElementKind::Input
+            | ElementKind::Button(_)
+            | ElementKind::Output => return None,
+        };
+        Some(strengths)
+    }
+
+    /// Schedules the change of a driver after the delay, passing on or dropping pulses as the timing demands
+    fn drive(&mut self, driver: usize, strength: u8, timing: Timing) {
+        if self.drivers[driver].projected == strength {
+            return;
+        }
+
+        let time = self.time + timing.get_delay_game_ticks();
+        match timing.get_pulse_behaviour() {
+            PulseBehaviour::Transport => self.schedule(driver, strength, time),
+            PulseBehaviour::Extend => {
+                // the output stays on for at least the delay
+                let time = if strength == 0 {
+                    time.max(self.drivers[driver].last_rise + timing.get_delay_game_ticks())
+                } else {
+                    time
+                };
+                self.schedule(driver, strength, time);
+            }
+            PulseBehaviour::Filter(min_pulse) => {
+                let min_pulse = u64::from(min_pulse) * GAME_TICKS_PER_REDSTONE_TICK;
+                match self.drivers[driver].pending {
+                    // the input returned before the last change propagated, the pulse is dropped
+                    Some(pending) if time.saturating_sub(pending.time) < min_pulse => {
+                        self.cancel(pending);
+                        // a change to yet another strength still reaches the output
+                        if self.drivers[driver].projected != strength {
+                            self.schedule(driver, strength, time);
+                        }
+                    }
+                    _ => self.schedule(driver, strength, time),
+                }
+            }
+        }
+    }
+
+    /// Schedules a pulse of an observer after its delay, the pulse lasts as long as the delay
+    fn observe(&mut self, element: usize) {
+        let element = &self.elements[element];
+        let delay = element.timing.get_delay_game_ticks();
+        if let Some(driver) = element.outputs.first().copied() {
+            self.pulse(driver, self.time + delay, delay);
+        }
+    }
+
+    /// Drives the output at full strength for the given number of game ticks, starting at the given time
+    fn pulse(&mut self, driver: usize, start: u64, length: u64) {
+        self.schedule(driver, MAX_SIGNAL_STRENGTH, start);
+        self.schedule(driver, 0, start + length);
+    }
+
+    /// Returns the strongest signal reaching a point from the given drivers and their distance in blocks
+    fn get_strength(&self, sources: &[(usize, u8)]) -> u8 {
+        sources
+            .iter()
+            .map(|(driver, distance)| self.drivers[*driver].strength.saturating_sub(*distance))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn schedule(&mut self, driver: usize, strength: u8, time: u64) {
+        self.sequence += 1;
+        let event = Event {
+            time,
+            sequence: self.sequence,
+            driver,
+            strength,
+        };
+        let driver = &mut self.drivers[driver];
+        if strength > driver.projected {
+            driver.last_rise = time;
+        }
+        driver.projected = strength;
+        driver.pending = Some(event);
+        self.queue.insert(event);
+    }
+
+    /// Removes a scheduled event, the driver keeps the value it had before
+    fn cancel(&mut self, event: Event) {
+        self.queue.remove(&event);
+        let previous = self
+            .queue
+            .iter()
+            .rev()
+            .find(|e| e.driver == event.driver)
+            .copied();
+        let driver = &mut self.drivers[event.driver];
+        driver.pending = previous;
+        driver.projected = previous.map_or(driver.strength, |e| e.strength);
+    }
+
+    /// Returns the distance in blocks of dust from the port to every port and wire reachable through the wires
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn get_distances(
+        circuit: &Circuit,
+        start: PortRef,
+        wires: &[WireId],
+    ) -> (HashMap<PortRef, u8>, HashMap<WireId, u8>) {
+        let mut port_distances: HashMap<PortRef, u8> = HashMap::from([(start, 0)]);
+        let mut wire_distances: HashMap<WireId, u8> = HashMap::new();
+        // the nets are small, so relaxing every wire until nothing changes is fast enough
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for id in wires {
+                let Some(wire) = circuit.get_wire(*id) else {
+                    continue;
+                };
+                let length = circuit
+                    .get_wire_length(*id)
+                    .map_or(0.0, |l| (l / BLOCK_SIZE).round())
+                    .min(f64::from(u8::MAX)) as u8;
+                for (from, to) in [
+                    (wire.get_from(), wire.get_to()),
+                    (wire.get_to(), wire.get_from()),
+                ] {
+                    let Some(distance) = port_distances.get(&from).copied() else {
+                        continue;
+                    };
+                    let wire_distance = wire_distances.entry(*id).or_insert(u8::MAX);
+                    *wire_distance = (*wire_distance).min(distance);
+                    let distance = distance.saturating_add(length);
+                    if port_distances.get(&to).is_none_or(|d| distance < *d) {
+                        port_distances.insert(to, distance);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        (port_distances, wire_distances)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{Component, ConnectionPoint};
+
+    fn comparator() -> Component {
+        let port = |x, y, kind| ConnectionPoint::new(x, y, [true; 4], kind);
+        Component::new(
+            ComponentType::Comparator(ComparatorMode::Compare),
+            (0.0, 0.0),
+            (50.0, 50.0),
+            vec![
+                port(0.0, 25.0, ConnectionKind::Input),
+                port(25.0, 50.0, ConnectionKind::Input),
+                port(50.0, 25.0, ConnectionKind::Output),
+            ],
+        )
+        .with_timing(Timing::comparator())
+    }
+
+    fn pin(direction: PinDirection, width: u8) -> Component {
+        let kind = match direction {
+            PinDirection::Input => ConnectionKind::Output,
+            _ => ConnectionKind::Input,
+        };
+        let port = ConnectionPoint::new(25.0, 25.0, [true; 4], kind).with_width(width);
+        Component::new(
+            ComponentType::Pin(direction, width),
+            (0.0, 0.0),
+            (25.0, 50.0),
+            vec![port],
+        )
+    }
+
+    /// A component with its inputs and then its outputs all at its position, so wires between components at the same
+    /// position have no length
+    fn part(component_type: ComponentType, x: f64, inputs: usize, outputs: usize) -> Component {
+        let port = |kind| ConnectionPoint::new(0.0, 0.0, [true; 4], kind);
+        let ports = (0..inputs)
+            .map(|_| port(ConnectionKind::Input))
+            .chain((0..outputs).map(|_| port(ConnectionKind::Output)))
+            .collect();
+        Component::new(component_type, (x, 0.0), (25.0, 25.0), ports)
+    }
+    fn gate(gate_type: LogicGateType, inputs: usize) -> Component {
+        part(ComponentType::LogicGate(gate_type), 0.0, inputs, 1)
+    }
+    fn input(circuit: &mut Circuit) -> ComponentId {
+        circuit.add_component(pin(PinDirection::Input, 1))
+    }
+    fn output(circuit: &mut Circuit, x: f64) -> ComponentId {
+        circuit.add_component(part(ComponentType::Lamp, x, 1, 0))
+    }
+    fn connect(circuit: &mut Circuit, from: (ComponentId, usize), to: (ComponentId, usize)) {
+        circuit.add_wire(PortRef::new(from.0, from.1), PortRef::new(to.0, to.1));
+    }
+    fn is_on(simulation: &Simulation, id: ComponentId) -> bool {
+        simulation
+            .get_port_strength(PortRef::new(id, 0))
+            .is_some_and(|s| s > 0)
+    }
+
+    #[test]
+    fn gates_evaluate_their_inverted_inputs() {
+        let mut circuit = Circuit::new();
+        let (a, b) = (input(&mut circuit), input(&mut circuit));
+        let and = circuit
+            .add_component(gate(LogicGateType::And, 2).with_inverted_inputs(vec![false, true]));
+        let lamp = output(&mut circuit, 0.0);
+        connect(&mut circuit, (a, 0), (and, 0));
+        connect(&mut circuit, (b, 0), (and, 1));
+        connect(&mut circuit, (and, 2), (lamp, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        simulation.set_input(a, true);
+        simulation.advance_ticks(0);
+        assert!(is_on(&simulation, lamp));
+        simulation.set_input(b, true);
+        simulation.advance_ticks(0);
+        assert!(!is_on(&simulation, lamp));
+        simulation.set_input(a, false);
+        simulation.set_input(b, false);
+        simulation.advance_ticks(0);
+        assert!(!is_on(&simulation, lamp));
+    }
+
+    #[test]
+    fn a_step_processes_the_next_point_in_time() {
+        let mut circuit = Circuit::new();
+        let a = input(&mut circuit);
+        let not = circuit.add_component(gate(LogicGateType::Not, 1).with_timing(Timing::torch()));
+        connect(&mut circuit, (a, 0), (not, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        // the inverter switches on when the simulation starts
+        let toggled = simulation.step_elements().unwrap();
+        assert_eq!(simulation.get_time(), 2);
+        assert!(toggled.is_empty());
+        assert_eq!(simulation.get_port_strength(PortRef::new(not, 1)), Some(15));
+        assert_eq!(simulation.step_elements(), None);
+
+        simulation.set_input(a, true);
+        let toggled = simulation.step_elements().unwrap();
+        assert_eq!(toggled.len(), 1);
+        assert_eq!(simulation.get_time(), 2);
+        simulation.step_elements();
+        assert_eq!(simulation.get_time(), 4);
+        assert_eq!(simulation.get_port_strength(PortRef::new(not, 1)), Some(0));
+        assert!(simulation.is_stable());
+    }
+
+    #[test]
+    fn running_until_a_change_settles_the_circuit() {
+        let mut circuit = Circuit::new();
+        let a = input(&mut circuit);
+        let not = circuit.add_component(gate(LogicGateType::Not, 1).with_timing(Timing::torch()));
+        let lamp = output(&mut circuit, 0.0);
+        connect(&mut circuit, (a, 0), (not, 0));
+        connect(&mut circuit, (not, 1), (lamp, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        assert!(simulation.run_until_change());
+        assert!(is_on(&simulation, lamp));
+        assert!(!simulation.run_until_change());
+        assert!(simulation.is_stable());
+
+        simulation.set_input(a, true);
+        assert!(simulation.run_until_change());
+        assert!(!is_on(&simulation, lamp));
+        assert_eq!(simulation.get_time(), 4);
+        assert!(!simulation.run_until_change());
+    }
+
+    #[test]
+    fn toggling_an_input_propagates_through_a_chain() {
+        let mut circuit = Circuit::new();
+        let a = input(&mut circuit);
+        let mut previous = a;
+        for _ in 0..3 {
+            let buffer = circuit
+                .add_component(gate(LogicGateType::Buffer, 1).with_timing(Timing::repeater(1)));
+            connect(
+                &mut circuit,
+                (previous, usize::from(previous != a)),
+                (buffer, 0),
+            );
+            previous = buffer;
+        }
+        let lamp = output(&mut circuit, 0.0);
+        connect(&mut circuit, (previous, 1), (lamp, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        assert!(simulation.toggle_input(a));
+        assert_eq!(simulation.get_input(a), Some(true));
+        simulation.advance_ticks(5);
+        assert!(!is_on(&simulation, lamp));
+        simulation.advance_ticks(1);
+        assert!(is_on(&simulation, lamp));
+
+        assert!(simulation.toggle_input(a));
+        simulation.advance_ticks(6);
+        assert!(!is_on(&simulation, lamp));
+        assert!(simulation.is_stable());
+    }
+
+    fn lever(circuit: &mut Circuit) -> ComponentId {
+        circuit.add_component(part(ComponentType::Lever, 0.0, 0, 1))
+    }
+
+    #[test]
+    fn repeaters_delay_by_their_setting() {
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        let repeater = circuit.add_component(
+            part(ComponentType::Repeater(3), 0.0, 1, 1).with_timing(Timing::repeater(3)),
+        );
+        let lamp = output(&mut circuit, 0.0);
+        connect(&mut circuit, (lever, 0), (repeater, 0));
+        connect(&mut circuit, (repeater, 1), (lamp, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        simulation.set_input(lever, true);
+        simulation.advance_ticks(5);
+        assert!(!is_on(&simulation, lamp));
+        simulation.advance_ticks(1);
+        assert!(is_on(&simulation, lamp));
+    }
+
+    #[test]
+    fn dust_loses_a_level_every_block() {
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        let near = output(&mut circuit, BLOCK_SIZE * 4.0);
+        let far = output(&mut circuit, BLOCK_SIZE * 9.0);
+        connect(&mut circuit, (lever, 0), (near, 0));
+        connect(&mut circuit, (near, 0), (far, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        simulation.set_input(lever, true);
+        simulation.advance_ticks(0);
+        assert_eq!(
+            simulation.get_port_strength(PortRef::new(near, 0)),
+            Some(11)
+        );
+        assert_eq!(simulation.get_port_strength(PortRef::new(far, 0)), Some(6));
+    }
+
+    #[test]
+    fn signals_die_after_15_blocks() {
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        let last = output(&mut circuit, BLOCK_SIZE * 14.0);
+        let beyond = output(&mut circuit, BLOCK_SIZE * 15.0);
+        connect(&mut circuit, (lever, 0), (last, 0));
+        connect(&mut circuit, (lever, 0), (beyond, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        simulation.set_input(lever, true);
+        simulation.advance_ticks(0);
+        assert_eq!(simulation.get_port_strength(PortRef::new(last, 0)), Some(1));
+        assert_eq!(
+            simulation.get_port_strength(PortRef::new(beyond, 0)),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn transported_pulses_pass_unchanged() {
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        let torch = circuit
+            .add_component(part(ComponentType::Torch, 0.0, 1, 1).with_timing(Timing::torch()));
+        connect(&mut circuit, (lever, 0), (torch, 0));
+        let mut simulation = Simulation::new(&circuit);
+        simulation.advance_ticks(2);
+        let output = PortRef::new(torch, 1);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+
+        // a pulse of a single game tick
+        simulation.set_input(lever, true);
+        simulation.advance_ticks(1);
+        simulation.set_input(lever, false);
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+    }
+
+    #[test]
+    fn extended_pulses_last_as_long_as_the_delay() {
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        let repeater = circuit.add_component(
+            part(ComponentType::Repeater(2), 0.0, 1, 1).with_timing(Timing::repeater(2)),
+        );
+        connect(&mut circuit, (lever, 0), (repeater, 0));
+        let mut simulation = Simulation::new(&circuit);
+        let output = PortRef::new(repeater, 1);
+
+        // a pulse of a single game tick
+        simulation.set_input(lever, true);
+        simulation.advance_ticks(1);
+        simulation.set_input(lever, false);
+        simulation.advance_ticks(3);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+        simulation.advance_ticks(3);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+    }
+
+    /// A latch or flip-flop with its inputs set by levers, returns the levers and the latch or flip-flop
+    fn sequential(
+        circuit: &mut Circuit,
+        sequential_type: SequentialType,
+    ) -> (Vec<ComponentId>, ComponentId) {
+        let inputs = sequential_type.get_inputs().len();
+        let timing = Timing::new(sequential_type.get_delay(), PulseBehaviour::Transport);
+        let component = circuit.add_component(
+            part(ComponentType::Sequential(sequential_type), 0.0, inputs, 2).with_timing(timing),
+        );
+        let levers = (0..inputs)
+            .map(|input| {
+                let lever = lever(circuit);
+                connect(circuit, (lever, 0), (component, input));
+                lever
+            })
+            .collect();
+        (levers, component)
+    }
+    /// Sets the lever and waits until the outputs followed
+    fn set(simulation: &mut Simulation, lever: ComponentId, value: bool) {
+        simulation.set_input(lever, value);
+        simulation.advance_ticks(10);
+    }
+    fn stored(simulation: &Simulation, id: ComponentId) -> bool {
+        let element = simulation.elements.iter().find(|e| e.id == id).unwrap();
+        let [q, inverse] =
+            [0, 1].map(|output| simulation.drivers[element.outputs[output]].strength > 0);
+        assert_ne!(q, inverse, "the outputs are not each other's inverse");
+        q
+    }
+
+    #[test]
+    fn d_flip_flops_store_on_the_rising_clock_edge_only() {
+        let mut circuit = Circuit::new();
+        let (levers, flip_flop) = sequential(&mut circuit, SequentialType::DFlipFlop);
+        let (d, clock) = (levers[0], levers[1]);
+        let mut simulation = Simulation::new(&circuit);
+        simulation.advance_ticks(10);
+
+        set(&mut simulation, d, true);
+        assert!(!stored(&simulation, flip_flop));
+        simulation.set_input(clock, true);
+        simulation.advance_ticks(3);
+        assert!(!stored(&simulation, flip_flop));
+        simulation.advance_ticks(1);
+        assert!(stored(&simulation, flip_flop));
+        // changes while the clock is high or falling are ignored
+        set(&mut simulation, d, false);
+        assert!(stored(&simulation, flip_flop));
+        set(&mut simulation, clock, false);
+        assert!(stored(&simulation, flip_flop));
+        set(&mut simulation, clock, true);
+        assert!(!stored(&simulation, flip_flop));
+    }
+
+    #[test]
+    fn t_flip_flops_toggle_on_every_rising_clock_edge() {
+        let mut circuit = Circuit::new();
+        let (levers, flip_flop) = sequential(&mut circuit, SequentialType::TFlipFlop);
+        let mut simulation = Simulation::new(&circuit);
+        simulation.advance_ticks(10);
+
+        for expected in [true, false, true] {
+            set(&mut simulation, levers[0], true);
+            assert_eq!(stored(&simulation, flip_flop), expected);
+            set(&mut simulation, levers[0], false);
+            assert_eq!(stored(&simulation, flip_flop), expected);
+        }
+    }
+
+    #[test]
+    fn jk_flip_flops_set_reset_keep_and_toggle_on_the_clock_edge() {
+        let mut circuit = Circuit::new();
+        let (levers, flip_flop) = sequential(&mut circuit, SequentialType::JkFlipFlop);
+        let (j, clock, k) = (levers[0], levers[1], levers[2]);
+        let mut simulation = Simulation::new(&circuit);
+        simulation.advance_ticks(10);
+        let tick = |simulation: &mut Simulation| {
+            set(simulation, clock, true);
+            set(simulation, clock, false);
+            stored(simulation, flip_flop)
+        };
+
+        set(&mut simulation, j, true);
+        assert!(!stored(&simulation, flip_flop));
+        assert!(tick(&mut simulation));
+        set(&mut simulation, j, false);
+        assert!(tick(&mut simulation));
+        set(&mut simulation, k, true);
+        assert!(!tick(&mut simulation));
+        set(&mut simulation, j, true);
+        assert!(tick(&mut simulation));
+        assert!(!tick(&mut simulation));
+    }
+
+    #[test]
+    fn d_latches_are_transparent_while_enabled() {
+        let mut circuit = Circuit::new();
+        let (levers, latch) = sequential(&mut circuit, SequentialType::DLatch);
+        let (d, enable) = (levers[0], levers[1]);
+        let mut simulation = Simulation::new(&circuit);
+        simulation.advance_ticks(10);
+
+        set(&mut simulation, d, true);
+        assert!(!stored(&simulation, latch));
+        set(&mut simulation, enable, true);
+        assert!(stored(&simulation, latch));
+        set(&mut simulation, d, false);
+        assert!(!stored(&simulation, latch));
+        set(&mut simulation, d, true);
+        assert!(stored(&simulation, latch));
+        set(&mut simulation, enable, false);
+        set(&mut simulation, d, false);
+        assert!(stored(&simulation, latch));
+    }
+
+    #[test]
+    fn sr_latches_keep_their_value_until_reset() {
+        let mut circuit = Circuit::new();
+        let (levers, latch) = sequential(&mut circuit, SequentialType::SrLatch);
+        let (set_input, reset) = (levers[0], levers[1]);
+        let mut simulation = Simulation::new(&circuit);
+        simulation.advance_ticks(10);
+
+        set(&mut simulation, set_input, true);
+        set(&mut simulation, set_input, false);
+        assert!(stored(&simulation, latch));
+        // both at once keep the value
+        set(&mut simulation, set_input, true);
+        set(&mut simulation, reset, true);
+        assert!(stored(&simulation, latch));
+        set(&mut simulation, set_input, false);
+        assert!(!stored(&simulation, latch));
+    }
+
+    #[test]
+    fn clocks_toggle_every_half_period() {
+        let mut circuit = Circuit::new();
+        let clock = circuit.add_component(part(ComponentType::Clock(4), 0.0, 0, 1));
+        let mut simulation = Simulation::new(&circuit);
+        let output = PortRef::new(clock, 0);
+
+        // a period of 4 redstone ticks is on for 4 game ticks and off for 4
+        simulation.advance_ticks(0);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+        simulation.advance_ticks(3);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+        simulation.advance_ticks(3);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+    }
+
+    #[test]
+    fn monostables_emit_a_pulse_of_their_length_on_the_rising_edge() {
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        let monostable = circuit.add_component(
+            part(ComponentType::Monostable(3), 0.0, 1, 1).with_timing(Timing::comparator()),
+        );
+        connect(&mut circuit, (lever, 0), (monostable, 0));
+        let mut simulation = Simulation::new(&circuit);
+        let output = PortRef::new(monostable, 1);
+
+        // after the delay of 2 game ticks the output is on for 3 redstone ticks
+        simulation.set_input(lever, true);
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+        simulation.advance_ticks(5);
+        assert_eq!(simulation.get_port_strength(output), Some(15));
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+        // the input staying on or falling does not start another pulse
+        simulation.advance_ticks(10);
+        set(&mut simulation, lever, false);
+        assert!(simulation.is_stable());
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+    }
+
+    #[test]
+    fn filtered_change_to_another_strength_propagates() {
+        let mut circuit = Circuit::new();
+        let id = circuit.add_component(comparator());
+        let mut simulation = Simulation::new(&circuit);
+        let output = PortRef::new(id, 2);
+        let Some(PortState::Driver(driver)) = simulation.ports.get(&output).copied() else {
+            panic!("the comparator has no output");
+        };
+
+        simulation.drive(driver, MAX_SIGNAL_STRENGTH, Timing::comparator());
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+        // the input changes again before the first change reached the output
+        simulation.drive(driver, 7, Timing::comparator());
+        simulation.advance_ticks(4);
+        assert_eq!(simulation.get_port_strength(output), Some(7));
+        assert!(simulation.is_stable());
+    }
+
+    #[test]
+    fn filtered_short_pulse_is_dropped() {
+        let mut circuit = Circuit::new();
+        let id = circuit.add_component(comparator());
+        let mut simulation = Simulation::new(&circuit);
+        let output = PortRef::new(id, 2);
+        let Some(PortState::Driver(driver)) = simulation.ports.get(&output).copied() else {
+            panic!("the comparator has no output");
+        };
+
+        simulation.drive(driver, MAX_SIGNAL_STRENGTH, Timing::comparator());
+        simulation.advance_ticks(1);
+        simulation.drive(driver, 0, Timing::comparator());
+        simulation.advance_ticks(4);
+        assert_eq!(simulation.get_port_strength(output), Some(0));
+        assert!(simulation.is_stable());
+    }
+
+    #[test]
+    fn narrow_driver_only_reaches_its_bits_of_a_bus_wire() {
+        let mut circuit = Circuit::new();
+        let bit = circuit.add_component(pin(PinDirection::Input, 1));
+        let bus = circuit.add_component(pin(PinDirection::Input, 4));
+        let output = circuit.add_component(pin(PinDirection::Output, 4));
+        let wire = circuit
+            .add_wire(PortRef::new(bit, 0), PortRef::new(output, 0))
+            .unwrap();
+        circuit.add_wire(PortRef::new(bus, 0), PortRef::new(output, 0));
+        let mut simulation = Simulation::new(&circuit);
+
+        simulation.set_input(bit, true);
+        simulation.set_input_bits(bus, 0b0001);
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_wire_bits(wire), Some(0b0001));
+        simulation.set_input(bit, false);
+        simulation.set_input_bits(bus, 0b1000);
+        simulation.advance_ticks(1);
+        assert_eq!(simulation.get_wire_bits(wire), Some(0b1000));
+    }
+}
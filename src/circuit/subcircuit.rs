@@ -0,0 +1,258 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Circuit, Component, ComponentId, ComponentType, ConnectionKind, PinDirection, PortRef,
+};
+
+/// Deepest nesting of subcircuits that is flattened, deeper instances are left out
+pub const MAX_SUBCIRCUIT_DEPTH: usize = 16;
+
+/// Identity of a user-defined component in a [`SubcircuitLibrary`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SubcircuitId(u32);
+
+impl Display for SubcircuitId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A user-defined component. The pins of its circuit become the ports of its instances.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subcircuit {
+    name: String,
+    circuit: Circuit,
+}
+
+impl Subcircuit {
+    pub const fn new(name: String, circuit: Circuit) -> Self {
+        Self { name, circuit }
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub const fn get_circuit(&self) -> &Circuit {
+        &self.circuit
+    }
+
+    /// Returns the pins that become the ports of the instances, ordered from top to bottom and left to right
+    pub fn get_pins(&self) -> Vec<ComponentId> {
+        get_port_pins(self.circuit.get_components().iter())
+    }
+    /// Returns the name of every port, the labels of the pins
+    pub fn get_port_labels(&self) -> Vec<&str> {
+        self.get_pins()
+            .into_iter()
+            .filter_map(|pin| self.circuit.get_component(pin))
+            .map(Component::get_label)
+            .collect()
+    }
+    /// Returns the number of bits of every port, the widths of the pins
+    pub fn get_port_widths(&self) -> Vec<u8> {
+        self.get_pins()
+            .into_iter()
+            .filter_map(
+                |pin| match self.circuit.get_component(pin)?.get_component_type() {
+                    ComponentType::Pin(_, width) => Some(width),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+    /// Returns the kind of every port as seen from outside. A bidirectional pin connected to an output inside the
+    /// subcircuit is an output.
+    pub fn get_port_kinds(&self) -> Vec<ConnectionKind> {
+        self.get_pins()
+            .into_iter()
+            .map(|pin| {
+                match self
+                    .circuit
+                    .get_component(pin)
+                    .map(Component::get_component_type)
+                {
+                    Some(ComponentType::Pin(PinDirection::Input, _)) => ConnectionKind::Input,
+                    Some(ComponentType::Pin(PinDirection::Output, _)) => ConnectionKind::Output,
+                    _ if self.is_driven(pin) => ConnectionKind::Output,
+                    _ => ConnectionKind::Input,
+                }
+            })
+            .collect()
+    }
+    /// Returns whether another component inside the subcircuit drives the net of the pin
+    fn is_driven(&self, pin: ComponentId) -> bool {
+        self.circuit
+            .get_net_of(PortRef::new(pin, 0))
+            .is_some_and(|net| {
+                net.get_ports()
+                    .iter()
+                    .filter(|p| p.component != pin)
+                    .any(|p| {
+                        self.circuit
+                            .get_component(p.component)
+                            .and_then(|c| c.get_ports().get(p.port))
+                            .is_some_and(|cp| cp.get_kind() == ConnectionKind::Output)
+                    })
+            })
+    }
+}
+
+/// Returns the pins among the components in the order of the ports of a subcircuit made of them
+pub fn get_port_pins<'a>(components: impl Iterator<Item = &'a Component>) -> Vec<ComponentId> {
+    let mut pins: Vec<_> = components
+        .filter(|c| matches!(c.get_component_type(), ComponentType::Pin(..)))
+        .collect();
+    pins.sort_by(|a, b| {
+        let (a, b) = (a.get_position(), b.get_position());
+        a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0))
+    });
+    pins.into_iter().map(Component::get_id).collect()
+}
+
+/// Returns for every port of a subcircuit made of the circuit `before` the port taking its place once the circuit was
+/// edited into `after`, `None` if the port is gone. Ports follow their pin wherever it was moved,
+/// a deleted pin is replaced by a new pin with the same label.
+pub fn map_port_pins(before: &Circuit, after: &Circuit) -> Vec<Option<usize>> {
+    fn label_of(circuit: &Circuit, pin: ComponentId) -> &str {
+        circuit.get_component(pin).map_or("", Component::get_label)
+    }
+    let old = get_port_pins(before.get_components().iter());
+    let new = get_port_pins(after.get_components().iter());
+    old.iter()
+        .map(|pin| {
+            new.iter().position(|p| p == pin).or_else(|| {
+                let label = label_of(before, *pin);
+                new.iter().position(|p| {
+                    !label.is_empty() && !old.contains(p) && label_of(after, *p) == label
+                })
+            })
+        })
+        .collect()
+}
+
+/// The user-defined components of a design
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubcircuitLibrary {
+    subcircuits: BTreeMap<SubcircuitId, Subcircuit>,
+    next_id: u32,
+}
+
+impl SubcircuitLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the subcircuit and returns its newly assigned id
+    pub fn add(&mut self, subcircuit: Subcircuit) -> SubcircuitId {
+        let id = SubcircuitId(self.next_id);
+        self.next_id += 1;
+        self.subcircuits.insert(id, subcircuit);
+        id
+    }
+    /// Adds the subcircuit under the id it was saved with, e.g. when loading a project
+    pub fn restore(&mut self, id: SubcircuitId, subcircuit: Subcircuit) {
+        self.next_id = self.next_id.max(id.0 + 1);
+        self.subcircuits.insert(id, subcircuit);
+    }
+    /// Replaces the circuit of the subcircuit after it was edited and returns the replaced circuit
+    pub fn replace_circuit(&mut self, id: SubcircuitId, circuit: Circuit) -> Option<Circuit> {
+        let subcircuit = self.subcircuits.get_mut(&id)?;
+        Some(std::mem::replace(&mut subcircuit.circuit, circuit))
+    }
+    pub fn get(&self, id: SubcircuitId) -> Option<&Subcircuit> {
+        self.subcircuits.get(&id)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (SubcircuitId, &Subcircuit)> {
+        self.subcircuits.iter().map(|(id, s)| (*id, s))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.subcircuits.is_empty()
+    }
+
+    /// Returns whether the circuit contains an instance of the subcircuit, directly or within another subcircuit
+    pub fn uses(&self, circuit: &Circuit, id: SubcircuitId) -> bool {
+        self.uses_within(circuit, id, MAX_SUBCIRCUIT_DEPTH)
+    }
+    fn uses_within(&self, circuit: &Circuit, id: SubcircuitId, depth: usize) -> bool {
+        depth > 0
+            && circuit.get_components().iter().any(|c| {
+                let ComponentType::Subcircuit(used) = c.get_component_type() else {
+                    return false;
+                };
+                used == id
+                    || self
+                        .get(used)
+                        .is_some_and(|s| self.uses_within(&s.circuit, id, depth - 1))
+            })
+    }
+
+    /// Replaces every instance of a subcircuit by its content, so the circuit can be simulated and exported.
+    /// Components and wires outside of the instances keep their ids, wires to an instance are connected to the
+    /// components inside it instead.
+    pub fn flatten(&self, circuit: &Circuit) -> Circuit {
+        self.flatten_within(circuit, MAX_SUBCIRCUIT_DEPTH)
+    }
+    fn flatten_within(&self, circuit: &Circuit, depth: usize) -> Circuit {
+        let mut flat = circuit.clone();
+        for component in circuit.get_components() {
+            let ComponentType::Subcircuit(id) = component.get_component_type() else {
+                continue;
+            };
+            let Some(subcircuit) = self.get(id).filter(|_| depth > 0) else {
+                flat.remove_component(component.get_id());
+                continue;
+            };
+            let content = self.flatten_within(&subcircuit.circuit, depth - 1);
+            flat.inline(component.get_id(), &content, &subcircuit.get_pins());
+        }
+        flat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pin(y: f64, label: &str) -> Component {
+        Component::new(
+            ComponentType::Pin(PinDirection::Input, 1),
+            (0.0, y),
+            (25.0, 50.0),
+            Vec::new(),
+        )
+        .with_label(label.to_owned())
+    }
+
+    #[test]
+    fn ports_follow_their_moved_pins() {
+        let mut before = Circuit::new();
+        let a = before.add_component(pin(0.0, "a"));
+        let b = before.add_component(pin(50.0, "b"));
+        let mut after = before.clone();
+        let mut moved = after.get_component(a).cloned().unwrap();
+        moved.set_position((0.0, 100.0));
+        after.replace_component(moved);
+
+        assert_eq!(get_port_pins(after.get_components().iter()), [b, a]);
+        assert_eq!(map_port_pins(&before, &after), [Some(1), Some(0)]);
+    }
+
+    #[test]
+    fn deleted_pins_are_replaced_by_pins_with_their_label() {
+        let mut before = Circuit::new();
+        let a = before.add_component(pin(0.0, "a"));
+        before.add_component(pin(50.0, "b"));
+        before.add_component(pin(100.0, ""));
+        let mut after = before.clone();
+        after.remove_component(a);
+        after.add_component(pin(150.0, "a"));
+
+        assert_eq!(map_port_pins(&before, &after), [Some(2), Some(0), Some(1)]);
+        let mut unlabeled = before.clone();
+        let last = get_port_pins(unlabeled.get_components().iter())[2];
+        unlabeled.remove_component(last);
+        unlabeled.add_component(pin(150.0, ""));
+        assert_eq!(map_port_pins(&before, &unlabeled), [Some(0), Some(1), None]);
+    }
+}
@@ -0,0 +1,70 @@
+/// Duration of a game tick in milliseconds
+pub const GAME_TICK_MS: u32 = 50;
+/// Number of game ticks in one redstone tick
+pub const GAME_TICKS_PER_REDSTONE_TICK: u64 = 2;
+/// Signal strength emitted by a powered component
+pub const MAX_SIGNAL_STRENGTH: u8 = 15;
+/// Length of one block in circuit coordinates, one block of dust weakens the signal by one
+pub const BLOCK_SIZE: f64 = 25.0;
+
+/// How a component reacts to pulses on its inputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PulseBehaviour {
+    /// Every pulse is passed on unchanged, e.g. a redstone torch
+    Transport,
+    /// Pulses shorter than the delay are extended to the delay, e.g. a repeater
+    Extend,
+    /// Pulses shorter than the given number of redstone ticks are dropped, e.g. a comparator
+    Filter(u8),
+}
+
+/// Propagation delay and pulse handling of a component
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Timing {
+    /// delay in redstone ticks
+    delay: u8,
+    pulse_behaviour: PulseBehaviour,
+}
+
+impl Timing {
+    /// The timing of components reacting instantly, e.g. dust or levers
+    pub const INSTANT: Self = Self::new(0, PulseBehaviour::Transport);
+
+    pub const fn new(delay: u8, pulse_behaviour: PulseBehaviour) -> Self {
+        Self {
+            delay,
+            pulse_behaviour,
+        }
+    }
+    pub const fn torch() -> Self {
+        Self::new(1, PulseBehaviour::Transport)
+    }
+    /// A repeater with a delay of 1 to 4 redstone ticks
+    pub const fn repeater(delay: u8) -> Self {
+        let delay = if delay < 1 {
+            1
+        } else if delay > 4 {
+            4
+        } else {
+            delay
+        };
+        Self::new(delay, PulseBehaviour::Extend)
+    }
+    pub const fn comparator() -> Self {
+        Self::new(1, PulseBehaviour::Filter(1))
+    }
+
+    /// Delay in game ticks
+    pub const fn get_delay_game_ticks(&self) -> u64 {
+        self.delay as u64 * GAME_TICKS_PER_REDSTONE_TICK
+    }
+    pub const fn get_pulse_behaviour(&self) -> PulseBehaviour {
+        self.pulse_behaviour
+    }
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self::INSTANT
+    }
+}
@@ -0,0 +1,339 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use super::{Circuit, ComponentId, ConnectionPoint, Edit, PortRef, WireId};
+
+/// Extra cost of a bend, so the router prefers straight wires
+const BEND_COST: u32 = 3;
+/// Extra cost of running along another wire, so wires only share the grid where they have to
+const OVERLAP_COST: u32 = 4;
+/// Grid cells around the components the wires may leave the design by
+const ROUTING_MARGIN: i32 = 4;
+
+/// A point of the routing grid, counted in grid spacings
+type GridPoint = (i32, i32);
+
+/// The steps up, right, down and left, in the order of the directions of a [`ConnectionPoint`]
+const STEPS: [GridPoint; 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Finds an orthogonal path from one port to another around the components of the circuit.
+/// The wire leaves and enters the ports only in the directions they allow.
+/// The corners lie on the grid with the given spacing, the one components snap to.
+/// Returns the corners of the path, without the ports themselves.
+pub fn route_wire(
+    circuit: &Circuit,
+    from: PortRef,
+    to: PortRef,
+    spacing: f64,
+) -> Option<Vec<(f64, f64)>> {
+    route_wire_ignoring(circuit, from, to, None, spacing)
+}
+
+/// Routes every wire attached to the components again, e.g. after they were moved or turned.
+/// Wires that can't be routed anymore run straight.
+pub fn reroute_wires(circuit: &Circuit, components: &[ComponentId], spacing: f64) -> Vec<Edit> {
+    circuit
+        .get_wires()
+        .iter()
+        .filter(|w| {
+            components.contains(&w.get_from().component)
+                || components.contains(&w.get_to().component)
+        })
+        .filter_map(|wire| {
+            let path = route_wire_ignoring(
+                circuit,
+                wire.get_from(),
+                wire.get_to(),
+                Some(wire.get_id()),
+                spacing,
+            )
+            .unwrap_or_default();
+            (path != wire.get_path()).then(|| Edit::ReplaceWire {
+                before: wire.clone(),
+                after: wire.clone().with_path(path),
+            })
+        })
+        .collect()
+}
+
+fn route_wire_ignoring(
+    circuit: &Circuit,
+    from: PortRef,
+    to: PortRef,
+    ignored_wire: Option<WireId>,
+    spacing: f64,
+) -> Option<Vec<(f64, f64)>> {
+    let source = circuit.get_port(from)?;
+    let target = circuit.get_port(to)?;
+    let start = to_grid(&source, spacing);
+    let end = to_grid(&target, spacing);
+    if start == end {
+        return Some(Vec::new());
+    }
+
+    let (blocked, bounds) = get_obstacles(circuit, start, end, spacing);
+    let wired = get_wired_points(circuit, ignored_wire, spacing);
+    let allowed = |point: &ConnectionPoint, step: usize| match step {
+        0 => point.get_direction_y_neg(),
+        1 => point.get_direction_x_pos(),
+        2 => point.get_direction_y_pos(),
+        _ => point.get_direction_x_neg(),
+    };
+    let ((min_x, min_y), (max_x, max_y)) = bounds;
+    let heuristic = |p: GridPoint| p.0.abs_diff(end.0) + p.1.abs_diff(end.1);
+
+    // a state is a point and the step that led there, 4 for the start
+    let mut costs: HashMap<(GridPoint, usize), u32> = HashMap::from([((start, 4), 0)]);
+    let mut previous: HashMap<(GridPoint, usize), (GridPoint, usize)> = HashMap::new();
+    let mut queue = BinaryHeap::from([Reverse((heuristic(start), 0, start, 4))]);
+    while let Some(Reverse((_, cost, point, step))) = queue.pop() {
+        if point == end {
+            let mut points = vec![point];
+            let mut state = (point, step);
+            while let Some(p) = previous.get(&state) {
+                points.push(p.0);
+                state = *p;
+            }
+            points.reverse();
+            return Some(get_corners(&points, spacing));
+        }
+        if costs.get(&(point, step)).is_some_and(|c| *c < cost) {
+            continue;
+        }
+        for (next_step, offset) in STEPS.iter().enumerate() {
+            // never turn back, and leave the source only where it allows
+            if step == (next_step + 2) % 4 || step == 4 && !allowed(&source, next_step) {
+                continue;
+            }
+            let next = (point.0 + offset.0, point.1 + offset.1);
+            if next.0 < min_x || next.0 > max_x || next.1 < min_y || next.1 > max_y {
+                continue;
+            }
+            if next == end {
+                // the wire enters the target from the side it points to
+                if !allowed(&target, (next_step + 2) % 4) {
+                    continue;
+                }
+            } else if blocked.contains(&next) {
+                continue;
+            }
+            let mut next_cost = cost + 1;
+            if step != 4 && step != next_step {
+                next_cost += BEND_COST;
+            }
+            if wired.contains(&next) {
+                next_cost += OVERLAP_COST;
+            }
+            if costs.get(&(next, next_step)).is_none_or(|c| next_cost < *c) {
+                costs.insert((next, next_step), next_cost);
+                previous.insert((next, next_step), (point, step));
+                queue.push(Reverse((
+                    next_cost + heuristic(next),
+                    next_cost,
+                    next,
+                    next_step,
+                )));
+            }
+        }
+    }
+    None
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn to_grid(point: &ConnectionPoint, spacing: f64) -> GridPoint {
+    (
+        (point.get_position_x() / spacing).round() as i32,
+        (point.get_position_y() / spacing).round() as i32,
+    )
+}
+
+/// Returns the points covered by components, including their outline,
+/// and the area the wire may be routed in
+#[allow(clippy::cast_possible_truncation)]
+fn get_obstacles(
+    circuit: &Circuit,
+    start: GridPoint,
+    end: GridPoint,
+    spacing: f64,
+) -> (HashSet<GridPoint>, (GridPoint, GridPoint)) {
+    let mut blocked = HashSet::new();
+    let mut min = (start.0.min(end.0), start.1.min(end.1));
+    let mut max = (start.0.max(end.0), start.1.max(end.1));
+    for component in circuit.get_components() {
+        let (x, y) = component.get_position();
+        let left = (x / spacing).floor() as i32;
+        let top = (y / spacing).floor() as i32;
+        let right = ((x + component.get_width()) / spacing).ceil() as i32;
+        let bottom = ((y + component.get_height()) / spacing).ceil() as i32;
+        for gx in left..=right {
+            for gy in top..=bottom {
+                blocked.insert((gx, gy));
+            }
+        }
+        min = (min.0.min(left), min.1.min(top));
+        max = (max.0.max(right), max.1.max(bottom));
+    }
+    (
+        blocked,
+        (
+            (min.0 - ROUTING_MARGIN, min.1 - ROUTING_MARGIN),
+            (max.0 + ROUTING_MARGIN, max.1 + ROUTING_MARGIN),
+        ),
+    )
+}
+
+/// Returns the points the orthogonal wires of the circuit run through
+#[allow(clippy::cast_possible_truncation)]
+fn get_wired_points(
+    circuit: &Circuit,
+    ignored_wire: Option<WireId>,
+    spacing: f64,
+) -> HashSet<GridPoint> {
+    let mut wired = HashSet::new();
+    for wire in circuit.get_wires() {
+        if Some(wire.get_id()) == ignored_wire {
+            continue;
+        }
+        let points = circuit.get_wire_points(wire.get_id()).unwrap_or_default();
+        for segment in points.windows(2) {
+            let a = (
+                (segment[0].0 / spacing).round() as i32,
+                (segment[0].1 / spacing).round() as i32,
+            );
+            let b = (
+                (segment[1].0 / spacing).round() as i32,
+                (segment[1].1 / spacing).round() as i32,
+            );
+            if a.0 == b.0 {
+                (a.1.min(b.1)..=a.1.max(b.1)).for_each(|y| {
+                    wired.insert((a.0, y));
+                });
+            } else if a.1 == b.1 {
+                (a.0.min(b.0)..=a.0.max(b.0)).for_each(|x| {
+                    wired.insert((x, a.1));
+                });
+            }
+        }
+    }
+    wired
+}
+
+/// Returns the points of the path where it changes its direction
+fn get_corners(points: &[GridPoint], spacing: f64) -> Vec<(f64, f64)> {
+    points
+        .windows(3)
+        .filter(|p| {
+            let first = (p[1].0 - p[0].0, p[1].1 - p[0].1);
+            let second = (p[2].0 - p[1].0, p[2].1 - p[1].1);
+            first != second
+        })
+        .map(|p| (f64::from(p[1].0) * spacing, f64::from(p[1].1) * spacing))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{Component, ComponentType, ConnectionKind};
+
+    /// A component with a single port at the offset, which a wire may only use in the direction
+    fn add(
+        circuit: &mut Circuit,
+        component_type: ComponentType,
+        position: (f64, f64),
+        port: (f64, f64),
+        direction: usize,
+    ) -> PortRef {
+        let mut directions = [false; 4];
+        directions[direction] = true;
+        let kind = match component_type {
+            ComponentType::Lever => ConnectionKind::Output,
+            _ => ConnectionKind::Input,
+        };
+        let ports = vec![ConnectionPoint::new(port.0, port.1, directions, kind)];
+        let id = circuit.add_component(Component::new(
+            component_type,
+            position,
+            (25.0, 25.0),
+            ports,
+        ));
+        PortRef::new(id, 0)
+    }
+    /// A lever driving a lamp further right and down
+    fn lever_and_lamp(circuit: &mut Circuit, lamp: (f64, f64)) -> (PortRef, PortRef) {
+        let lever = add(circuit, ComponentType::Lever, (0.0, 0.0), (25.0, 0.0), 1);
+        let lamp = add(circuit, ComponentType::Lamp, lamp, (0.0, 0.0), 3);
+        (lever, lamp)
+    }
+    /// Returns the whole path of the wire, from the source port through the corners to the target port
+    fn route(circuit: &Circuit, from: PortRef, to: PortRef, spacing: f64) -> Vec<(f64, f64)> {
+        let position = |port| {
+            let point = circuit.get_port(port).unwrap();
+            (point.get_position_x(), point.get_position_y())
+        };
+        let corners = route_wire(circuit, from, to, spacing).unwrap();
+        [position(from)]
+            .into_iter()
+            .chain(corners)
+            .chain([position(to)])
+            .collect()
+    }
+
+    #[test]
+    fn routes_only_run_horizontally_and_vertically() {
+        let mut circuit = Circuit::new();
+        let (lever, lamp) = lever_and_lamp(&mut circuit, (200.0, 100.0));
+
+        let path = route(&circuit, lever, lamp, 25.0);
+        assert!(path.len() > 2);
+        for segment in path.windows(2) {
+            assert!(
+                segment[0].0 == segment[1].0 || segment[0].1 == segment[1].1,
+                "{segment:?}"
+            );
+        }
+        // the wire leaves the lever to the right and enters the lamp from the left
+        assert_eq!(path[1].1, 0.0);
+        assert_eq!(path[path.len() - 2].1, 100.0);
+    }
+
+    #[test]
+    fn routes_go_around_components() {
+        let mut circuit = Circuit::new();
+        let (lever, lamp) = lever_and_lamp(&mut circuit, (200.0, 0.0));
+        let gate = Component::new(
+            ComponentType::Lamp,
+            (100.0, -50.0),
+            (50.0, 100.0),
+            Vec::new(),
+        );
+        circuit.add_component(gate);
+
+        let path = route(&circuit, lever, lamp, 25.0);
+        let crosses = |a: (f64, f64), b: (f64, f64)| {
+            let (left, right) = (a.0.min(b.0), a.0.max(b.0));
+            let (top, bottom) = (a.1.min(b.1), a.1.max(b.1));
+            left <= 150.0 && right >= 100.0 && top <= 50.0 && bottom >= -50.0
+        };
+        assert!(path.len() > 2);
+        for segment in path.windows(2) {
+            assert!(!crosses(segment[0], segment[1]), "{segment:?}");
+        }
+    }
+
+    #[test]
+    fn corners_snap_to_the_grid_spacing() {
+        let mut circuit = Circuit::new();
+        let (lever, lamp) = lever_and_lamp(&mut circuit, (200.0, 110.0));
+
+        for spacing in [10.0, 25.0] {
+            let corners = route_wire(&circuit, lever, lamp, spacing).unwrap();
+            assert!(!corners.is_empty());
+            for (x, y) in corners {
+                assert_eq!((x % spacing, y % spacing), (0.0, 0.0), "{spacing}");
+            }
+        }
+    }
+}
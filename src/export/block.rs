@@ -0,0 +1,359 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+/// Position of a block in a layout as (x, y, z), y pointing up
+pub type BlockPosition = (i32, i32, i32);
+
+/// Number of characters that fit on a line of a sign
+pub const SIGN_LINE_LENGTH: usize = 15;
+/// Number of lines on a sign
+pub const SIGN_LINES: usize = 4;
+/// The dye colors text on signs can have, with their color as rgb
+const DYE_COLORS: [(&str, (u8, u8, u8)); 16] = [
+    ("white", (0xff, 0xff, 0xff)),
+    ("orange", (0xff, 0x68, 0x1f)),
+    ("magenta", (0xff, 0x00, 0xff)),
+    ("light_blue", (0x9a, 0xc0, 0xcd)),
+    ("yellow", (0xff, 0xff, 0x00)),
+    ("lime", (0xbf, 0xff, 0x00)),
+    ("pink", (0xff, 0x69, 0xb4)),
+    ("gray", (0x80, 0x80, 0x80)),
+    ("light_gray", (0xd3, 0xd3, 0xd3)),
+    ("cyan", (0x00, 0xff, 0xff)),
+    ("purple", (0xa0, 0x20, 0xf0)),
+    ("blue", (0x00, 0x00, 0xff)),
+    ("brown", (0x8b, 0x45, 0x13)),
+    ("green", (0x00, 0xff, 0x00)),
+    ("red", (0xff, 0x00, 0x00)),
+    ("black", (0x00, 0x00, 0x00)),
+];
+
+/// A horizontal direction in the world. North is the negative z axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    /// Returns the direction from one block to the neighbouring block, `None` if they are not horizontal neighbours
+    pub fn between(from: BlockPosition, to: BlockPosition) -> Option<Self> {
+        match (to.0 - from.0, to.1 - from.1, to.2 - from.2) {
+            (0, 0, -1) => Some(Self::North),
+            (1, 0, 0) => Some(Self::East),
+            (0, 0, 1) => Some(Self::South),
+            (-1, 0, 0) => Some(Self::West),
+            _ => None,
+        }
+    }
+    pub const fn get_opposite(&self) -> Self {
+        match self {
+            Self::North => Self::South,
+            Self::East => Self::West,
+            Self::South => Self::North,
+            Self::West => Self::East,
+        }
+    }
+    /// Returns the offset of the neighbouring block in this direction as (x, z)
+    pub const fn get_offset(&self) -> (i32, i32) {
+        match self {
+            Self::North => (0, -1),
+            Self::East => (1, 0),
+            Self::South => (0, 1),
+            Self::West => (-1, 0),
+        }
+    }
+    pub const fn get_name(&self) -> &'static str {
+        match self {
+            Self::North => "north",
+            Self::East => "east",
+            Self::South => "south",
+            Self::West => "west",
+        }
+    }
+}
+
+/// A block state, e.g. `minecraft:redstone_wall_torch[facing=east]`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Block {
+    name: String,
+    /// The properties sorted by name
+    properties: Vec<(String, String)>,
+}
+
+impl Block {
+    pub const AIR: &'static str = "minecraft:air";
+
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            properties: Vec::new(),
+        }
+    }
+    pub fn with_property(mut self, name: &str, value: &str) -> Self {
+        self.properties.retain(|(n, _)| n != name);
+        self.properties.push((name.to_string(), value.to_string()));
+        self.properties.sort();
+        self
+    }
+
+    pub fn solid() -> Self {
+        Self::new("minecraft:stone")
+    }
+    pub fn dust() -> Self {
+        Self::new("minecraft:redstone_wire")
+    }
+    /// A torch attached to the side of the block behind it, pointing away from it
+    pub fn wall_torch(facing: Direction) -> Self {
+        Self::new("minecraft:redstone_wall_torch").with_property("facing", facing.get_name())
+    }
+    /// A repeater passing the signal on in the given direction
+    pub fn repeater(direction: Direction) -> Self {
+        // the facing of a repeater points towards its input
+        Self::new("minecraft:repeater")
+            .with_property("facing", direction.get_opposite().get_name())
+            .with_property("delay", "1")
+    }
+    pub fn lever() -> Self {
+        Self::new("minecraft:lever")
+            .with_property("face", "floor")
+            .with_property("facing", "north")
+    }
+    pub fn lamp() -> Self {
+        Self::new("minecraft:redstone_lamp")
+    }
+    /// A comparator passing the signal on in the given direction
+    pub fn comparator(direction: Direction, subtract: bool) -> Self {
+        // like a repeater the facing points towards its input
+        Self::new("minecraft:comparator")
+            .with_property("facing", direction.get_opposite().get_name())
+            .with_property("mode", if subtract { "subtract" } else { "compare" })
+    }
+    /// An observer watching the block in the given direction, it emits its pulses to the opposite side
+    pub fn observer(facing: Direction) -> Self {
+        Self::new("minecraft:observer").with_property("facing", facing.get_name())
+    }
+    pub fn button(wooden: bool) -> Self {
+        Self::new(if wooden {
+            "minecraft:oak_button"
+        } else {
+            "minecraft:stone_button"
+        })
+        .with_property("face", "floor")
+        .with_property("facing", "north")
+    }
+    pub fn pressure_plate() -> Self {
+        Self::new("minecraft:stone_pressure_plate")
+    }
+    /// A piston pushing upwards, so it doesn't disturb the wires around it
+    pub fn piston(sticky: bool) -> Self {
+        Self::new(if sticky {
+            "minecraft:sticky_piston"
+        } else {
+            "minecraft:piston"
+        })
+        .with_property("facing", "up")
+    }
+    pub fn note_block() -> Self {
+        Self::new("minecraft:note_block")
+    }
+    /// A standing sign, turned by sixteenths of a full turn clockwise from facing south
+    pub fn sign(rotation: u8) -> Self {
+        Self::new("minecraft:oak_sign").with_property("rotation", &(rotation % 16).to_string())
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    pub fn get_properties(&self) -> &[(String, String)] {
+        &self.properties
+    }
+    pub fn get_property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Display for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.properties.is_empty() {
+            let properties: Vec<_> = self
+                .properties
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect();
+            write!(f, "[{}]", properties.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// The text written on the front of a sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignText {
+    lines: Vec<String>,
+    /// The name of the dye color of the text
+    color: &'static str,
+}
+
+impl SignText {
+    /// Wraps the text at the spaces into the lines of a sign, text that doesn't fit is left out.
+    /// The color is a CSS color as `#rrggbb`, the closest dye color is used.
+    pub fn new(text: &str, color: &str) -> Self {
+        let mut lines: Vec<String> = Vec::new();
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                let mut word: Vec<char> = word.chars().collect();
+                // words longer than a line are broken
+                while !word.is_empty() {
+                    let length = line.chars().count();
+                    let space = usize::from(length > 0);
+                    if length + space + word.len().min(SIGN_LINE_LENGTH) > SIGN_LINE_LENGTH {
+                        lines.push(std::mem::take(&mut line));
+                        continue;
+                    }
+                    let rest = word.split_off(word.len().min(SIGN_LINE_LENGTH - length - space));
+                    if space > 0 {
+                        line.push(' ');
+                    }
+                    line.extend(word);
+                    word = rest;
+                }
+            }
+            lines.push(line);
+        }
+        lines.truncate(SIGN_LINES);
+        Self {
+            lines,
+            color: Self::get_dye_color(color),
+        }
+    }
+    fn get_dye_color(color: &str) -> &'static str {
+        let channel = |range| {
+            color
+                .get(range)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+                .map_or(0, i32::from)
+        };
+        let (r, g, b) = (channel(1..3), channel(3..5), channel(5..7));
+        DYE_COLORS
+            .iter()
+            .min_by_key(|(_, (dr, dg, db))| {
+                (r - i32::from(*dr)).pow(2)
+                    + (g - i32::from(*dg)).pow(2)
+                    + (b - i32::from(*db)).pow(2)
+            })
+            .map_or("black", |(name, _)| name)
+    }
+
+    pub fn get_lines(&self) -> &[String] {
+        &self.lines
+    }
+    pub const fn get_color(&self) -> &'static str {
+        self.color
+    }
+}
+
+/// The blocks realising a design, independent of the file format it is exported in.
+/// Positions without a block are air.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockLayout {
+    blocks: BTreeMap<BlockPosition, Block>,
+    /// The text of the signs among the blocks
+    signs: BTreeMap<BlockPosition, SignText>,
+}
+
+impl BlockLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn set_block(&mut self, position: BlockPosition, block: Block) {
+        self.blocks.insert(position, block);
+        self.signs.remove(&position);
+    }
+    /// Places a standing sign with the text, `rotation` as in [`Block::sign`]
+    pub fn set_sign(&mut self, position: BlockPosition, rotation: u8, text: SignText) {
+        self.set_block(position, Block::sign(rotation));
+        self.signs.insert(position, text);
+    }
+    /// Returns the text of every sign with its position relative to the lowest corner of the bounding box, the way
+    /// the file formats store them
+    pub fn get_signs(&self) -> Vec<(BlockPosition, &SignText)> {
+        let Some((min, _)) = self.get_bounds() else {
+            return Vec::new();
+        };
+        self.signs
+            .iter()
+            .map(|(p, text)| ((p.0 - min.0, p.1 - min.1, p.2 - min.2), text))
+            .collect()
+    }
+    pub fn get_block(&self, position: BlockPosition) -> Option<&Block> {
+        self.blocks.get(&position)
+    }
+    pub fn get_blocks(&self) -> impl Iterator<Item = (&BlockPosition, &Block)> {
+        self.blocks.iter()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    /// Returns the lowest and the highest corner of the bounding box
+    pub fn get_bounds(&self) -> Option<(BlockPosition, BlockPosition)> {
+        let mut positions = self.blocks.keys();
+        let first = *positions.next()?;
+        Some(positions.fold((first, first), |(min, max), p| {
+            (
+                (min.0.min(p.0), min.1.min(p.1), min.2.min(p.2)),
+                (max.0.max(p.0), max.1.max(p.1), max.2.max(p.2)),
+            )
+        }))
+    }
+    /// Returns the size of the bounding box as (width, height, length)
+    #[allow(clippy::cast_sign_loss)]
+    pub fn get_size(&self) -> (u32, u32, u32) {
+        self.get_bounds().map_or((0, 0, 0), |(min, max)| {
+            (
+                (max.0 - min.0 + 1) as u32,
+                (max.1 - min.1 + 1) as u32,
+                (max.2 - min.2 + 1) as u32,
+            )
+        })
+    }
+    /// Returns the distinct blocks of the layout, air first
+    pub fn get_palette(&self) -> Vec<Block> {
+        let mut palette = vec![Block::new(Block::AIR)];
+        for block in self.blocks.values() {
+            if !palette.contains(block) {
+                palette.push(block.clone());
+            }
+        }
+        palette
+    }
+    /// Returns the index into the palette of every position in the bounding box, x changing fastest, then z, then y
+    #[allow(clippy::cast_sign_loss)]
+    pub fn get_palette_indices(&self, palette: &[Block]) -> Vec<usize> {
+        let Some((min, _)) = self.get_bounds() else {
+            return Vec::new();
+        };
+        let (width, height, length) = self.get_size();
+        let (width, length) = (width as usize, length as usize);
+        let mut indices = vec![0; width * height as usize * length];
+        for (position, block) in &self.blocks {
+            let (x, y, z) = (
+                (position.0 - min.0) as usize,
+                (position.1 - min.1) as usize,
+                (position.2 - min.2) as usize,
+            );
+            indices[(y * length + z) * width + x] =
+                palette.iter().position(|b| b == block).unwrap_or_default();
+        }
+        indices
+    }
+}
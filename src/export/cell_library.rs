@@ -0,0 +1,397 @@
+use crate::circuit::{
+    ButtonType, Circuit, ComparatorMode, Component, ComponentType, ConnectionKind, LogicGateType,
+    PinDirection, PortRef, SequentialType, MAX_REPEATER_DELAY, MIN_CLOCK_PERIOD,
+};
+
+use super::{Block, BlockLayout, Direction};
+
+/// The layer holding the logic, the layer below is the floor carrying dust and torches
+pub const LOGIC_LAYER: i32 = 1;
+
+/// The blocks realising a component, drawn from above with x to the right and z downwards.
+///
+/// `d` is dust, `S` a solid block, `>`, `<` and `i` a torch attached to the block on its left, on its right and below it,
+/// `1` to `4` a repeater facing east with that delay, `^` a repeater facing north locking the repeater above it, `C` and `c` a comparator facing east in compare and subtract mode,
+/// `O` an observer watching the block on its left, `L` a lamp, `N` a note block, `H` and `Y` a piston and a sticky piston,
+/// and `V`, `B`, `W` and `P` a solid block with a lever, a stone button, a wooden button and a pressure plate on top.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    kind: CellKind,
+    rows: Vec<String>,
+    /// For every port of the component the first block outside the cell the wire has to start at,
+    /// and the direction the wire has to leave the cell in. Ports the cell can't realise are `None`.
+    ports: Vec<Option<((i32, i32), Direction)>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CellKind {
+    Gate,
+    /// A pin, lever, button or pressure plate driving its net
+    Source,
+    /// A pin, lamp, piston or note block reading its net
+    Sink,
+}
+
+impl Cell {
+    fn new(kind: CellKind, rows: &[&str], ports: &[((i32, i32), Direction)]) -> Self {
+        Self {
+            kind,
+            rows: rows.iter().map(|r| (*r).to_owned()).collect(),
+            ports: ports.iter().copied().map(Some).collect(),
+        }
+    }
+
+    /// The inputs are joined by dust, one every other row, with the output in the middle
+    fn or(inputs: i32, inverted: bool) -> Self {
+        let output = if inverted { "dS>" } else { "ddd" };
+        Self::gate(inputs, |z, input| {
+            let mut row = if input { "dd" } else { ".d" }.to_owned();
+            if z == inputs {
+                row.push_str(output);
+            }
+            row
+        })
+        .with_output((5, inputs))
+    }
+    /// The inputs are inverted by torches and joined by dust, which gives a NAND
+    fn and(inputs: i32, inverted: bool) -> Self {
+        let output = if inverted { "d" } else { "S>" };
+        Self::gate(inputs, |z, input| {
+            let mut row = if input { "dS>d" } else { "...d" }.to_owned();
+            if z == inputs {
+                row.push_str(output);
+            }
+            row
+        })
+        .with_output((if inverted { 5 } else { 6 }, inputs))
+    }
+    /// A gate with an input in every other row, the dust joining them runs from the first to the last input
+    fn gate(inputs: i32, row: impl Fn(i32, bool) -> String) -> Self {
+        let mut rows = vec![String::new()];
+        rows.extend((1..2 * inputs).map(|z| row(z, z % 2 == 1)));
+        Self {
+            kind: CellKind::Gate,
+            rows,
+            ports: (0..inputs)
+                .map(|input| Some(((-1, 2 * input + 1), Direction::West)))
+                .collect(),
+        }
+    }
+    fn with_output(mut self, position: (i32, i32)) -> Self {
+        self.ports.push(Some((position, Direction::East)));
+        self
+    }
+
+    /// `A & !B | !A & B` from four NAND gates sharing the inverted inputs.
+    /// Gates with more inputs are replaced by a chain of these before placement.
+    fn xor(inputs: i32, inverted: bool) -> Self {
+        let mut cell = Self::new(
+            CellKind::Gate,
+            &[
+                "ddS>ddddS>d",
+                "...d..d...d",
+                "...d..d...d",
+                "...ddS>...d",
+                "...d..d...d",
+                "...d..d...d",
+                "ddS>ddddS>d",
+            ],
+            &[((-1, 0), Direction::West), ((-1, 6), Direction::West)],
+        );
+        cell.ports.extend((2..inputs).map(|_| None));
+        if inverted {
+            cell.rows[3].push_str("dS>");
+            cell.with_output((14, 3))
+        } else {
+            cell.with_output((11, 3))
+        }
+    }
+    fn not() -> Self {
+        Self::new(
+            CellKind::Gate,
+            &["", "dS>"],
+            &[((-1, 1), Direction::West), ((3, 1), Direction::East)],
+        )
+    }
+    fn repeater(delay: u8) -> Self {
+        Self::new(
+            CellKind::Gate,
+            &["", &delay.to_string()],
+            &[((-1, 1), Direction::West), ((1, 1), Direction::East)],
+        )
+    }
+    /// The side input enters the comparator from below
+    fn comparator(mode: ComparatorMode) -> Self {
+        let symbol = match mode {
+            ComparatorMode::Compare => "C",
+            ComparatorMode::Subtract => "c",
+        };
+        Self::new(
+            CellKind::Gate,
+            &["", symbol, ""],
+            &[
+                ((-1, 1), Direction::West),
+                ((0, 2), Direction::South),
+                ((1, 1), Direction::East),
+            ],
+        )
+    }
+    /// The observer watches the dust of its input
+    fn observer() -> Self {
+        Self::new(
+            CellKind::Gate,
+            &["", "dO"],
+            &[((-1, 1), Direction::West), ((2, 1), Direction::East)],
+        )
+    }
+    /// Two torches powering each other, the output of each one is the inverse of the other
+    fn sr_latch() -> Self {
+        Self::new(
+            CellKind::Gate,
+            &["", "dS>ddddd", ".d.d.d", ".d<S.S>d", "...d", "dddd"],
+            &[
+                ((-1, 1), Direction::West),
+                ((-1, 5), Direction::West),
+                ((8, 3), Direction::East),
+                ((8, 1), Direction::East),
+            ],
+        )
+    }
+    /// A repeater locked by a second one while the enable input is off
+    fn d_latch() -> Self {
+        Self::new(
+            CellKind::Gate,
+            &["", "d1dddd", ".^.d", ".i.d", "dS.S>d"],
+            &[
+                ((-1, 1), Direction::West),
+                ((-1, 4), Direction::West),
+                ((6, 1), Direction::East),
+                ((6, 4), Direction::East),
+            ],
+        )
+    }
+    /// Two locking repeaters in a row, the first one is locked while the clock is on and the second one while it is off
+    fn d_flip_flop() -> Self {
+        Self::new(
+            CellKind::Gate,
+            &["", "d1d1dddd", ".^.^.d", ".d.i.d", "dddS.S>d"],
+            &[
+                ((-1, 1), Direction::West),
+                ((-1, 4), Direction::West),
+                ((8, 1), Direction::East),
+                ((8, 4), Direction::East),
+            ],
+        )
+    }
+    /// A d flip-flop whose inverted output is fed back into its input
+    fn t_flip_flop() -> Self {
+        Self::new(
+            CellKind::Gate,
+            &[
+                "dddddddd",
+                "d......d",
+                "S>1d1dddd",
+                "..^.^.d",
+                "..d.i.d",
+                "ddddS.S>d",
+            ],
+            &[
+                ((-1, 5), Direction::West),
+                ((9, 2), Direction::East),
+                ((9, 5), Direction::East),
+            ],
+        )
+    }
+    /// A torch switching itself off through a loop of repeaters, which is as long as half the period
+    fn clock(period: u8) -> Self {
+        let repeaters = delays(period / 2 - 1);
+        let width = repeaters.len() + 3;
+        Self::new(
+            CellKind::Source,
+            &[
+                "",
+                &format!("S>{repeaters}d"),
+                &format!("d{}d", ".".repeat(width - 2)),
+                &"d".repeat(width),
+            ],
+            &[((width as i32, 2), Direction::East)],
+        )
+    }
+    /// A comparator subtracting the input delayed by the pulse length from the input itself
+    fn monostable(length: u8) -> Self {
+        let repeaters = delays(length);
+        let comparator = repeaters.len() + 2;
+        Self::new(
+            CellKind::Gate,
+            &[
+                "",
+                &format!("{}cd", "d".repeat(comparator)),
+                &format!(".d{}d", ".".repeat(comparator - 2)),
+                &format!(".d{repeaters}d"),
+            ],
+            &[
+                ((-1, 1), Direction::West),
+                ((comparator as i32 + 2, 1), Direction::East),
+            ],
+        )
+    }
+    /// The block below the lever, button or pressure plate powers the wire
+    fn source(symbol: &str) -> Self {
+        Self::new(
+            CellKind::Source,
+            &["", symbol],
+            &[((1, 1), Direction::East)],
+        )
+    }
+    /// The wire runs straight into the lamp, piston or note block, so the dust points into it
+    fn sink(symbol: &str) -> Self {
+        Self::new(CellKind::Sink, &["", symbol], &[((1, 1), Direction::East)])
+    }
+
+    /// A cell without blocks for components that are replaced before placement
+    fn empty(component: &Component) -> Self {
+        Self {
+            kind: CellKind::Gate,
+            rows: Vec::new(),
+            ports: vec![None; component.get_ports().len()],
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn of(component: &Component, circuit: &Circuit) -> Self {
+        let inputs = component
+            .get_ports()
+            .iter()
+            .filter(|p| p.get_kind() == ConnectionKind::Input)
+            .count() as i32;
+        match component.get_component_type() {
+            ComponentType::LogicGate(gate_type) => match gate_type {
+                LogicGateType::And => Self::and(inputs, false),
+                LogicGateType::Or => Self::or(inputs, false),
+                LogicGateType::Xor => Self::xor(inputs, false),
+                LogicGateType::Xnor => Self::xor(inputs, true),
+                LogicGateType::Nand => Self::and(inputs, true),
+                LogicGateType::Nor => Self::or(inputs, true),
+                LogicGateType::Not => Self::not(),
+                LogicGateType::Buffer => Self::repeater(1),
+            },
+            ComponentType::Torch => Self::not(),
+            ComponentType::Repeater(delay) => Self::repeater(delay.clamp(1, MAX_REPEATER_DELAY)),
+            ComponentType::Comparator(mode) => Self::comparator(mode),
+            ComponentType::Observer => Self::observer(),
+            ComponentType::Pin(PinDirection::Output, _) => Self::sink("L"),
+            ComponentType::Pin(PinDirection::Bidirectional, _)
+                if is_output_pin(component, circuit) =>
+            {
+                Self::sink("L")
+            }
+            ComponentType::Pin(..) | ComponentType::Lever => Self::source("V"),
+            ComponentType::Button(ButtonType::Stone) => Self::source("B"),
+            ComponentType::Button(ButtonType::Wood) => Self::source("W"),
+            ComponentType::PressurePlate => Self::source("P"),
+            ComponentType::Lamp => Self::sink("L"),
+            ComponentType::Piston => Self::sink("H"),
+            ComponentType::StickyPiston => Self::sink("Y"),
+            ComponentType::NoteBlock => Self::sink("N"),
+            ComponentType::Sequential(sequential_type) => match sequential_type {
+                SequentialType::SrLatch => Self::sr_latch(),
+                SequentialType::DLatch => Self::d_latch(),
+                SequentialType::DFlipFlop => Self::d_flip_flop(),
+                SequentialType::TFlipFlop => Self::t_flip_flop(),
+                // replaced by a d flip-flop and gates before placement, as its feedback can't be drawn in a cell
+                SequentialType::JkFlipFlop => Self::empty(component),
+            },
+            ComponentType::Clock(period) => Self::clock(period.max(MIN_CLOCK_PERIOD)),
+            ComponentType::Monostable(length) => Self::monostable(length.max(1)),
+            // replaced by its content before placement
+            ComponentType::Subcircuit(_) => Self::empty(component),
+            // replaced by the connections of the bits before placement
+            ComponentType::Splitter(_) | ComponentType::Merger(_) => Self::empty(component),
+        }
+    }
+
+    /// Returns whether the cell drives its net without reading another one, e.g. a lever
+    pub fn is_source(&self) -> bool {
+        self.kind == CellKind::Source
+    }
+    /// Returns whether the cell only reads its net, e.g. a lamp
+    pub fn is_sink(&self) -> bool {
+        self.kind == CellKind::Sink
+    }
+    /// Returns the size of the cell as (width, length)
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    pub fn get_size(&self) -> (i32, i32) {
+        let width = self.rows.iter().map(|r| r.len()).max().unwrap_or_default();
+        (width as i32, self.rows.len() as i32)
+    }
+    pub fn get_port(&self, port: usize) -> Option<((i32, i32), Direction)> {
+        self.ports.get(port).copied().flatten()
+    }
+    /// Returns whether the port drives its net
+    pub fn is_driver(&self, port: usize) -> bool {
+        self.get_port(port)
+            .is_some_and(|(_, d)| d == Direction::East)
+            && !self.is_sink()
+    }
+
+    pub fn place(&self, layout: &mut BlockLayout, origin: (i32, i32)) {
+        for (z, row) in (0..).zip(&self.rows) {
+            for (x, symbol) in (0..).zip(row.chars()) {
+                let position = (origin.0 + x, LOGIC_LAYER, origin.1 + z);
+                let block = match symbol {
+                    'd' => Block::dust(),
+                    'S' => Block::solid(),
+                    '>' => Block::wall_torch(Direction::East),
+                    '<' => Block::wall_torch(Direction::West),
+                    'i' => Block::wall_torch(Direction::North),
+                    '^' => Block::repeater(Direction::North),
+                    '1'..='4' => {
+                        Block::repeater(Direction::East).with_property("delay", &symbol.to_string())
+                    }
+                    'C' | 'c' => Block::comparator(Direction::East, symbol == 'c'),
+                    'O' => Block::observer(Direction::West),
+                    'L' => Block::lamp(),
+                    'N' => Block::note_block(),
+                    'H' | 'Y' => Block::piston(symbol == 'Y'),
+                    'V' | 'B' | 'W' | 'P' => {
+                        let top = match symbol {
+                            'V' => Block::lever(),
+                            'P' => Block::pressure_plate(),
+                            _ => Block::button(symbol == 'W'),
+                        };
+                        layout.set_block((position.0, position.1 + 1, position.2), top);
+                        Block::solid()
+                    }
+                    _ => continue,
+                };
+                layout.set_block(position, block);
+            }
+        }
+    }
+}
+
+/// Splits a delay into the symbols of the fewest repeaters adding up to it
+fn delays(delay: u8) -> String {
+    let mut symbols = String::new();
+    let mut remaining = delay;
+    while remaining > 0 {
+        let repeater = remaining.min(MAX_REPEATER_DELAY);
+        symbols.push_str(&repeater.to_string());
+        remaining -= repeater;
+    }
+    symbols
+}
+
+/// A pin is an output if it is connected to the output of another component
+fn is_output_pin(component: &Component, circuit: &Circuit) -> bool {
+    circuit
+        .get_net_of(PortRef::new(component.get_id(), 0))
+        .is_some_and(|net| {
+            net.get_ports().iter().any(|port| {
+                circuit
+                    .get_component(port.component)
+                    .and_then(|c| c.get_ports().get(port.port))
+                    .is_some_and(|p| p.get_kind() == ConnectionKind::Output)
+            })
+        })
+}
@@ -0,0 +1,31 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum ExportError {
+    /// The design is empty, there is nothing to export
+    Empty,
+    /// The layout exceeds the size the file format can store
+    TooLarge((u32, u32, u32)),
+    Io(std::io::Error),
+}
+
+impl Display for ExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "The design is empty"),
+            Self::TooLarge((x, y, z)) => {
+                write!(
+                    f,
+                    "The design is too large to be exported ({x}x{y}x{z} blocks)"
+                )
+            }
+            Self::Io(e) => write!(f, "The file could not be written: {e}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for ExportError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
@@ -0,0 +1,48 @@
+use super::{
+    to_litematic, to_sponge_schematic, to_structure, BlockLayout, ExportError, LITEMATIC_EXTENSION,
+    SPONGE_SCHEMATIC_EXTENSION, STRUCTURE_EXTENSION,
+};
+
+/// The structure file formats a design can be exported in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportFormat {
+    /// Sponge schematic, read by WorldEdit
+    SpongeSchematic,
+    Litematic,
+    /// Vanilla structure file, read by structure blocks
+    Structure,
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 3] = [Self::SpongeSchematic, Self::Litematic, Self::Structure];
+
+    pub const fn get_name(&self) -> &'static str {
+        match self {
+            Self::SpongeSchematic => "Sponge Schematic (.schem)",
+            Self::Litematic => "Litematica (.litematic)",
+            Self::Structure => "Structure (.nbt)",
+        }
+    }
+    pub const fn get_extension(&self) -> &'static str {
+        match self {
+            Self::SpongeSchematic => SPONGE_SCHEMATIC_EXTENSION,
+            Self::Litematic => LITEMATIC_EXTENSION,
+            Self::Structure => STRUCTURE_EXTENSION,
+        }
+    }
+
+    /// Writes the layout in this format.
+    /// `name` is the name of the design and `time` the time of the export in milliseconds since the unix epoch.
+    pub fn export(
+        &self,
+        layout: &BlockLayout,
+        name: &str,
+        time: i64,
+    ) -> Result<Vec<u8>, ExportError> {
+        match self {
+            Self::SpongeSchematic => to_sponge_schematic(layout),
+            Self::Litematic => to_litematic(layout, name, time),
+            Self::Structure => to_structure(layout),
+        }
+    }
+}
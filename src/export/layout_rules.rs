@@ -0,0 +1,882 @@
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, BinaryHeap, HashMap, HashSet},
+};
+
+use crate::circuit::{Circuit, ComponentId, DesignRule, Problem, RuleSettings};
+
+use super::{Block, BlockPosition, CellBounds, Direction, RoutedLayout};
+
+/// Strength of a signal leaving a power source, dust loses one level of it per block
+const MAX_STRENGTH: u8 = 15;
+/// A torch burns out if it toggles more often than this within the burnout window
+const BURNOUT_TOGGLES: u32 = 8;
+/// The window the game counts the toggles of a torch in, 60 game ticks in redstone ticks
+const BURNOUT_WINDOW: u32 = 30;
+
+/// A block of the layout by what it does to a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Part {
+    Dust,
+    /// A block conducting the power of the components pointing into it
+    Solid,
+    /// A torch attached to the block in the opposite direction, or to the block below it if `None`
+    Torch(Option<Direction>),
+    /// A repeater passing the signal on in the direction, with its delay
+    Repeater(Direction, u8),
+    /// A comparator passing the signal on in the direction, in subtract mode if set
+    Comparator(Direction, bool),
+    /// An observer emitting its pulses in the direction
+    Observer(Direction),
+    /// A lever, button or pressure plate on top of its block
+    Switch,
+    Piston,
+    /// A block only reading the signal, e.g. a lamp
+    Mechanism,
+}
+
+impl Part {
+    /// Returns the part the block is, `None` for blocks that don't take part in the circuit
+    fn of(block: &Block) -> Option<Self> {
+        let facing = || {
+            let name = block.get_property("facing")?;
+            Direction::ALL.into_iter().find(|d| d.get_name() == name)
+        };
+        let name = block.get_name().trim_start_matches("minecraft:");
+        // the facing of repeaters and comparators points towards their input, of observers towards what they watch
+        Some(match name {
+            "redstone_wire" => Self::Dust,
+            "stone" => Self::Solid,
+            "redstone_torch" => Self::Torch(None),
+            "redstone_wall_torch" => Self::Torch(Some(facing()?)),
+            "repeater" => Self::Repeater(
+                facing()?.get_opposite(),
+                block
+                    .get_property("delay")
+                    .and_then(|delay| delay.parse().ok())
+                    .unwrap_or(1),
+            ),
+            "comparator" => Self::Comparator(
+                facing()?.get_opposite(),
+                block.get_property("mode") == Some("subtract"),
+            ),
+            "observer" => Self::Observer(facing()?.get_opposite()),
+            "lever" => Self::Switch,
+            name if name.ends_with("_button") || name.ends_with("_pressure_plate") => Self::Switch,
+            "piston" | "sticky_piston" => Self::Piston,
+            "redstone_lamp" | "note_block" => Self::Mechanism,
+            _ => return None,
+        })
+    }
+
+    const fn get_name(&self) -> &'static str {
+        match self {
+            Self::Dust => "dust",
+            Self::Solid => "block",
+            Self::Torch(_) => "torch",
+            Self::Repeater(..) => "repeater",
+            Self::Comparator(..) => "comparator",
+            Self::Observer(_) => "observer",
+            Self::Switch => "switch",
+            Self::Piston => "piston",
+            Self::Mechanism => "mechanism",
+        }
+    }
+    /// Delay in redstone ticks until a change of the input reaches the output
+    fn get_delay(&self) -> u32 {
+        match self {
+            Self::Repeater(_, delay) => u32::from(*delay),
+            Self::Torch(_) | Self::Comparator(..) | Self::Observer(_) => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// What switches a piston, dust counts as a whole so the same net reaching it twice is one signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Signal {
+    Network(usize),
+    Part(BlockPosition),
+}
+
+fn moved(position: BlockPosition, direction: Direction) -> BlockPosition {
+    let (x, z) = direction.get_offset();
+    (position.0 + x, position.1, position.2 + z)
+}
+const fn above(position: BlockPosition) -> BlockPosition {
+    (position.0, position.1 + 1, position.2)
+}
+const fn below(position: BlockPosition) -> BlockPosition {
+    (position.0, position.1 - 1, position.2)
+}
+/// Returns the blocks touching the sides, the top and the bottom of the block
+fn get_neighbours(position: BlockPosition) -> [BlockPosition; 6] {
+    let [north, east, south, west] = Direction::ALL.map(|d| moved(position, d));
+    [north, east, south, west, above(position), below(position)]
+}
+/// Returns the block a torch is attached to
+fn get_attachment(position: BlockPosition, facing: Option<Direction>) -> BlockPosition {
+    facing.map_or(below(position), |f| moved(position, f.get_opposite()))
+}
+
+/// The parts of a layout with the signals they carry while everything powering them is on
+struct Analysis<'a> {
+    routed: &'a RoutedLayout,
+    circuit: &'a Circuit,
+    settings: &'a RuleSettings,
+    /// The lowest corner of the layout, positions in the messages are relative to it like in the exported files
+    origin: BlockPosition,
+    parts: HashMap<BlockPosition, Part>,
+    /// The blocks of every network of connected dust
+    networks: Vec<Vec<BlockPosition>>,
+    network_of: HashMap<BlockPosition, usize>,
+    /// The strength of every powered dust block
+    strengths: HashMap<BlockPosition, u8>,
+    /// The strength every comparator passes on while its rear input is on
+    comparators: HashMap<BlockPosition, u8>,
+    problems: Vec<Problem>,
+}
+
+impl<'a> Analysis<'a> {
+    fn new(routed: &'a RoutedLayout, circuit: &'a Circuit, settings: &'a RuleSettings) -> Self {
+        let layout = routed.get_layout();
+        let parts: HashMap<_, _> = layout
+            .get_blocks()
+            .filter_map(|(position, block)| Some((*position, Part::of(block)?)))
+            .collect();
+        let comparators = parts
+            .iter()
+            .filter(|(_, part)| matches!(part, Part::Comparator(..)))
+            .map(|(position, _)| (*position, MAX_STRENGTH))
+            .collect();
+        let mut analysis = Self {
+            routed,
+            circuit,
+            settings,
+            origin: layout.get_bounds().map_or((0, 0, 0), |(min, _)| min),
+            parts,
+            networks: Vec::new(),
+            network_of: HashMap::new(),
+            strengths: HashMap::new(),
+            comparators,
+            problems: Vec::new(),
+        };
+        analysis.find_networks();
+        analysis.find_strengths();
+        analysis
+    }
+
+    fn get(&self, position: BlockPosition) -> Option<Part> {
+        self.parts.get(&position).copied()
+    }
+    fn is_checked(&self, rule: DesignRule) -> bool {
+        self.settings.get_severity(rule).is_some()
+    }
+    /// Returns the position relative to the lowest corner of the layout as text
+    fn describe(&self, position: BlockPosition) -> String {
+        format!(
+            "({}, {}, {})",
+            position.0 - self.origin.0,
+            position.1 - self.origin.1,
+            position.2 - self.origin.2
+        )
+    }
+    /// Returns the location of the component whose cell is nearest to the block, with the component
+    fn locate(&self, position: BlockPosition) -> ((f64, f64), Vec<ComponentId>) {
+        let distance = |((x, z), (width, length)): &CellBounds| {
+            let dx = (x - position.0).max(position.0 - (x + width - 1)).max(0);
+            let dz = (z - position.2).max(position.2 - (z + length - 1)).max(0);
+            dx + dz
+        };
+        self.routed
+            .get_cells()
+            .iter()
+            .filter_map(|(id, cell)| Some((distance(cell), *id, self.circuit.get_component(*id)?)))
+            .min_by_key(|(distance, id, _)| (*distance, *id))
+            .map_or(((0.0, 0.0), Vec::new()), |(_, id, component)| {
+                (component.get_position(), vec![id])
+            })
+    }
+    fn add(&mut self, rule: DesignRule, message: String, position: BlockPosition) {
+        if let Some(severity) = self.settings.get_severity(rule) {
+            let (location, components) = self.locate(position);
+            self.problems
+                .push(Problem::new(rule, severity, message, location).with_components(components));
+        }
+    }
+
+    /// Returns the dust the dust at the position connects to, on its level and one level above or below
+    fn get_dust_links(&self, position: BlockPosition) -> Vec<BlockPosition> {
+        let mut links = Vec::new();
+        for direction in Direction::ALL {
+            let side = moved(position, direction);
+            match self.get(side) {
+                Some(Part::Dust) => links.push(side),
+                // dust climbs onto the block beside it unless a block above cuts it
+                Some(Part::Solid)
+                    if self.get(above(position)) != Some(Part::Solid)
+                        && self.get(above(side)) == Some(Part::Dust) =>
+                {
+                    links.push(above(side));
+                }
+                // and runs down the side of its own block
+                None if self.get(below(side)) == Some(Part::Dust) => links.push(below(side)),
+                _ => {}
+            }
+        }
+        links
+    }
+    /// Returns the directions the dust at the position is shaped towards
+    fn get_connections(&self, position: BlockPosition) -> Vec<Direction> {
+        let links = self.get_dust_links(position);
+        Direction::ALL
+            .into_iter()
+            .filter(|direction| {
+                let side = moved(position, *direction);
+                let on_axis = |d: Direction| d == *direction || d == direction.get_opposite();
+                links.iter().any(|l| (l.0, l.2) == (side.0, side.2))
+                    || match self.get(side) {
+                        Some(Part::Torch(_) | Part::Switch | Part::Comparator(..)) => true,
+                        Some(Part::Repeater(d, _)) => on_axis(d),
+                        Some(Part::Observer(d)) => d == direction.get_opposite(),
+                        _ => false,
+                    }
+            })
+            .collect()
+    }
+    /// Returns whether the dust at the position powers the block beside it in the direction
+    fn points_into(&self, position: BlockPosition, direction: Direction) -> bool {
+        // dust without connections is a cross, dust with one connection a line
+        match self.get_connections(position).as_slice() {
+            [] => true,
+            [single] => *single == direction || single.get_opposite() == direction,
+            connections => connections.contains(&direction),
+        }
+    }
+    /// Returns the block the repeater, comparator or observer at the position powers, with the strength
+    fn get_output(&self, position: BlockPosition) -> Option<(BlockPosition, u8)> {
+        match self.get(position)? {
+            Part::Repeater(direction, _) | Part::Observer(direction) => {
+                Some((moved(position, direction), MAX_STRENGTH))
+            }
+            Part::Comparator(direction, _) => Some((
+                moved(position, direction),
+                self.comparators.get(&position).copied().unwrap_or_default(),
+            )),
+            _ => None,
+        }
+    }
+    /// Returns whether the repeater or comparator at the position reads the block at `source`
+    fn reads_from(&self, position: BlockPosition, source: BlockPosition) -> bool {
+        match self.get(position) {
+            Some(Part::Repeater(direction, _)) => {
+                moved(position, direction.get_opposite()) == source
+            }
+            // the side of a comparator reads as well
+            Some(Part::Comparator(direction, _)) => {
+                Direction::between(position, source).is_some_and(|d| d != direction)
+            }
+            Some(Part::Observer(direction)) => moved(position, direction.get_opposite()) == source,
+            _ => false,
+        }
+    }
+    fn get_dust_strength(&self, position: BlockPosition) -> u8 {
+        self.strengths.get(&position).copied().unwrap_or_default()
+    }
+    /// Returns the strength the block at the position passes on to the dust around it
+    fn get_strong_power(&self, position: BlockPosition) -> u8 {
+        get_neighbours(position)
+            .into_iter()
+            .map(|neighbour| match self.get(neighbour) {
+                Some(Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_)) => self
+                    .get_output(neighbour)
+                    .filter(|(target, _)| *target == position)
+                    .map_or(0, |(_, strength)| strength),
+                // a torch powers the block above it, a switch the block it is on
+                Some(Part::Torch(_)) if neighbour == below(position) => MAX_STRENGTH,
+                Some(Part::Switch) if neighbour == above(position) => MAX_STRENGTH,
+                _ => 0,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+    /// Returns the strength the dust on and pointing into the block at the position gives it
+    fn get_weak_power(&self, position: BlockPosition) -> u8 {
+        Direction::ALL
+            .into_iter()
+            .map(|direction| moved(position, direction))
+            .filter(|side| {
+                Direction::between(*side, position).is_some_and(|d| self.points_into(*side, d))
+            })
+            .chain([above(position)])
+            .map(|dust| self.get_dust_strength(dust))
+            .max()
+            .unwrap_or_default()
+    }
+    /// Returns the strength the dust at the position gets from anything but other dust
+    fn get_source_strength(&self, position: BlockPosition) -> u8 {
+        get_neighbours(position)
+            .into_iter()
+            .map(|neighbour| match self.get(neighbour) {
+                Some(Part::Torch(_) | Part::Switch) if neighbour.1 == position.1 => MAX_STRENGTH,
+                Some(Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_)) => self
+                    .get_output(neighbour)
+                    .filter(|(target, _)| *target == position)
+                    .map_or(0, |(_, strength)| strength),
+                Some(Part::Solid) => self.get_strong_power(neighbour),
+                _ => 0,
+            })
+            .max()
+            .unwrap_or_default()
+    }
+    /// Returns the strength the block at `source` gives the repeater or comparator at `reader`,
+    /// the side of a comparator only reads dust and components pointing into it
+    fn get_input_strength(&self, source: BlockPosition, reader: BlockPosition, side: bool) -> u8 {
+        match self.get(source) {
+            Some(Part::Dust) => self.get_dust_strength(source),
+            Some(Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_)) => self
+                .get_output(source)
+                .filter(|(target, _)| *target == reader)
+                .map_or(0, |(_, strength)| strength),
+            Some(Part::Solid) if !side => self
+                .get_strong_power(source)
+                .max(self.get_weak_power(source)),
+            Some(Part::Torch(_) | Part::Switch) if !side => MAX_STRENGTH,
+            _ => 0,
+        }
+    }
+    /// Returns the strength at the rear and the stronger one of the sides of the comparator at the position
+    fn get_comparator_inputs(&self, position: BlockPosition) -> (u8, u8) {
+        let Some(Part::Comparator(direction, _)) = self.get(position) else {
+            return (0, 0);
+        };
+        let rear =
+            self.get_input_strength(moved(position, direction.get_opposite()), position, false);
+        let side = Direction::ALL
+            .into_iter()
+            .filter(|d| *d != direction && *d != direction.get_opposite())
+            .map(|d| self.get_input_strength(moved(position, d), position, true))
+            .max()
+            .unwrap_or_default();
+        (rear, side)
+    }
+
+    fn find_networks(&mut self) {
+        let dust: BTreeSet<_> = self
+            .parts
+            .iter()
+            .filter(|(_, part)| **part == Part::Dust)
+            .map(|(position, _)| *position)
+            .collect();
+        let mut network_of = HashMap::new();
+        let mut networks = Vec::new();
+        for start in dust {
+            if network_of.contains_key(&start) {
+                continue;
+            }
+            let index = networks.len();
+            let mut members = vec![start];
+            network_of.insert(start, index);
+            let mut stack = vec![start];
+            while let Some(position) = stack.pop() {
+                for link in self.get_dust_links(position) {
+                    if let Entry::Vacant(entry) = network_of.entry(link) {
+                        entry.insert(index);
+                        members.push(link);
+                        stack.push(link);
+                    }
+                }
+            }
+            networks.push(members);
+        }
+        self.networks = networks;
+        self.network_of = network_of;
+    }
+    /// Spreads the signals through the dust, the strength of the comparators depends on the dust behind them,
+    /// so it is spread again until it doesn't change any more
+    fn find_strengths(&mut self) {
+        for _ in 0..=self.comparators.len() {
+            self.spread_signals();
+            let comparators: HashMap<_, _> = self
+                .comparators
+                .keys()
+                .map(|position| (*position, self.get_comparator_inputs(*position).0))
+                .collect();
+            if comparators == self.comparators {
+                break;
+            }
+            self.comparators = comparators;
+        }
+    }
+    fn spread_signals(&mut self) {
+        // the strongest signals first, so every block keeps the strongest one reaching it
+        let mut queue: BinaryHeap<_> = self
+            .network_of
+            .keys()
+            .map(|position| (self.get_source_strength(*position), *position))
+            .filter(|(strength, _)| *strength > 0)
+            .collect();
+        let mut strengths = HashMap::new();
+        while let Some((strength, position)) = queue.pop() {
+            if strengths.contains_key(&position) {
+                continue;
+            }
+            strengths.insert(position, strength);
+            if strength > 1 {
+                for link in self.get_dust_links(position) {
+                    if !strengths.contains_key(&link) {
+                        queue.push((strength - 1, link));
+                    }
+                }
+            }
+        }
+        self.strengths = strengths;
+    }
+
+    /// Returns the parts reading the block at the position while it is powered
+    fn get_block_readers(&self, position: BlockPosition) -> Vec<BlockPosition> {
+        let mut readers = Vec::new();
+        for direction in Direction::ALL {
+            let side = moved(position, direction);
+            match self.get(side) {
+                Some(Part::Torch(facing)) if get_attachment(side, facing) == position => {
+                    readers.push(side);
+                }
+                Some(Part::Repeater(output, _) | Part::Comparator(output, _))
+                    if output == direction =>
+                {
+                    readers.push(side);
+                }
+                _ => {}
+            }
+        }
+        if self.get(above(position)) == Some(Part::Torch(None)) {
+            readers.push(above(position));
+        }
+        readers
+    }
+    /// Returns the parts reading the dust of the network
+    fn get_network_readers(&self, network: usize) -> Vec<BlockPosition> {
+        let mut readers = Vec::new();
+        for dust in &self.networks[network] {
+            if self.get(below(*dust)) == Some(Part::Solid) {
+                readers.extend(self.get_block_readers(below(*dust)));
+            }
+            for direction in Direction::ALL {
+                let side = moved(*dust, direction);
+                match self.get(side) {
+                    Some(Part::Solid) if self.points_into(*dust, direction) => {
+                        readers.extend(self.get_block_readers(side));
+                    }
+                    Some(Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_))
+                        if self.reads_from(side, *dust) =>
+                    {
+                        readers.push(side);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        readers
+    }
+    /// Returns the parts switched by the block at `target` when the part at `source` powers it.
+    /// A powered block passes the power on to the dust around it.
+    fn get_powered(
+        &self,
+        target: BlockPosition,
+        source: BlockPosition,
+        readers: &[Vec<BlockPosition>],
+    ) -> Vec<BlockPosition> {
+        match self.get(target) {
+            Some(Part::Dust) => readers[self.network_of[&target]].clone(),
+            Some(Part::Solid) => {
+                let mut powered = self.get_block_readers(target);
+                for neighbour in get_neighbours(target) {
+                    if let Some(network) = self.network_of.get(&neighbour) {
+                        powered.extend(readers[*network].iter().copied());
+                    }
+                }
+                powered
+            }
+            Some(Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_))
+                if self.reads_from(target, source) =>
+            {
+                vec![target]
+            }
+            _ => Vec::new(),
+        }
+    }
+    /// Returns for every torch, repeater, comparator and observer the ones it switches
+    fn get_signal_graph(&self) -> HashMap<BlockPosition, Vec<BlockPosition>> {
+        let readers: Vec<_> = (0..self.networks.len())
+            .map(|network| self.get_network_readers(network))
+            .collect();
+        let mut graph = HashMap::new();
+        for (position, part) in &self.parts {
+            let mut switched = match part {
+                Part::Torch(facing) => {
+                    let attachment = get_attachment(*position, *facing);
+                    let mut switched = self.get_powered(above(*position), *position, &readers);
+                    // a torch only powers the block above it, the other blocks around it don't conduct its signal
+                    for side in Direction::ALL.map(|d| moved(*position, d)) {
+                        if side != attachment && self.get(side) != Some(Part::Solid) {
+                            switched.extend(self.get_powered(side, *position, &readers));
+                        }
+                    }
+                    switched
+                }
+                Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_) => self
+                    .get_output(*position)
+                    .map(|(target, _)| self.get_powered(target, *position, &readers))
+                    .unwrap_or_default(),
+                _ => continue,
+            };
+            switched.sort_unstable();
+            switched.dedup();
+            graph.insert(*position, switched);
+        }
+        graph
+    }
+    /// Returns the signals switching a piston at the position, from anything but the block at `except`
+    fn get_activators(
+        &self,
+        position: BlockPosition,
+        except: BlockPosition,
+    ) -> Vec<(Signal, BlockPosition)> {
+        let mut activators = Vec::new();
+        for neighbour in get_neighbours(position) {
+            if neighbour == except {
+                continue;
+            }
+            let towards = Direction::between(neighbour, position);
+            let signals = match self.get(neighbour) {
+                Some(Part::Dust)
+                    if neighbour == above(position)
+                        || towards.is_some_and(|d| self.points_into(neighbour, d)) =>
+                {
+                    vec![Signal::Network(self.network_of[&neighbour])]
+                }
+                Some(Part::Torch(facing)) if get_attachment(neighbour, facing) != position => {
+                    vec![Signal::Part(neighbour)]
+                }
+                Some(Part::Switch) => vec![Signal::Part(neighbour)],
+                Some(Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_))
+                    if self
+                        .get_output(neighbour)
+                        .is_some_and(|(t, _)| t == position) =>
+                {
+                    vec![Signal::Part(neighbour)]
+                }
+                Some(Part::Solid) => self.get_block_signals(neighbour),
+                _ => Vec::new(),
+            };
+            activators.extend(signals.into_iter().map(|signal| (signal, neighbour)));
+        }
+        activators
+    }
+    /// Returns the signals powering the block at the position
+    fn get_block_signals(&self, position: BlockPosition) -> Vec<Signal> {
+        let mut signals = Vec::new();
+        for neighbour in get_neighbours(position) {
+            let towards = Direction::between(neighbour, position);
+            match self.get(neighbour) {
+                Some(Part::Dust)
+                    if neighbour == above(position)
+                        || towards.is_some_and(|d| self.points_into(neighbour, d)) =>
+                {
+                    signals.push(Signal::Network(self.network_of[&neighbour]));
+                }
+                Some(Part::Torch(_)) if neighbour == below(position) => {
+                    signals.push(Signal::Part(neighbour));
+                }
+                Some(Part::Switch) if neighbour == above(position) => {
+                    signals.push(Signal::Part(neighbour));
+                }
+                Some(Part::Repeater(..) | Part::Comparator(..) | Part::Observer(_))
+                    if self
+                        .get_output(neighbour)
+                        .is_some_and(|(t, _)| t == position) =>
+                {
+                    signals.push(Signal::Part(neighbour));
+                }
+                _ => {}
+            }
+        }
+        signals
+    }
+
+    fn check_long_dust_runs(&mut self) {
+        if !self.is_checked(DesignRule::LongDustRun) {
+            return;
+        }
+        // the first dust of every network the signal doesn't reach
+        let mut lost: BTreeMap<usize, BlockPosition> = BTreeMap::new();
+        for (position, network) in &self.network_of {
+            if self.strengths.contains_key(position) {
+                continue;
+            }
+            let at_end = self
+                .get_dust_links(*position)
+                .iter()
+                .any(|link| self.get_dust_strength(*link) == 1);
+            if at_end {
+                let first = lost.entry(*network).or_insert(*position);
+                *first = (*first).min(*position);
+            }
+        }
+        for position in lost.into_values() {
+            let message = format!(
+                "The signal runs out before the dust at {}, a repeater has to refresh it on the way",
+                self.describe(position)
+            );
+            self.add(DesignRule::LongDustRun, message, position);
+        }
+    }
+
+    fn check_torch_burnout(&mut self) {
+        if !self.is_checked(DesignRule::TorchBurnout) {
+            return;
+        }
+        let graph = self.get_signal_graph();
+        let torches: BTreeSet<_> = self
+            .parts
+            .iter()
+            .filter(|(_, part)| matches!(part, Part::Torch(_)))
+            .map(|(position, _)| *position)
+            .collect();
+        let mut reported = HashSet::new();
+        for torch in torches {
+            if reported.contains(&torch) {
+                continue;
+            }
+            let Some((delay, torches)) = self.find_oscillation(torch, &graph) else {
+                continue;
+            };
+            reported.extend(torches);
+            let toggles = BURNOUT_WINDOW / delay.max(1);
+            if toggles <= BURNOUT_TOGGLES {
+                continue;
+            }
+            let message = format!(
+                "The torch at {} toggles every {delay} ticks and burns out, the loop through it needs a delay of at least {} ticks",
+                self.describe(torch),
+                BURNOUT_WINDOW / BURNOUT_TOGGLES + 1
+            );
+            self.add(DesignRule::TorchBurnout, message, torch);
+        }
+    }
+    /// Returns the delay of the shortest loop switching the torch off through itself, which is how often it toggles,
+    /// with the torches on the loop. Loops through an even number of torches hold their state instead.
+    fn find_oscillation(
+        &self,
+        torch: BlockPosition,
+        graph: &HashMap<BlockPosition, Vec<BlockPosition>>,
+    ) -> Option<(u32, Vec<BlockPosition>)> {
+        // the delay until a change of the torch reaches a part, with whether it was inverted on the way
+        let mut previous = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, (torch, false), None))]);
+        while let Some(Reverse((delay, state, before))) = queue.pop() {
+            if previous.contains_key(&state) {
+                continue;
+            }
+            previous.insert(state, before);
+            if state == (torch, true) {
+                let mut torches = Vec::new();
+                let mut current = before;
+                while let Some((position, inverted)) = current {
+                    if matches!(self.get(position), Some(Part::Torch(_))) {
+                        torches.push(position);
+                    }
+                    current = previous.get(&(position, inverted)).copied().flatten();
+                }
+                return Some((delay, torches));
+            }
+            let (position, inverted) = state;
+            let Some(part) = self.get(position) else {
+                continue;
+            };
+            let switched = (position, inverted != matches!(part, Part::Torch(_)));
+            for next in graph.get(&position).into_iter().flatten() {
+                let next = (*next, switched.1);
+                if !previous.contains_key(&next) {
+                    queue.push(Reverse((delay + part.get_delay(), next, Some(state))));
+                }
+            }
+        }
+        None
+    }
+
+    fn check_quasi_connectivity(&mut self) {
+        if !self.is_checked(DesignRule::QuasiConnectivity) {
+            return;
+        }
+        let pistons: BTreeSet<_> = self
+            .parts
+            .iter()
+            .filter(|(_, part)| **part == Part::Piston)
+            .map(|(position, _)| *position)
+            .collect();
+        for piston in pistons {
+            // a piston is also switched by what would switch a piston in the block above it
+            let own: Vec<_> = self
+                .get_activators(piston, above(piston))
+                .into_iter()
+                .map(|(signal, _)| signal)
+                .collect();
+            let foreign = self
+                .get_activators(above(piston), piston)
+                .into_iter()
+                .filter(|(signal, _)| !own.contains(signal))
+                .min();
+            let Some((_, source)) = foreign else {
+                continue;
+            };
+            let name = self.get(source).map_or("block", |part| part.get_name());
+            let message = format!(
+                "The piston at {} is also switched by the {name} at {} next to the block above it",
+                self.describe(piston),
+                self.describe(source)
+            );
+            self.add(DesignRule::QuasiConnectivity, message, piston);
+        }
+    }
+
+    fn check_weak_signals(&mut self) {
+        if !self.is_checked(DesignRule::WeakSignal) {
+            return;
+        }
+        let mut comparators: Vec<_> = self
+            .parts
+            .iter()
+            .filter_map(|(position, part)| match part {
+                Part::Comparator(_, subtract) => Some((*position, *subtract)),
+                _ => None,
+            })
+            .collect();
+        comparators.sort_unstable();
+        for (position, subtract) in comparators {
+            let (rear, side) = self.get_comparator_inputs(position);
+            if rear == 0 || side == 0 {
+                continue;
+            }
+            let message = if !subtract && rear < side {
+                format!(
+                    "The comparator at {} gets strength {rear} at its rear but {side} at its side and stays off while both are on",
+                    self.describe(position)
+                )
+            } else if subtract && side < rear {
+                format!(
+                    "The comparator at {} gets strength {side} at its side but {rear} at its rear and still emits {} while both are on",
+                    self.describe(position),
+                    rear - side
+                )
+            } else {
+                continue;
+            };
+            self.add(DesignRule::WeakSignal, message, position);
+        }
+    }
+}
+
+/// Checks the blocks a circuit is exported as against the redstone rules that are turned on, `circuit` is the circuit
+/// the layout was placed and routed from. The problems are located at the component whose cell is nearest to them,
+/// the positions in their messages are relative to the lowest corner of the layout like in the exported files.
+/// The problems are sorted by severity, errors first.
+pub fn check_layout(
+    routed: &RoutedLayout,
+    circuit: &Circuit,
+    settings: &RuleSettings,
+) -> Vec<Problem> {
+    let mut analysis = Analysis::new(routed, circuit, settings);
+    analysis.check_long_dust_runs();
+    analysis.check_torch_burnout();
+    analysis.check_quasi_connectivity();
+    analysis.check_weak_signals();
+    let mut problems = analysis.problems;
+    problems.sort_by_key(Problem::get_severity);
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::BlockLayout;
+
+    fn check(layout: BlockLayout, rule: DesignRule) -> Vec<Problem> {
+        let routed = RoutedLayout::from_layout(layout);
+        check_layout(&routed, &Circuit::new(), &RuleSettings::default())
+            .into_iter()
+            .filter(|p| p.get_rule() == rule)
+            .collect()
+    }
+    /// A lever at x = 0 and a line of dust along x from it
+    fn dust_line(length: i32) -> BlockLayout {
+        let mut layout = BlockLayout::new();
+        layout.set_block((0, 0, 0), Block::lever());
+        for x in 1..=length {
+            layout.set_block((x, 0, 0), Block::dust());
+        }
+        layout
+    }
+
+    #[test]
+    fn the_signal_runs_out_after_fifteen_blocks_of_dust() {
+        assert!(check(dust_line(15), DesignRule::LongDustRun).is_empty());
+
+        let problems = check(dust_line(16), DesignRule::LongDustRun);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].get_message(),
+            "The signal runs out before the dust at (16, 0, 0), a repeater has to refresh it on the way"
+        );
+    }
+
+    #[test]
+    fn a_torch_switching_itself_off_burns_out() {
+        let mut layout = BlockLayout::new();
+        // the torch powers the dust beside it, which climbs onto the block the torch hangs on
+        layout.set_block((0, 0, 0), Block::solid());
+        layout.set_block((1, 0, 0), Block::wall_torch(Direction::East));
+        layout.set_block((1, 0, 1), Block::dust());
+        layout.set_block((0, 0, 1), Block::dust());
+        layout.set_block((0, 1, 0), Block::dust());
+
+        let problems = check(layout, DesignRule::TorchBurnout);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].get_message(),
+            "The torch at (1, 0, 0) toggles every 1 ticks and burns out, the loop through it needs a delay of at least 4 ticks"
+        );
+    }
+
+    #[test]
+    fn dust_above_the_block_over_a_piston_switches_it() {
+        let mut layout = BlockLayout::new();
+        layout.set_block((0, 0, 0), Block::piston(false));
+        layout.set_block((0, 2, 0), Block::dust());
+        layout.set_block((1, 2, 0), Block::lever());
+
+        let problems = check(layout, DesignRule::QuasiConnectivity);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].get_message(),
+            "The piston at (0, 0, 0) is also switched by the dust at (0, 2, 0) next to the block above it"
+        );
+    }
+
+    #[test]
+    fn a_comparator_with_a_weaker_rear_stays_off() {
+        // four blocks of dust lose three levels on the way to the rear, the side is next to its lever
+        let mut layout = dust_line(4);
+        layout.set_block((5, 0, 0), Block::comparator(Direction::East, false));
+        layout.set_block((5, 0, 1), Block::dust());
+        layout.set_block((5, 0, 2), Block::lever());
+
+        let problems = check(layout, DesignRule::WeakSignal);
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].get_message(),
+            "The comparator at (5, 0, 0) gets strength 12 at its rear but 15 at its side and stays off while both are on"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use super::{BlockLayout, ExportError, SignText, Tag, DATA_VERSION};
+
+pub const LITEMATIC_EXTENSION: &str = "litematic";
+/// The version of the Litematica schematic format for Minecraft 1.20
+const LITEMATIC_VERSION: i32 = 6;
+
+/// Writes the layout as a gzip compressed Litematica schematic with a single region named after the design.
+/// `time` is the time of the export in milliseconds since the unix epoch.
+pub fn to_litematic(layout: &BlockLayout, name: &str, time: i64) -> Result<Vec<u8>, ExportError> {
+    if layout.is_empty() {
+        return Err(ExportError::Empty);
+    }
+    let size = layout.get_size();
+    let (Ok(width), Ok(height), Ok(length)) = (
+        i32::try_from(size.0),
+        i32::try_from(size.1),
+        i32::try_from(size.2),
+    ) else {
+        return Err(ExportError::TooLarge(size));
+    };
+    let vector =
+        |x, y, z| Tag::compound([("x", Tag::Int(x)), ("y", Tag::Int(y)), ("z", Tag::Int(z))]);
+
+    let palette = layout.get_palette();
+    let block_states = pack_indices(&layout.get_palette_indices(&palette), palette.len());
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let block_count = layout.get_blocks().count() as i32;
+    let tile_entities = layout
+        .get_signs()
+        .into_iter()
+        .map(|((x, y, z), sign)| {
+            let mut entries = vec![
+                ("x".to_string(), Tag::Int(x)),
+                ("y".to_string(), Tag::Int(y)),
+                ("z".to_string(), Tag::Int(z)),
+                (
+                    "id".to_string(),
+                    Tag::String(SignText::BLOCK_ENTITY_ID.to_string()),
+                ),
+            ];
+            entries.extend(sign.to_entries());
+            Tag::Compound(entries)
+        })
+        .collect();
+    let region = Tag::compound([
+        ("Position", vector(0, 0, 0)),
+        ("Size", vector(width, height, length)),
+        (
+            "BlockStatePalette",
+            Tag::List(palette.iter().map(|b| b.to_tag()).collect()),
+        ),
+        ("BlockStates", Tag::LongArray(block_states)),
+        ("TileEntities", Tag::List(tile_entities)),
+        ("Entities", Tag::List(Vec::new())),
+        ("PendingBlockTicks", Tag::List(Vec::new())),
+        ("PendingFluidTicks", Tag::List(Vec::new())),
+    ]);
+    let metadata = Tag::compound([
+        ("Name", Tag::String(name.to_string())),
+        ("Author", Tag::String(String::new())),
+        ("Description", Tag::String(String::new())),
+        ("RegionCount", Tag::Int(1)),
+        ("TotalVolume", Tag::Int(width * height * length)),
+        ("TotalBlocks", Tag::Int(block_count)),
+        ("TimeCreated", Tag::Long(time)),
+        ("TimeModified", Tag::Long(time)),
+        ("EnclosingSize", vector(width, height, length)),
+    ]);
+    let litematic = Tag::compound([
+        ("MinecraftDataVersion", Tag::Int(DATA_VERSION)),
+        ("Version", Tag::Int(LITEMATIC_VERSION)),
+        ("Metadata", metadata),
+        ("Regions", Tag::Compound(vec![(name.to_string(), region)])),
+    ]);
+    Ok(litematic.to_gzip("")?)
+}
+
+/// Packs the palette indices tightly into longs, an index may span two longs.
+/// Every index takes as many bits as the largest one needs, but at least two.
+#[allow(clippy::cast_possible_wrap)]
+fn pack_indices(indices: &[usize], palette_size: usize) -> Vec<i64> {
+    let bits = (usize::BITS - palette_size.saturating_sub(1).leading_zeros()).max(2) as usize;
+    let mut longs = vec![0_u64; (indices.len() * bits).div_ceil(64)];
+    for (i, index) in indices.iter().enumerate() {
+        let start = i * bits;
+        let (long, offset) = (start / 64, start % 64);
+        let value = *index as u64;
+        longs[long] |= value << offset;
+        if offset + bits > 64 {
+            longs[long + 1] |= value >> (64 - offset);
+        }
+    }
+    longs.into_iter().map(|l| l as i64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::nbt::tests::sample_layout;
+
+    #[test]
+    fn region_has_the_size_palette_and_blocks_of_the_layout() {
+        let layout = sample_layout();
+        let (_, litematic) = Tag::from_gzip(&to_litematic(&layout, "Adder", 1000).unwrap());
+        let region = litematic
+            .get("Regions")
+            .and_then(|r| r.get("Adder"))
+            .unwrap();
+        let size = region.get("Size").unwrap();
+        assert_eq!(size.get("x"), Some(&Tag::Int(3)));
+        assert_eq!(size.get("y"), Some(&Tag::Int(2)));
+        assert_eq!(size.get("z"), Some(&Tag::Int(2)));
+        let metadata = litematic.get("Metadata").unwrap();
+        assert_eq!(metadata.get("TotalVolume"), Some(&Tag::Int(12)));
+        assert_eq!(metadata.get("TotalBlocks"), Some(&Tag::Int(4)));
+
+        let palette = layout.get_palette();
+        assert_eq!(
+            region.get("BlockStatePalette"),
+            Some(&Tag::List(palette.iter().map(|b| b.to_tag()).collect()))
+        );
+
+        // 5 blocks in the palette take 3 bits each
+        let Some(Tag::LongArray(longs)) = region.get("BlockStates") else {
+            panic!("no block states");
+        };
+        let bits: Vec<bool> = longs
+            .iter()
+            .flat_map(|l| (0..64).map(move |bit| l >> bit & 1 == 1))
+            .collect();
+        let indices: Vec<usize> = bits
+            .chunks(3)
+            .take(12)
+            .map(|c| c.iter().rev().fold(0, |v, b| v << 1 | usize::from(*b)))
+            .collect();
+        assert_eq!(indices, layout.get_palette_indices(&palette));
+    }
+
+    #[test]
+    fn indices_may_span_two_longs() {
+        let indices: Vec<usize> = (0..30).map(|i| i % 5).collect();
+        let longs = pack_indices(&indices, 5);
+        assert_eq!(longs.len(), 2);
+        // the 22nd index starts at bit 63
+        #[allow(clippy::cast_sign_loss)]
+        let spanning = ((longs[0] as u64) >> 63) | ((longs[1] as u64) & 0b11) << 1;
+        assert_eq!(spanning, 1);
+    }
+}
@@ -0,0 +1,24 @@
+//! Exporting designs as Minecraft structures.
+//!
+//! A [`Circuit`](crate::circuit::Circuit) is first placed and routed as a [`BlockLayout`], the blocks that have to be
+//! placed in a world, which is then written in one of the structure file formats.
+mod block;
+pub use block::*;
+mod cell_library;
+mod export_error;
+pub use export_error::*;
+mod export_format;
+pub use export_format::*;
+mod layout_rules;
+pub use layout_rules::*;
+mod litematic;
+pub use litematic::*;
+mod nbt;
+pub use nbt::*;
+mod place_and_route;
+pub use place_and_route::*;
+mod router;
+mod sponge_schematic;
+pub use sponge_schematic::*;
+mod structure;
+pub use structure::*;
@@ -0,0 +1,320 @@
+use std::io::Write;
+
+use flate2::{write::GzEncoder, Compression};
+
+use super::{Block, SignText, SIGN_LINES};
+
+/// A tag of Minecraft's Named Binary Tag format
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    String(String),
+    /// All elements have to be of the same type
+    List(Vec<Tag>),
+    /// The entries in the order they are written
+    Compound(Vec<(String, Tag)>),
+    ByteArray(Vec<i8>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    const fn get_id(&self) -> u8 {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) => 3,
+            Self::Long(_) => 4,
+            Self::ByteArray(_) => 7,
+            Self::String(_) => 8,
+            Self::List(_) => 9,
+            Self::Compound(_) => 10,
+            Self::IntArray(_) => 11,
+            Self::LongArray(_) => 12,
+        }
+    }
+
+    /// Creates a compound from its entries
+    pub fn compound<const N: usize>(entries: [(&str, Tag); N]) -> Self {
+        Self::Compound(
+            entries
+                .into_iter()
+                .map(|(name, tag)| (name.to_string(), tag))
+                .collect(),
+        )
+    }
+
+    /// Writes the tag as the root of an uncompressed NBT file
+    pub fn write_named(&self, name: &str, out: &mut Vec<u8>) {
+        out.push(self.get_id());
+        write_string(name, out);
+        self.write_payload(out);
+    }
+
+    /// Writes the tag as the root of a gzip compressed NBT file, the way Minecraft stores structures
+    pub fn to_gzip(&self, name: &str) -> Result<Vec<u8>, std::io::Error> {
+        let mut nbt = Vec::new();
+        self.write_named(name, &mut nbt);
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&nbt)?;
+        encoder.finish()
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    fn write_payload(&self, out: &mut Vec<u8>) {
+        match self {
+            Self::Byte(value) => out.push(*value as u8),
+            Self::Short(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::Int(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::Long(value) => out.extend_from_slice(&value.to_be_bytes()),
+            Self::String(value) => write_string(value, out),
+            Self::List(values) => {
+                // empty lists have the element type End
+                out.push(values.first().map_or(0, Tag::get_id));
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                values.iter().for_each(|v| v.write_payload(out));
+            }
+            Self::Compound(entries) => {
+                for (name, tag) in entries {
+                    tag.write_named(name, out);
+                }
+                // End tag
+                out.push(0);
+            }
+            Self::ByteArray(values) => {
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                out.extend(values.iter().map(|v| *v as u8));
+            }
+            Self::IntArray(values) => {
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                values
+                    .iter()
+                    .for_each(|v| out.extend_from_slice(&v.to_be_bytes()));
+            }
+            Self::LongArray(values) => {
+                out.extend_from_slice(&(values.len() as i32).to_be_bytes());
+                values
+                    .iter()
+                    .for_each(|v| out.extend_from_slice(&v.to_be_bytes()));
+            }
+        }
+    }
+}
+
+/// Writes a string with its length. Block names are plain ASCII, so UTF-8 matches Java's modified UTF-8.
+#[allow(clippy::cast_possible_truncation)]
+fn write_string(value: &str, out: &mut Vec<u8>) {
+    out.extend_from_slice(&(value.len() as u16).to_be_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+impl Block {
+    /// The block state as stored in palettes, with its name and properties
+    pub fn to_tag(&self) -> Tag {
+        let mut entries = vec![("Name".to_string(), Tag::String(self.get_name().to_string()))];
+        if !self.get_properties().is_empty() {
+            let properties = self
+                .get_properties()
+                .iter()
+                .map(|(name, value)| (name.clone(), Tag::String(value.clone())))
+                .collect();
+            entries.push(("Properties".to_string(), Tag::Compound(properties)));
+        }
+        Tag::Compound(entries)
+    }
+}
+
+impl SignText {
+    /// The id of the block entity of signs
+    pub const BLOCK_ENTITY_ID: &'static str = "minecraft:sign";
+
+    /// The data of the block entity of the sign, without its id and position
+    pub fn to_entries(&self) -> Vec<(String, Tag)> {
+        let side = |lines: &[String], color: &str| {
+            let messages = (0..SIGN_LINES)
+                .map(|i| {
+                    Tag::String(Self::to_text_component(
+                        lines.get(i).map_or("", String::as_str),
+                    ))
+                })
+                .collect();
+            Tag::compound([
+                ("messages", Tag::List(messages)),
+                ("color", Tag::String(color.to_string())),
+                ("has_glowing_text", Tag::Byte(0)),
+            ])
+        };
+        vec![
+            (
+                "front_text".to_string(),
+                side(self.get_lines(), self.get_color()),
+            ),
+            ("back_text".to_string(), side(&[], "black")),
+            ("is_waxed".to_string(), Tag::Byte(0)),
+        ]
+    }
+
+    /// Writes the line as a JSON text component. Everything but printable ASCII is escaped, so the string is
+    /// written the same in UTF-8 and Java's modified UTF-8.
+    fn to_text_component(line: &str) -> String {
+        let mut json = String::from(r#"{"text":""#);
+        for c in line.chars() {
+            match c {
+                '"' | '\\' => {
+                    json.push('\\');
+                    json.push(c);
+                }
+                ' '..='~' => json.push(c),
+                _ => {
+                    let mut units = [0; 2];
+                    for unit in c.encode_utf16(&mut units) {
+                        json.push_str(&format!("\\u{unit:04x}"));
+                    }
+                }
+            }
+        }
+        json.push_str(r#""}"#);
+        json
+    }
+}
+
+#[cfg(test)]
+impl Tag {
+    /// Reads the root of a gzip compressed NBT file with its name
+    pub fn from_gzip(bytes: &[u8]) -> (String, Self) {
+        use std::io::Read;
+        let mut nbt = Vec::new();
+        flate2::read::GzDecoder::new(bytes)
+            .read_to_end(&mut nbt)
+            .unwrap();
+        let mut input = nbt.as_slice();
+        let id = take::<1>(&mut input)[0];
+        let name = read_string(&mut input);
+        let tag = Self::read_payload(id, &mut input);
+        assert!(input.is_empty(), "trailing bytes after the root tag");
+        (name, tag)
+    }
+
+    /// Returns the entry of a compound with the name
+    pub fn get(&self, name: &str) -> Option<&Self> {
+        match self {
+            Self::Compound(entries) => entries.iter().find(|(n, _)| n == name).map(|(_, t)| t),
+            _ => None,
+        }
+    }
+
+    fn read_payload(id: u8, input: &mut &[u8]) -> Self {
+        let len = |input: &mut &[u8]| usize::try_from(i32::from_be_bytes(take(input))).unwrap();
+        match id {
+            1 => Self::Byte(i8::from_be_bytes(take(input))),
+            2 => Self::Short(i16::from_be_bytes(take(input))),
+            3 => Self::Int(i32::from_be_bytes(take(input))),
+            4 => Self::Long(i64::from_be_bytes(take(input))),
+            7 => Self::ByteArray(
+                (0..len(input))
+                    .map(|_| i8::from_be_bytes(take(input)))
+                    .collect(),
+            ),
+            8 => Self::String(read_string(input)),
+            9 => {
+                let id = take::<1>(input)[0];
+                Self::List(
+                    (0..len(input))
+                        .map(|_| Self::read_payload(id, input))
+                        .collect(),
+                )
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let id = take::<1>(input)[0];
+                    if id == 0 {
+                        return Self::Compound(entries);
+                    }
+                    let name = read_string(input);
+                    entries.push((name, Self::read_payload(id, input)));
+                }
+            }
+            11 => Self::IntArray(
+                (0..len(input))
+                    .map(|_| i32::from_be_bytes(take(input)))
+                    .collect(),
+            ),
+            12 => Self::LongArray(
+                (0..len(input))
+                    .map(|_| i64::from_be_bytes(take(input)))
+                    .collect(),
+            ),
+            _ => panic!("unknown tag id {id}"),
+        }
+    }
+}
+
+#[cfg(test)]
+fn take<const N: usize>(input: &mut &[u8]) -> [u8; N] {
+    let (bytes, rest) = input.split_at(N);
+    *input = rest;
+    bytes.try_into().unwrap()
+}
+
+#[cfg(test)]
+fn read_string(input: &mut &[u8]) -> String {
+    let len = u16::from_be_bytes(take(input)) as usize;
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+    use crate::export::{BlockLayout, Direction};
+
+    /// A small layout with a sign, spanning 3 blocks along x, 2 up and 2 along z
+    pub fn sample_layout() -> BlockLayout {
+        let mut layout = BlockLayout::new();
+        layout.set_block((5, 10, -3), Block::solid());
+        layout.set_block((5, 11, -3), Block::lever());
+        layout.set_block((7, 10, -2), Block::wall_torch(Direction::East));
+        layout.set_sign((6, 10, -3), 4, SignText::new("in", "#000000"));
+        layout
+    }
+
+    #[test]
+    fn tags_are_read_back_as_written() {
+        let tag = Tag::compound([
+            ("byte", Tag::Byte(-1)),
+            ("short", Tag::Short(-300)),
+            ("int", Tag::Int(70_000)),
+            ("long", Tag::Long(-5_000_000_000)),
+            ("string", Tag::String("minecraft:stone".to_string())),
+            ("empty", Tag::List(Vec::new())),
+            ("list", Tag::List(vec![Tag::Int(1), Tag::Int(2)])),
+            ("bytes", Tag::ByteArray(vec![0, -128, 127])),
+            ("ints", Tag::IntArray(vec![-1, 0, 1])),
+            ("longs", Tag::LongArray(vec![i64::MIN, i64::MAX])),
+            ("nested", Tag::compound([("x", Tag::Int(3))])),
+        ]);
+        assert_eq!(
+            Tag::from_gzip(&tag.to_gzip("root").unwrap()),
+            ("root".to_string(), tag)
+        );
+    }
+
+    #[test]
+    fn blocks_are_written_with_their_properties() {
+        let tag = Block::wall_torch(Direction::East).to_tag();
+        assert_eq!(
+            tag.get("Name"),
+            Some(&Tag::String("minecraft:redstone_wall_torch".to_string()))
+        );
+        assert_eq!(
+            tag.get("Properties").and_then(|p| p.get("facing")),
+            Some(&Tag::String("east".to_string()))
+        );
+        assert_eq!(Block::solid().to_tag().get("Properties"), None);
+    }
+}
@@ -0,0 +1,1075 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::circuit::{
+    AnnotationAnchor, Circuit, Component, ComponentId, ComponentType, ConnectionKind,
+    ConnectionPoint, LogicGateType, NetId, PortRef, SequentialType, BLOCK_SIZE,
+};
+
+use super::{
+    cell_library::{Cell, LOGIC_LAYER},
+    router::{RouteNode, Router, ROUTING_LAYERS},
+    Block, BlockLayout, Direction, SignText,
+};
+
+/// Blocks between the cells of a column
+const CELL_SPACING: i32 = 5;
+/// Width of the routing channel between two columns, without the room for the wires
+const CHANNEL_WIDTH: i32 = 8;
+/// Room around the placed cells the wires may use
+const ROUTING_MARGIN: i32 = 8;
+/// Length of dust after which a repeater refreshes the signal.
+/// Leaves some strength for the dust inside the cells, a signal is lost after 15 blocks.
+const MAX_DUST_RUN: u32 = 13;
+/// The ports of the two input gates added by expansions
+const GATE_PORTS: [ConnectionKind; 3] = [
+    ConnectionKind::Input,
+    ConnectionKind::Input,
+    ConnectionKind::Output,
+];
+/// How often the nets are routed again in a different order if some of them could not be routed
+const ROUTING_ATTEMPTS: usize = 3;
+
+/// The origin and the size of a placed cell as (x, z)
+pub type CellBounds = ((i32, i32), (i32, i32));
+
+/// A circuit placed and routed as a block-level redstone layout
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoutedLayout {
+    layout: BlockLayout,
+    /// Nets that could not be routed completely
+    unrouted_nets: Vec<NetId>,
+    /// The cell of every component
+    cells: HashMap<ComponentId, CellBounds>,
+}
+
+impl RoutedLayout {
+    pub fn get_layout(&self) -> &BlockLayout {
+        &self.layout
+    }
+    pub fn get_unrouted_nets(&self) -> &[NetId] {
+        &self.unrouted_nets
+    }
+    pub fn get_cells(&self) -> &HashMap<ComponentId, CellBounds> {
+        &self.cells
+    }
+}
+
+#[cfg(test)]
+impl RoutedLayout {
+    /// A layout built by hand, without cells or nets
+    pub fn from_layout(layout: BlockLayout) -> Self {
+        Self {
+            layout,
+            ..Self::default()
+        }
+    }
+}
+
+/// The first blocks of a wire leaving a port, straight out of the cell
+struct Stub {
+    /// Starting at the cell
+    nodes: Vec<RouteNode>,
+    direction: Direction,
+    inverted: bool,
+}
+
+impl Stub {
+    fn get_end(&self) -> RouteNode {
+        self.nodes[self.nodes.len() - 1]
+    }
+}
+
+/// A routed net as a tree of dust growing from its driver
+struct RoutedNet {
+    root: RouteNode,
+    parents: HashMap<RouteNode, RouteNode>,
+}
+
+/// Places the cells of the components in columns by their logic level, inputs on the left and outputs on the right,
+/// and routes the nets between them on the routing layers, inserting repeaters where the signal gets too weak.
+pub fn place_and_route(circuit: &Circuit) -> RoutedLayout {
+    let circuit = &expand_xor_gates(&expand_jk_flip_flops(&expand_buses(circuit)));
+    let cells: HashMap<ComponentId, Cell> = circuit
+        .get_components()
+        .iter()
+        .map(|c| (c.get_id(), Cell::of(c, circuit)))
+        .collect();
+    let origins = place(circuit, &cells);
+
+    let mut layout = BlockLayout::new();
+    let mut router = Router::new(get_routing_bounds(&cells, &origins));
+    for (id, cell) in &cells {
+        let origin = origins[id];
+        cell.place(&mut layout, origin);
+        let (width, length) = cell.get_size();
+        for x in origin.0 - 1..=origin.0 + width {
+            for z in origin.1 - 1..=origin.1 + length {
+                router.block(RouteNode::new(x, z, 0));
+                let inside =
+                    x >= origin.0 && x < origin.0 + width && z >= origin.1 && z < origin.1 + length;
+                if inside {
+                    (1..ROUTING_LAYERS).for_each(|layer| router.block(RouteNode::new(x, z, layer)));
+                }
+            }
+        }
+    }
+
+    let mut stubs: HashMap<PortRef, Stub> = HashMap::new();
+    for (index, net) in circuit.get_nets().iter().enumerate() {
+        for port in net.get_ports() {
+            if let Some(stub) = get_stub(circuit, &cells, &origins, *port) {
+                stub.nodes.iter().for_each(|n| router.reserve(*n, index));
+                stubs.insert(*port, stub);
+            }
+        }
+    }
+
+    // the driver and the sinks of every net
+    let mut terminals = HashMap::new();
+    for (index, net) in circuit.get_nets().iter().enumerate() {
+        let (drivers, sinks): (Vec<_>, Vec<_>) = net
+            .get_ports()
+            .iter()
+            .filter(|p| stubs.contains_key(p))
+            .partition(|p| cells[&p.component].is_driver(p.port));
+        // a net without a driver is routed from any of its ports
+        let mut ports = drivers.into_iter().chain(sinks);
+        let Some(root) = ports.next().map(|p| stubs[p].get_end()) else {
+            continue;
+        };
+        let mut sinks: Vec<_> = ports.map(|p| stubs[p].get_end()).collect();
+        sinks.sort_by_key(|s| s.x.abs_diff(root.x) + s.z.abs_diff(root.z));
+        terminals.insert(index, (root, sinks));
+    }
+
+    // short nets first, nets that failed are moved to the front and everything is routed again
+    let mut order: Vec<_> = terminals.keys().copied().collect();
+    order.sort_by_key(|index| (get_net_extent(&terminals[index]), *index));
+    let mut best = route_nets(router.clone(), &order, &terminals);
+    let mut failed = best.1.clone();
+    for _ in 0..ROUTING_ATTEMPTS {
+        if failed.is_empty() {
+            break;
+        }
+        order.retain(|index| !failed.contains(index));
+        order.splice(0..0, failed);
+        let attempt = route_nets(router.clone(), &order, &terminals);
+        failed = attempt.1.clone();
+        if attempt.1.len() < best.1.len() {
+            best = attempt;
+        }
+    }
+    let (routed_nets, failed) = best;
+    let unrouted_nets = failed
+        .iter()
+        .map(|index| circuit.get_nets()[*index].get_id())
+        .collect();
+
+    for stub in stubs.values() {
+        place_stub(&mut layout, stub);
+    }
+    let stub_nodes: HashSet<_> = stubs
+        .values()
+        .flat_map(|s| s.nodes.iter().copied())
+        .collect();
+    for net in routed_nets.values() {
+        place_net(&mut layout, net, &stub_nodes);
+    }
+    place_floor(&mut layout);
+    place_annotations(&mut layout, circuit, &origins, &stubs);
+    let cells = origins
+        .iter()
+        .map(|(id, origin)| (*id, (*origin, cells[id].get_size())))
+        .collect();
+    RoutedLayout {
+        layout,
+        unrouted_nets,
+        cells,
+    }
+}
+
+/// Replaces every JK flip-flop by a D flip-flop storing `J & !Q | !K & Q`.
+/// The feedback of its output into the gates in front of it would cross the clock inside a cell.
+fn expand_jk_flip_flops(circuit: &Circuit) -> Circuit {
+    const JK_FLIP_FLOP: ComponentType = ComponentType::Sequential(SequentialType::JkFlipFlop);
+
+    let mut expanded = circuit.clone();
+    // the parts now realising the ports of the flip-flops
+    let mut replacements = HashMap::new();
+    for component in circuit.get_components() {
+        if component.get_component_type() != JK_FLIP_FLOP {
+            continue;
+        }
+        let id = component.get_id();
+        let position = component.get_position();
+        expanded.remove_component(id);
+        let flip_flop = expanded.add_component(part(
+            ComponentType::Sequential(SequentialType::DFlipFlop),
+            position,
+            &[
+                ConnectionKind::Input,
+                ConnectionKind::Input,
+                ConnectionKind::Output,
+                ConnectionKind::Output,
+            ],
+        ));
+        let set = expanded.add_component(
+            part(
+                ComponentType::LogicGate(LogicGateType::And),
+                position,
+                &GATE_PORTS,
+            )
+            .with_inverted_inputs(vec![false, true]),
+        );
+        let keep = expanded.add_component(
+            part(
+                ComponentType::LogicGate(LogicGateType::And),
+                position,
+                &GATE_PORTS,
+            )
+            .with_inverted_inputs(vec![true, false]),
+        );
+        let next = expanded.add_component(part(
+            ComponentType::LogicGate(LogicGateType::Or),
+            position,
+            &GATE_PORTS,
+        ));
+        for (from, to) in [
+            (PortRef::new(set, 2), PortRef::new(next, 0)),
+            (PortRef::new(keep, 2), PortRef::new(next, 1)),
+            (PortRef::new(next, 2), PortRef::new(flip_flop, 0)),
+            (PortRef::new(flip_flop, 2), PortRef::new(set, 1)),
+            (PortRef::new(flip_flop, 2), PortRef::new(keep, 1)),
+        ] {
+            expanded.add_wire(from, to);
+        }
+        // J, the clock, K, Q and its inverse
+        let ports = [
+            PortRef::new(set, 0),
+            PortRef::new(flip_flop, 1),
+            PortRef::new(keep, 0),
+            PortRef::new(flip_flop, 2),
+            PortRef::new(flip_flop, 3),
+        ];
+        for (port, replacement) in ports.into_iter().enumerate() {
+            replacements.insert(PortRef::new(id, port), replacement);
+        }
+    }
+    reconnect_nets(circuit, &mut expanded, &replacements);
+    expanded
+}
+
+/// Replaces every XOR and XNOR gate with more than two inputs by a chain of two input gates computing the parity.
+/// Only the last gate of the chain of an XNOR inverts.
+fn expand_xor_gates(circuit: &Circuit) -> Circuit {
+    let mut expanded = circuit.clone();
+    // the gates of the chains now realising the ports of the gates
+    let mut replacements = HashMap::new();
+    for component in circuit.get_components() {
+        let ComponentType::LogicGate(gate_type @ (LogicGateType::Xor | LogicGateType::Xnor)) =
+            component.get_component_type()
+        else {
+            continue;
+        };
+        let inputs = component
+            .get_ports()
+            .iter()
+            .filter(|p| p.get_kind() == ConnectionKind::Input)
+            .count();
+        if inputs <= 2 {
+            continue;
+        }
+        let id = component.get_id();
+        expanded.remove_component(id);
+        let gates: Vec<_> = (1..inputs)
+            .map(|stage| {
+                let last = stage == inputs - 1;
+                let gate_type = if last { gate_type } else { LogicGateType::Xor };
+                // the first gate reads the first two inputs, every further one the previous gate and the next input
+                let inverted = if stage == 1 {
+                    vec![
+                        component.is_input_inverted(0),
+                        component.is_input_inverted(1),
+                    ]
+                } else {
+                    vec![false, component.is_input_inverted(stage)]
+                };
+                expanded.add_component(
+                    part(
+                        ComponentType::LogicGate(gate_type),
+                        component.get_position(),
+                        &GATE_PORTS,
+                    )
+                    .with_inverted_inputs(inverted),
+                )
+            })
+            .collect();
+        for pair in gates.windows(2) {
+            expanded.add_wire(PortRef::new(pair[0], 2), PortRef::new(pair[1], 0));
+        }
+        let mut ports = vec![PortRef::new(gates[0], 0), PortRef::new(gates[0], 1)];
+        ports.extend(gates[1..].iter().map(|gate| PortRef::new(*gate, 1)));
+        ports.push(PortRef::new(gates[gates.len() - 1], 2));
+        for (port, replacement) in ports.into_iter().enumerate() {
+            replacements.insert(PortRef::new(id, port), replacement);
+        }
+    }
+    reconnect_nets(circuit, &mut expanded, &replacements);
+    expanded
+}
+
+/// A component added by an expansion, its ports only have a kind as it is never drawn
+fn part(
+    component_type: ComponentType,
+    position: (f64, f64),
+    kinds: &[ConnectionKind],
+) -> Component {
+    let ports = kinds
+        .iter()
+        .map(|kind| ConnectionPoint::new(0.0, 0.0, [false; 4], *kind))
+        .collect();
+    Component::new(component_type, position, (0.0, 0.0), ports)
+}
+
+/// Connects the nets of replaced ports again with the ports replacing them,
+/// as the wires to the replaced components were removed with them
+fn reconnect_nets(
+    circuit: &Circuit,
+    expanded: &mut Circuit,
+    replacements: &HashMap<PortRef, PortRef>,
+) {
+    for net in circuit.get_nets() {
+        if !net.get_ports().iter().any(|p| replacements.contains_key(p)) {
+            continue;
+        }
+        let ports: Vec<_> = net
+            .get_ports()
+            .iter()
+            .map(|p| replacements.get(p).copied().unwrap_or(*p))
+            .collect();
+        for port in &ports[1..] {
+            expanded.add_wire(ports[0], *port);
+        }
+    }
+}
+
+/// Replaces every bus by its bits, as the cells only carry single bits. A pin carrying a bus becomes a pin for every
+/// bit, and splitters and mergers become the connections of every bit to the bit of the bus in the same place.
+fn expand_buses(circuit: &Circuit) -> Circuit {
+    /// A bit of a net
+    type Lane = (NetId, u8);
+    fn find(parents: &HashMap<Lane, Lane>, mut lane: Lane) -> Lane {
+        while let Some(parent) = parents.get(&lane) {
+            lane = *parent;
+        }
+        lane
+    }
+
+    let mut expanded = circuit.clone();
+    // the single bit pins now realising the bits of a pin carrying a bus
+    let mut bits: HashMap<PortRef, Vec<PortRef>> = HashMap::new();
+    // the bus port and the bit ports of the splitters and mergers
+    let mut splitters: Vec<(PortRef, Vec<PortRef>)> = Vec::new();
+    for component in circuit.get_components() {
+        let id = component.get_id();
+        match component.get_component_type() {
+            ComponentType::Splitter(width) => {
+                let ports = (1..=usize::from(width))
+                    .map(|p| PortRef::new(id, p))
+                    .collect();
+                splitters.push((PortRef::new(id, 0), ports));
+            }
+            ComponentType::Merger(width) => {
+                let ports = (0..usize::from(width))
+                    .map(|p| PortRef::new(id, p))
+                    .collect();
+                splitters.push((PortRef::new(id, usize::from(width)), ports));
+            }
+            ComponentType::Pin(direction, width) if width > 1 => {
+                let (x, y) = component.get_position();
+                let ports = component
+                    .get_ports()
+                    .iter()
+                    .map(|p| p.with_width(1))
+                    .collect();
+                let pins = (0..width)
+                    .map(|bit| {
+                        let pin = Component::new(
+                            ComponentType::Pin(direction, 1),
+                            (x, y + f64::from(bit) * component.get_height()),
+                            (component.get_width(), component.get_height()),
+                            Vec::clone(&ports),
+                        )
+                        .with_label(format!("{}{bit}", component.get_label()));
+                        PortRef::new(expanded.add_component(pin), 0)
+                    })
+                    .collect();
+                bits.insert(PortRef::new(id, 0), pins);
+            }
+            _ => continue,
+        }
+        expanded.remove_component(id);
+    }
+    if bits.is_empty() && splitters.is_empty() {
+        return expanded;
+    }
+
+    // joins the bits of every bus with the bit ports of the splitters and mergers on it
+    let net_of = |port: PortRef| circuit.get_net_of(port).map(|net| net.get_id());
+    let mut parents = HashMap::new();
+    for (bus, ports) in &splitters {
+        let Some(bus) = net_of(*bus) else {
+            continue;
+        };
+        for (bit, port) in (0..).zip(ports) {
+            let Some(net) = net_of(*port) else {
+                continue;
+            };
+            let (a, b) = (find(&parents, (bus, bit)), find(&parents, (net, 0)));
+            if a != b {
+                parents.insert(a, b);
+            }
+        }
+    }
+    let removed = |port: &PortRef| {
+        bits.contains_key(port)
+            || splitters
+                .iter()
+                .any(|(bus, _)| bus.component == port.component)
+    };
+    let mut lanes: HashMap<Lane, Vec<PortRef>> = HashMap::new();
+    for net in circuit.get_nets() {
+        if !net.get_ports().iter().any(removed) {
+            continue;
+        }
+        for port in net.get_ports() {
+            if let Some(pins) = bits.get(port) {
+                for (bit, pin) in (0..).zip(pins) {
+                    lanes
+                        .entry(find(&parents, (net.get_id(), bit)))
+                        .or_default()
+                        .push(*pin);
+                }
+            } else if !removed(port) {
+                lanes
+                    .entry(find(&parents, (net.get_id(), 0)))
+                    .or_default()
+                    .push(*port);
+            }
+        }
+    }
+    // the wires to the removed components were removed with them, so the bits are connected again
+    for ports in lanes.values() {
+        for port in ports.iter().skip(1) {
+            expanded.add_wire(ports[0], *port);
+        }
+    }
+    expanded
+}
+
+/// Routes the nets in the given order, returns the routed nets and the nets that could not be routed completely
+fn route_nets(
+    mut router: Router,
+    order: &[usize],
+    terminals: &HashMap<usize, (RouteNode, Vec<RouteNode>)>,
+) -> (HashMap<usize, RoutedNet>, Vec<usize>) {
+    let mut routed_nets = HashMap::new();
+    let mut failed = Vec::new();
+    for index in order {
+        let (root, sinks) = &terminals[index];
+        let mut tree = HashSet::from([*root]);
+        let mut parents = HashMap::new();
+        for sink in sinks {
+            match router.route(*index, &tree, *sink) {
+                Some(path) => {
+                    for pair in path.windows(2) {
+                        parents.insert(pair[1], pair[0]);
+                    }
+                    tree.extend(path);
+                }
+                None if !failed.contains(index) => failed.push(*index),
+                None => {}
+            }
+        }
+        routed_nets.insert(
+            *index,
+            RoutedNet {
+                root: *root,
+                parents,
+            },
+        );
+    }
+    (routed_nets, failed)
+}
+
+/// Assigns every component a column by its logic level and stacks the cells of a column,
+/// ordered by the position of the cells driving them to avoid crossings
+fn place(
+    circuit: &Circuit,
+    cells: &HashMap<ComponentId, Cell>,
+) -> HashMap<ComponentId, (i32, i32)> {
+    // edges from the component driving a net to the components reading it
+    let mut edges: Vec<(ComponentId, ComponentId)> = Vec::new();
+    for net in circuit.get_nets() {
+        let (drivers, sinks): (Vec<&PortRef>, Vec<&PortRef>) = net
+            .get_ports()
+            .iter()
+            .partition(|p| cells.get(&p.component).is_some_and(|c| c.is_driver(p.port)));
+        for driver in &drivers {
+            edges.extend(sinks.iter().map(|s| (driver.component, s.component)));
+        }
+    }
+
+    // longest path levels, loops stop growing after as many rounds as there are components
+    let components = circuit.get_components();
+    let mut levels: HashMap<ComponentId, usize> =
+        components.iter().map(|c| (c.get_id(), 0)).collect();
+    for _ in 0..components.len() {
+        let mut changed = false;
+        for (from, to) in &edges {
+            let level = levels[from] + 1;
+            if !cells[to].is_source() && levels[to] < level && level <= components.len() {
+                levels.insert(*to, level);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    let last_level = levels.values().copied().max().unwrap_or_default() + 1;
+    for component in components {
+        if cells[&component.get_id()].is_sink() {
+            levels.insert(component.get_id(), last_level);
+        }
+    }
+
+    let mut origins = HashMap::new();
+    let mut x = 0;
+    for level in 0..=last_level {
+        let column: Vec<_> = components
+            .iter()
+            .filter(|c| levels[&c.get_id()] == level)
+            .collect();
+        if column.is_empty() {
+            continue;
+        }
+        // the average height of the drivers, falling back to the position on the canvas
+        #[allow(clippy::cast_precision_loss)]
+        let barycenter = |id: ComponentId, position: (f64, f64)| {
+            let drivers: Vec<_> = edges
+                .iter()
+                .filter(|(_, to)| *to == id)
+                .filter_map(|(from, _)| origins.get(from).map(|(_, z): &(i32, i32)| f64::from(*z)))
+                .collect();
+            if drivers.is_empty() {
+                position.1 / BLOCK_SIZE
+            } else {
+                drivers.iter().sum::<f64>() / drivers.len() as f64
+            }
+        };
+        let mut column: Vec<_> = column
+            .into_iter()
+            .map(|c| (barycenter(c.get_id(), c.get_position()), c))
+            .collect();
+        column.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut z = 0;
+        let mut width = 0;
+        let mut inputs = 0;
+        for (_, component) in column {
+            let cell = &cells[&component.get_id()];
+            let size = cell.get_size();
+            origins.insert(component.get_id(), (x, z));
+            z += size.1 + CELL_SPACING;
+            width = width.max(size.0);
+            inputs += component
+                .get_ports()
+                .iter()
+                .filter(|p| p.get_kind() != ConnectionKind::Output)
+                .count();
+        }
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let channel = CHANNEL_WIDTH + 2 * inputs as i32;
+        x += width + channel;
+    }
+    origins
+}
+
+fn get_routing_bounds(
+    cells: &HashMap<ComponentId, Cell>,
+    origins: &HashMap<ComponentId, (i32, i32)>,
+) -> ((i32, i32), (i32, i32)) {
+    let (mut min, mut max) = ((0, 0), (0, 0));
+    for (id, cell) in cells {
+        let origin = origins[id];
+        let size = cell.get_size();
+        min = (min.0.min(origin.0), min.1.min(origin.1));
+        max = (max.0.max(origin.0 + size.0), max.1.max(origin.1 + size.1));
+    }
+    (
+        (min.0 - ROUTING_MARGIN, min.1 - ROUTING_MARGIN),
+        (max.0 + ROUTING_MARGIN, max.1 + ROUTING_MARGIN),
+    )
+}
+
+/// Returns the first blocks of the wire of a port. An inverted input gets a torch on a block in front of it.
+fn get_stub(
+    circuit: &Circuit,
+    cells: &HashMap<ComponentId, Cell>,
+    origins: &HashMap<ComponentId, (i32, i32)>,
+    port: PortRef,
+) -> Option<Stub> {
+    let component = circuit.get_component(port.component)?;
+    let ((x, z), direction) = cells.get(&port.component)?.get_port(port.port)?;
+    let origin = origins.get(&port.component)?;
+    let ports = component.get_ports();
+    let inverted = ports.get(port.port)?.get_kind() == ConnectionKind::Input
+        && component.is_input_inverted(
+            ports[..port.port]
+                .iter()
+                .filter(|p| p.get_kind() == ConnectionKind::Input)
+                .count(),
+        );
+    let length = if inverted { 4 } else { 2 };
+    let start = RouteNode::new(origin.0 + x, origin.1 + z, 0);
+    let offset = direction.get_offset();
+    Some(Stub {
+        nodes: (0..length)
+            .map(|i| RouteNode::new(start.x + offset.0 * i, start.z + offset.1 * i, 0))
+            .collect(),
+        direction,
+        inverted,
+    })
+}
+
+fn get_net_extent((root, sinks): &(RouteNode, Vec<RouteNode>)) -> u32 {
+    let (mut min, mut max) = ((root.x, root.z), (root.x, root.z));
+    for sink in sinks {
+        min = (min.0.min(sink.x), min.1.min(sink.z));
+        max = (max.0.max(sink.x), max.1.max(sink.z));
+    }
+    max.0.abs_diff(min.0) + max.1.abs_diff(min.1)
+}
+
+const fn get_height(layer: u8) -> i32 {
+    LOGIC_LAYER + 2 * layer as i32
+}
+
+fn place_stub(layout: &mut BlockLayout, stub: &Stub) {
+    for (i, node) in stub.nodes.iter().enumerate() {
+        let position = (node.x, get_height(node.layer), node.z);
+        let block = match i {
+            // the signal flows into the cell, away from the block the torch is attached to
+            0 if stub.inverted => Block::wall_torch(stub.direction.get_opposite()),
+            1 if stub.inverted => Block::solid(),
+            _ => Block::dust(),
+        };
+        layout.set_block(position, block);
+    }
+}
+
+/// Places the dust of the net, with vias where it changes the layer and repeaters on straight runs
+fn place_net(layout: &mut BlockLayout, net: &RoutedNet, stubs: &HashSet<RouteNode>) {
+    let mut children: HashMap<RouteNode, Vec<RouteNode>> = HashMap::new();
+    for (child, parent) in &net.parents {
+        children.entry(*parent).or_default().push(*child);
+    }
+    let repeaters = get_repeaters(net, &children, stubs);
+
+    let mut stack = vec![net.root];
+    while let Some(node) = stack.pop() {
+        let next = children.get(&node).map(Vec::as_slice).unwrap_or_default();
+        let position = (node.x, get_height(node.layer), node.z);
+        if !stubs.contains(&node) {
+            let repeater = repeaters.get(&node).copied();
+            layout.set_block(position, repeater.map_or_else(Block::dust, Block::repeater));
+        }
+        if node.layer > 0 {
+            layout.set_block((node.x, position.1 - 1, node.z), Block::solid());
+        }
+        for child in next {
+            if child.layer != node.layer {
+                let (x, z) = node.get_via_middle(child);
+                let height = get_height(node.layer.min(child.layer)) + 1;
+                layout.set_block((x, height, z), Block::dust());
+                layout.set_block((x, height - 1, z), Block::solid());
+            }
+            stack.push(*child);
+        }
+    }
+}
+
+/// Chooses the blocks of the net that become repeaters, so no dust is more than [`MAX_DUST_RUN`] blocks behind the
+/// driver or a repeater. A repeater needs a straight run on one layer, so a run that turns, branches or changes the
+/// layer too late gets its repeater on the last straight block before that.
+fn get_repeaters(
+    net: &RoutedNet,
+    children: &HashMap<RouteNode, Vec<RouteNode>>,
+    stubs: &HashSet<RouteNode>,
+) -> HashMap<RouteNode, Direction> {
+    // the direction a repeater on the block would face, if it can hold one
+    let get_direction = |node: RouteNode| {
+        let parent = net.parents.get(&node)?;
+        let [child] = children.get(&node).map(Vec::as_slice).unwrap_or_default() else {
+            return None;
+        };
+        if stubs.contains(&node) || parent.layer != node.layer || child.layer != node.layer {
+            return None;
+        }
+        Direction::between((parent.x, 0, parent.z), (node.x, 0, node.z))
+            .filter(|d| Direction::between((node.x, 0, node.z), (child.x, 0, child.z)) == Some(*d))
+    };
+    // blocks moved back to from a run that got too long
+    let mut forced = HashSet::new();
+    'placement: loop {
+        let mut repeaters = HashMap::new();
+        // the dust since the driver or the last repeater, and the last block of it that can hold a repeater
+        let mut stack = vec![(net.root, 0, None)];
+        while let Some((node, run, straight)) = stack.pop() {
+            // a via adds the dust of its staircase
+            let via = net
+                .parents
+                .get(&node)
+                .is_some_and(|p| p.layer != node.layer);
+            let run = run + 1 + u32::from(via);
+            let direction = get_direction(node);
+            let (run, straight) = match direction {
+                Some(direction) if run >= MAX_DUST_RUN || forced.contains(&node) => {
+                    repeaters.insert(node, direction);
+                    (0, None)
+                }
+                Some(_) => (run, Some(node)),
+                None if run > MAX_DUST_RUN => {
+                    if let Some(straight) = straight {
+                        forced.insert(straight);
+                        continue 'placement;
+                    }
+                    // nothing to refresh the signal on, the sinks behind it may stay unpowered
+                    (run, None)
+                }
+                None => (run, straight),
+            };
+            for child in children.get(&node).map(Vec::as_slice).unwrap_or_default() {
+                stack.push((*child, run, straight));
+            }
+        }
+        return repeaters;
+    }
+}
+
+/// Places a sign with the text of every annotation above the cell of the component or the start of the net it is
+/// attached to. Free annotations stand in a row in front of the layout.
+fn place_annotations(
+    layout: &mut BlockLayout,
+    circuit: &Circuit,
+    origins: &HashMap<ComponentId, (i32, i32)>,
+    stubs: &HashMap<PortRef, Stub>,
+) {
+    // above the highest routing layer, on a block of its own
+    let height = get_height(ROUTING_LAYERS);
+    let Some((min, _)) = layout.get_bounds() else {
+        return;
+    };
+    let mut annotations: Vec<_> = circuit
+        .get_annotations()
+        .iter()
+        .filter(|a| !a.get_text().trim().is_empty())
+        .collect();
+    annotations.sort_by(|a, b| {
+        let (a, b) = (a.get_position(), b.get_position());
+        a.1.total_cmp(&b.1).then(a.0.total_cmp(&b.0))
+    });
+    let mut free_x = min.0;
+    for annotation in annotations {
+        let anchored = match annotation.get_anchor() {
+            Some(AnnotationAnchor::Component(id)) => origins.get(&id).copied(),
+            Some(AnnotationAnchor::Wire(id)) => circuit
+                .get_wire(id)
+                .and_then(|w| stubs.get(&w.get_from()).or_else(|| stubs.get(&w.get_to())))
+                .map(|stub| (stub.get_end().x, stub.get_end().z)),
+            None => None,
+        };
+        let (mut x, z) = anchored.unwrap_or_else(|| {
+            free_x += 2;
+            (free_x - 2, min.2 - 2)
+        });
+        // the signs of the same cell or net stand next to each other
+        while layout.get_block((x, height, z)).is_some() {
+            x += 1;
+        }
+        layout.set_block((x, height - 1, z), Block::solid());
+        layout.set_sign(
+            (x, height, z),
+            annotation.get_rotation() * 4,
+            SignText::new(annotation.get_text(), annotation.get_color()),
+        );
+    }
+}
+
+/// Places solid blocks below the logic layer to carry the dust and torches
+fn place_floor(layout: &mut BlockLayout) {
+    let floor: Vec<_> = layout
+        .get_blocks()
+        .filter(|((_, y, _), _)| *y == LOGIC_LAYER)
+        .map(|((x, y, z), _)| (*x, y - 1, *z))
+        .collect();
+    for position in floor {
+        layout.set_block(position, Block::solid());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::PinDirection;
+
+    fn add(
+        circuit: &mut Circuit,
+        component_type: ComponentType,
+        kinds: &[ConnectionKind],
+    ) -> ComponentId {
+        circuit.add_component(part(component_type, (0.0, 0.0), kinds))
+    }
+    fn lever(circuit: &mut Circuit) -> ComponentId {
+        add(circuit, ComponentType::Lever, &[ConnectionKind::Output])
+    }
+    fn lamp(circuit: &mut Circuit) -> ComponentId {
+        add(circuit, ComponentType::Lamp, &[ConnectionKind::Input])
+    }
+    /// A net on the lowest layer following the paths, each of them starts at a block of the ones before
+    fn routed_net(paths: &[&[(i32, i32)]]) -> RoutedNet {
+        let node = |(x, z): (i32, i32)| RouteNode::new(x, z, 0);
+        let mut parents = HashMap::new();
+        for path in paths {
+            for pair in path.windows(2) {
+                parents.insert(node(pair[1]), node(pair[0]));
+            }
+        }
+        RoutedNet {
+            root: node(paths[0][0]),
+            parents,
+        }
+    }
+    /// The blocks from one block to another, turning once from x to z
+    fn line(from: (i32, i32), to: (i32, i32)) -> Vec<(i32, i32)> {
+        let step = |from: i32, to: i32| if to < from { -1 } else { 1 };
+        let mut blocks = vec![from];
+        let (mut x, mut z) = from;
+        while x != to.0 {
+            x += step(x, to.0);
+            blocks.push((x, z));
+        }
+        while z != to.1 {
+            z += step(z, to.1);
+            blocks.push((x, z));
+        }
+        blocks
+    }
+    /// Returns the most dust any block of the placed net is behind the driver or a repeater
+    fn get_longest_run(layout: &BlockLayout, net: &RoutedNet) -> u32 {
+        let mut children: HashMap<RouteNode, Vec<RouteNode>> = HashMap::new();
+        for (child, parent) in &net.parents {
+            children.entry(*parent).or_default().push(*child);
+        }
+        let mut longest = 0;
+        let mut stack = vec![(net.root, 0)];
+        while let Some((node, run)) = stack.pop() {
+            let block = layout
+                .get_block((node.x, get_height(node.layer), node.z))
+                .unwrap();
+            let run = if block.get_name() == Block::dust().get_name() {
+                run + 1
+            } else {
+                0
+            };
+            longest = longest.max(run);
+            for child in children.get(&node).into_iter().flatten() {
+                stack.push((*child, run));
+            }
+        }
+        longest
+    }
+
+    #[test]
+    fn every_input_of_a_wide_xor_is_wired() {
+        let mut circuit = Circuit::new();
+        let levers: Vec<_> = (0..3).map(|_| lever(&mut circuit)).collect();
+        let xor = add(
+            &mut circuit,
+            ComponentType::LogicGate(LogicGateType::Xnor),
+            &[
+                ConnectionKind::Input,
+                ConnectionKind::Input,
+                ConnectionKind::Input,
+                ConnectionKind::Output,
+            ],
+        );
+        let lamp = lamp(&mut circuit);
+        for (input, lever) in levers.iter().enumerate() {
+            circuit.add_wire(PortRef::new(*lever, 0), PortRef::new(xor, input));
+        }
+        circuit.add_wire(PortRef::new(xor, 3), PortRef::new(lamp, 0));
+
+        let expanded = expand_xor_gates(&circuit);
+        let gates: Vec<_> = expanded
+            .get_components()
+            .iter()
+            .filter(|c| matches!(c.get_component_type(), ComponentType::LogicGate(_)))
+            .collect();
+        assert_eq!(gates.len(), 2);
+        // only the last gate of the chain inverts
+        let inverting = gates
+            .iter()
+            .filter(|c| c.get_component_type() == ComponentType::LogicGate(LogicGateType::Xnor))
+            .count();
+        assert_eq!(inverting, 1);
+        for port in levers
+            .iter()
+            .map(|l| PortRef::new(*l, 0))
+            .chain([PortRef::new(lamp, 0)])
+        {
+            let net = expanded.get_net_of(port).unwrap();
+            let gate_port = net
+                .get_ports()
+                .iter()
+                .find(|p| p.component != port.component)
+                .unwrap();
+            let gate = expanded.get_component(gate_port.component).unwrap();
+            assert!(Cell::of(gate, &expanded).get_port(gate_port.port).is_some());
+        }
+
+        let routed = place_and_route(&circuit);
+        assert!(routed.get_unrouted_nets().is_empty());
+        assert_eq!(routed.get_cells().len(), 6);
+    }
+
+    #[test]
+    fn turning_nets_are_repeated_before_the_turn() {
+        let net = routed_net(&[&line((0, 0), (13, 7))]);
+        let mut layout = BlockLayout::new();
+        place_net(&mut layout, &net, &HashSet::new());
+        assert!(get_longest_run(&layout, &net) <= 15);
+    }
+
+    #[test]
+    fn branching_nets_are_repeated_before_the_branch() {
+        // the branches turn at every block right after the fork
+        let staircase = |z: i32| {
+            let mut blocks = vec![(12, 0)];
+            for step in 1..=3 {
+                blocks.push((11 + step, z * step));
+                blocks.push((12 + step, z * step));
+            }
+            blocks.extend(line((15, 4 * z), (20, 4 * z)));
+            blocks
+        };
+        let net = routed_net(&[&line((0, 0), (12, 0)), &staircase(1), &staircase(-1)]);
+        let mut layout = BlockLayout::new();
+        place_net(&mut layout, &net, &HashSet::new());
+        assert!(get_longest_run(&layout, &net) <= 15);
+    }
+
+    #[test]
+    fn gates_are_placed_between_their_inputs_and_outputs() {
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        let not = add(
+            &mut circuit,
+            ComponentType::LogicGate(LogicGateType::Not),
+            &[ConnectionKind::Input, ConnectionKind::Output],
+        );
+        let lamp = lamp(&mut circuit);
+        circuit.add_wire(PortRef::new(lever, 0), PortRef::new(not, 0));
+        circuit.add_wire(PortRef::new(not, 1), PortRef::new(lamp, 0));
+
+        let routed = place_and_route(&circuit);
+        assert!(routed.get_unrouted_nets().is_empty());
+        let x = |id| routed.get_cells()[&id].0 .0;
+        assert!(x(lever) < x(not) && x(not) < x(lamp));
+        let blocks: Vec<_> = routed
+            .get_layout()
+            .get_blocks()
+            .map(|(_, b)| b.get_name())
+            .collect();
+        for name in [
+            "minecraft:lever",
+            "minecraft:redstone_wall_torch",
+            "minecraft:redstone_lamp",
+        ] {
+            assert!(blocks.contains(&name), "no {name}");
+        }
+    }
+
+    #[test]
+    fn buses_are_split_into_a_lane_for_every_bit() {
+        let mut circuit = Circuit::new();
+        let pin = circuit.add_component(
+            Component::new(
+                ComponentType::Pin(PinDirection::Input, 2),
+                (0.0, 0.0),
+                (25.0, 25.0),
+                vec![
+                    ConnectionPoint::new(0.0, 0.0, [false; 4], ConnectionKind::Output)
+                        .with_width(2),
+                ],
+            )
+            .with_label("a".to_owned()),
+        );
+        let splitter = add(
+            &mut circuit,
+            ComponentType::Splitter(2),
+            &[
+                ConnectionKind::Input,
+                ConnectionKind::Output,
+                ConnectionKind::Output,
+            ],
+        );
+        let lamps = [lamp(&mut circuit), lamp(&mut circuit)];
+        circuit.add_wire(PortRef::new(pin, 0), PortRef::new(splitter, 0));
+        for (bit, lamp) in lamps.iter().enumerate() {
+            circuit.add_wire(PortRef::new(splitter, bit + 1), PortRef::new(*lamp, 0));
+        }
+
+        let expanded = expand_buses(&circuit);
+        assert_eq!(expanded.get_components().len(), 4);
+        for (bit, lamp) in lamps.iter().enumerate() {
+            let net = expanded.get_net_of(PortRef::new(*lamp, 0)).unwrap();
+            assert_eq!(net.get_ports().len(), 2);
+            let pin = net
+                .get_ports()
+                .iter()
+                .filter_map(|p| expanded.get_component(p.component))
+                .find(|c| c.get_id() != *lamp)
+                .unwrap();
+            assert_eq!(
+                pin.get_component_type(),
+                ComponentType::Pin(PinDirection::Input, 1)
+            );
+            assert_eq!(pin.get_label(), format!("a{bit}"));
+        }
+        let routed = place_and_route(&circuit);
+        assert!(routed.get_unrouted_nets().is_empty());
+        assert_eq!(routed.get_cells().len(), 4);
+    }
+
+    #[test]
+    fn long_nets_get_repeaters() {
+        // the lamps are stacked in a column, so the net runs along all of them
+        let mut circuit = Circuit::new();
+        let lever = lever(&mut circuit);
+        for _ in 0..5 {
+            let lamp = lamp(&mut circuit);
+            circuit.add_wire(PortRef::new(lever, 0), PortRef::new(lamp, 0));
+        }
+
+        let routed = place_and_route(&circuit);
+        assert!(routed.get_unrouted_nets().is_empty());
+        let repeaters = routed
+            .get_layout()
+            .get_blocks()
+            .filter(|(_, b)| b.get_name() == "minecraft:repeater")
+            .count();
+        assert!(repeaters > 0);
+    }
+}
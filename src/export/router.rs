@@ -0,0 +1,200 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use super::Direction;
+
+/// Number of layers dust can be routed on. Layer n carries its dust at y = 1 + 2n on a solid block.
+pub const ROUTING_LAYERS: u8 = 2;
+/// Cost of changing the layer, a staircase of two blocks
+const VIA_COST: u32 = 6;
+
+/// A block of dust on one of the routing layers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RouteNode {
+    pub x: i32,
+    pub z: i32,
+    pub layer: u8,
+}
+
+impl RouteNode {
+    pub const fn new(x: i32, z: i32, layer: u8) -> Self {
+        Self { x, z, layer }
+    }
+    const fn moved(&self, offset: (i32, i32), distance: i32, layer: u8) -> Self {
+        Self::new(
+            self.x + offset.0 * distance,
+            self.z + offset.1 * distance,
+            layer,
+        )
+    }
+    /// Returns the block between two nodes connected by a via
+    pub const fn get_via_middle(&self, other: &Self) -> (i32, i32) {
+        ((self.x + other.x) / 2, (self.z + other.z) / 2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Owner {
+    /// Reserved by a component
+    Blocked,
+    Net(usize),
+}
+
+/// Routes nets as dust through a grid of routing layers.
+///
+/// Dust of different nets is never placed next to each other on the same layer, so nets can't connect by accident.
+/// Nets cross by changing the layer through a via, a staircase of dust climbing from one layer to the next.
+#[derive(Clone)]
+pub struct Router {
+    /// The lowest and highest (x, z) the dust may be placed at
+    bounds: ((i32, i32), (i32, i32)),
+    occupancy: HashMap<RouteNode, Owner>,
+}
+
+impl Router {
+    pub fn new(bounds: ((i32, i32), (i32, i32))) -> Self {
+        Self {
+            bounds,
+            occupancy: HashMap::new(),
+        }
+    }
+
+    /// Prevents routing through the block on the given layer
+    pub fn block(&mut self, node: RouteNode) {
+        self.occupancy.insert(node, Owner::Blocked);
+    }
+    /// Reserves the block for the net, e.g. the first blocks of the wires leaving a cell
+    pub fn reserve(&mut self, node: RouteNode, net: usize) {
+        self.occupancy.insert(node, Owner::Net(net));
+    }
+
+    /// Finds the cheapest path from one of the sources to the target and reserves it for the net.
+    /// The path starts at the source and ends at the target.
+    pub fn route(
+        &mut self,
+        net: usize,
+        sources: &HashSet<RouteNode>,
+        target: RouteNode,
+    ) -> Option<Vec<RouteNode>> {
+        let heuristic =
+            |n: &RouteNode| n.x.abs_diff(target.x) + n.z.abs_diff(target.z) + u32::from(n.layer);
+        let mut costs: HashMap<RouteNode, u32> = HashMap::new();
+        let mut previous: HashMap<RouteNode, RouteNode> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for source in sources {
+            costs.insert(*source, 0);
+            queue.push(Reverse((heuristic(source), 0, *source)));
+        }
+
+        while let Some(Reverse((_, cost, node))) = queue.pop() {
+            if node == target {
+                let mut path = vec![node];
+                while let Some(p) = previous.get(path.last().unwrap_or(&node)) {
+                    path.push(*p);
+                }
+                path.reverse();
+                self.occupy(&path, net);
+                return Some(path);
+            }
+            if costs.get(&node).is_some_and(|c| *c < cost) {
+                continue;
+            }
+            for (next, step_cost) in self.get_moves(node, net, sources, target) {
+                let next_cost = cost + step_cost;
+                if costs.get(&next).is_none_or(|c| next_cost < *c) {
+                    costs.insert(next, next_cost);
+                    previous.insert(next, node);
+                    queue.push(Reverse((next_cost + heuristic(&next), next_cost, next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the nodes reachable from the node with the cost of getting there
+    fn get_moves(
+        &self,
+        node: RouteNode,
+        net: usize,
+        sources: &HashSet<RouteNode>,
+        target: RouteNode,
+    ) -> Vec<(RouteNode, u32)> {
+        let can_enter = |n: RouteNode| {
+            let owned = match self.occupancy.get(&n) {
+                None => true,
+                // reserved blocks of the own net may only be entered at the ends of the path
+                Some(Owner::Net(owner)) => *owner == net && (n == target || sources.contains(&n)),
+                Some(Owner::Blocked) => false,
+            };
+            owned && self.is_free_for(n, net)
+        };
+        let mut moves = Vec::new();
+        for direction in Direction::ALL {
+            let offset = direction.get_offset();
+            let next = node.moved(offset, 1, node.layer);
+            if can_enter(next) {
+                moves.push((next, 1));
+            }
+            for layer in 0..ROUTING_LAYERS {
+                if layer == node.layer {
+                    continue;
+                }
+                let next = node.moved(offset, 2, layer);
+                if can_enter(next)
+                    && self
+                        .get_via_blocks(node, next)
+                        .iter()
+                        .all(|b| self.is_unused_for(*b, net))
+                {
+                    moves.push((next, VIA_COST));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Returns whether the block lies within the bounds and no other net is routed next to it on the same layer
+    fn is_free_for(&self, node: RouteNode, net: usize) -> bool {
+        let ((min_x, min_z), (max_x, max_z)) = self.bounds;
+        (min_x..=max_x).contains(&node.x)
+            && (min_z..=max_z).contains(&node.z)
+            && Direction::ALL.iter().all(|d| {
+                !matches!(self.occupancy.get(&node.moved(d.get_offset(), 1, node.layer)),
+                    Some(Owner::Net(owner)) if *owner != net)
+            })
+    }
+    fn is_unused_for(&self, node: RouteNode, net: usize) -> bool {
+        !self.occupancy.contains_key(&node) && self.is_free_for(node, net)
+    }
+
+    /// Returns the blocks on the routing layers a via between the nodes needs to be empty:
+    /// both layers at the middle of the staircase and the block above its lower end
+    fn get_via_blocks(&self, from: RouteNode, to: RouteNode) -> [RouteNode; 3] {
+        let (lower, upper) = if from.layer < to.layer {
+            (from, to)
+        } else {
+            (to, from)
+        };
+        let (x, z) = from.get_via_middle(&to);
+        [
+            RouteNode::new(x, z, lower.layer),
+            RouteNode::new(x, z, upper.layer),
+            RouteNode::new(lower.x, lower.z, upper.layer),
+        ]
+    }
+
+    fn occupy(&mut self, path: &[RouteNode], net: usize) {
+        for node in path {
+            self.occupancy.insert(*node, Owner::Net(net));
+        }
+        for pair in path.windows(2) {
+            if pair[0].layer != pair[1].layer {
+                for block in self.get_via_blocks(pair[0], pair[1]) {
+                    self.occupancy.insert(block, Owner::Net(net));
+                }
+            }
+        }
+    }
+}
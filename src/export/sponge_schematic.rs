@@ -0,0 +1,150 @@
+use super::{BlockLayout, ExportError, SignText, Tag};
+
+/// The Minecraft data version the exported files target (1.20.1)
+pub const DATA_VERSION: i32 = 3465;
+pub const SPONGE_SCHEMATIC_EXTENSION: &str = "schem";
+/// Version 2 of the Sponge schematic format, as read by WorldEdit
+const SPONGE_SCHEMATIC_VERSION: i32 = 2;
+
+/// Writes the layout as a gzip compressed Sponge schematic
+pub fn to_sponge_schematic(layout: &BlockLayout) -> Result<Vec<u8>, ExportError> {
+    if layout.is_empty() {
+        return Err(ExportError::Empty);
+    }
+    let size = layout.get_size();
+    let (Ok(width), Ok(height), Ok(length)) = (
+        u16::try_from(size.0),
+        u16::try_from(size.1),
+        u16::try_from(size.2),
+    ) else {
+        return Err(ExportError::TooLarge(size));
+    };
+
+    let palette = layout.get_palette();
+    let indices = layout.get_palette_indices(&palette);
+    #[allow(clippy::cast_possible_wrap)]
+    let block_data = indices
+        .into_iter()
+        .flat_map(write_var_int)
+        .map(|b| b as i8)
+        .collect();
+
+    let block_entities = layout
+        .get_signs()
+        .into_iter()
+        .map(|((x, y, z), sign)| {
+            let mut entries = vec![
+                ("Pos".to_string(), Tag::IntArray(vec![x, y, z])),
+                (
+                    "Id".to_string(),
+                    Tag::String(SignText::BLOCK_ENTITY_ID.to_string()),
+                ),
+            ];
+            entries.extend(sign.to_entries());
+            Tag::Compound(entries)
+        })
+        .collect();
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let schematic = Tag::compound([
+        ("Version", Tag::Int(SPONGE_SCHEMATIC_VERSION)),
+        ("DataVersion", Tag::Int(DATA_VERSION)),
+        ("Width", Tag::Short(width as i16)),
+        ("Height", Tag::Short(height as i16)),
+        ("Length", Tag::Short(length as i16)),
+        ("Offset", Tag::IntArray(vec![0, 0, 0])),
+        ("PaletteMax", Tag::Int(palette.len() as i32)),
+        (
+            "Palette",
+            Tag::Compound(
+                palette
+                    .iter()
+                    .enumerate()
+                    .map(|(index, block)| (block.to_string(), Tag::Int(index as i32)))
+                    .collect(),
+            ),
+        ),
+        ("BlockData", Tag::ByteArray(block_data)),
+        ("BlockEntities", Tag::List(block_entities)),
+    ]);
+    Ok(schematic.to_gzip("Schematic")?)
+}
+
+/// Encodes the value as a variable length integer, 7 bits per byte
+#[allow(clippy::cast_possible_truncation)]
+fn write_var_int(mut value: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::nbt::tests::sample_layout;
+
+    /// Decodes the variable length integers of the block data
+    #[allow(clippy::cast_sign_loss)]
+    fn read_var_ints(bytes: &[i8]) -> Vec<usize> {
+        let mut values = Vec::new();
+        let (mut value, mut shift) = (0, 0);
+        for byte in bytes.iter().map(|b| *b as u8) {
+            value |= usize::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 {
+                values.push(value);
+                (value, shift) = (0, 0);
+            }
+        }
+        values
+    }
+
+    #[test]
+    fn schematic_has_the_size_palette_and_blocks_of_the_layout() {
+        let layout = sample_layout();
+        let (name, schematic) = Tag::from_gzip(&to_sponge_schematic(&layout).unwrap());
+        assert_eq!(name, "Schematic");
+        assert_eq!(schematic.get("Width"), Some(&Tag::Short(3)));
+        assert_eq!(schematic.get("Height"), Some(&Tag::Short(2)));
+        assert_eq!(schematic.get("Length"), Some(&Tag::Short(2)));
+
+        let palette = layout.get_palette();
+        assert_eq!(palette.len(), 5);
+        assert_eq!(schematic.get("PaletteMax"), Some(&Tag::Int(5)));
+        let Some(Tag::Compound(entries)) = schematic.get("Palette") else {
+            panic!("no palette");
+        };
+        let written: Vec<_> = entries.iter().map(|(name, _)| name.clone()).collect();
+        let expected: Vec<_> = palette.iter().map(ToString::to_string).collect();
+        assert_eq!(written, expected);
+        for (index, (_, tag)) in entries.iter().enumerate() {
+            assert_eq!(*tag, Tag::Int(i32::try_from(index).unwrap()));
+        }
+
+        let Some(Tag::ByteArray(data)) = schematic.get("BlockData") else {
+            panic!("no block data");
+        };
+        assert_eq!(read_var_ints(data), layout.get_palette_indices(&palette));
+        let Some(Tag::List(entities)) = schematic.get("BlockEntities") else {
+            panic!("no block entities");
+        };
+        assert_eq!(entities.len(), 1);
+        assert_eq!(entities[0].get("Pos"), Some(&Tag::IntArray(vec![1, 0, 0])));
+    }
+
+    #[test]
+    fn large_indices_take_several_bytes() {
+        assert_eq!(write_var_int(5), vec![5]);
+        assert_eq!(write_var_int(300), vec![0xac, 0x02]);
+        #[allow(clippy::cast_possible_wrap)]
+        let bytes: Vec<_> = write_var_int(300).into_iter().map(|b| b as i8).collect();
+        assert_eq!(read_var_ints(&bytes), vec![300]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use super::{BlockLayout, ExportError, SignText, Tag, DATA_VERSION};
+
+pub const STRUCTURE_EXTENSION: &str = "nbt";
+
+/// Writes the layout as a gzip compressed structure file, as saved by structure blocks
+pub fn to_structure(layout: &BlockLayout) -> Result<Vec<u8>, ExportError> {
+    if layout.is_empty() {
+        return Err(ExportError::Empty);
+    }
+    let size = layout.get_size();
+    let (Ok(width), Ok(height), Ok(length)) = (
+        i32::try_from(size.0),
+        i32::try_from(size.1),
+        i32::try_from(size.2),
+    ) else {
+        return Err(ExportError::TooLarge(size));
+    };
+    let vector = |x, y, z| Tag::List(vec![Tag::Int(x), Tag::Int(y), Tag::Int(z)]);
+
+    let palette = layout.get_palette();
+    let indices = layout.get_palette_indices(&palette);
+    let signs: HashMap<_, _> = layout.get_signs().into_iter().collect();
+    // air is stored as well, so placing the structure clears the area like a structure block does
+    let mut blocks = Vec::with_capacity(indices.len());
+    let mut indices = indices.into_iter();
+    for y in 0..height {
+        for z in 0..length {
+            for x in 0..width {
+                #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+                let state = indices.next().unwrap_or_default() as i32;
+                let mut block = vec![
+                    ("state".to_string(), Tag::Int(state)),
+                    ("pos".to_string(), vector(x, y, z)),
+                ];
+                if let Some(sign) = signs.get(&(x, y, z)) {
+                    let mut nbt = vec![(
+                        "id".to_string(),
+                        Tag::String(SignText::BLOCK_ENTITY_ID.to_string()),
+                    )];
+                    nbt.extend(sign.to_entries());
+                    block.push(("nbt".to_string(), Tag::Compound(nbt)));
+                }
+                blocks.push(Tag::Compound(block));
+            }
+        }
+    }
+    let structure = Tag::compound([
+        ("DataVersion", Tag::Int(DATA_VERSION)),
+        ("size", vector(width, height, length)),
+        (
+            "palette",
+            Tag::List(palette.iter().map(|b| b.to_tag()).collect()),
+        ),
+        ("blocks", Tag::List(blocks)),
+        ("entities", Tag::List(Vec::new())),
+    ]);
+    Ok(structure.to_gzip("")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::nbt::tests::sample_layout;
+
+    #[test]
+    fn structure_has_the_size_palette_and_blocks_of_the_layout() {
+        let layout = sample_layout();
+        let (_, structure) = Tag::from_gzip(&to_structure(&layout).unwrap());
+        assert_eq!(
+            structure.get("size"),
+            Some(&Tag::List(vec![Tag::Int(3), Tag::Int(2), Tag::Int(2)]))
+        );
+        let palette = layout.get_palette();
+        assert_eq!(
+            structure.get("palette"),
+            Some(&Tag::List(palette.iter().map(|b| b.to_tag()).collect()))
+        );
+
+        let Some(Tag::List(blocks)) = structure.get("blocks") else {
+            panic!("no blocks");
+        };
+        assert_eq!(blocks.len(), 12);
+        let indices = layout.get_palette_indices(&palette);
+        for block in blocks {
+            let Some(Tag::List(pos)) = block.get("pos") else {
+                panic!("block without a position");
+            };
+            let [Tag::Int(x), Tag::Int(y), Tag::Int(z)] = pos.as_slice() else {
+                panic!("malformed position");
+            };
+            let index = usize::try_from((y * 2 + z) * 3 + x).unwrap();
+            let state = i32::try_from(indices[index]).unwrap();
+            assert_eq!(block.get("state"), Some(&Tag::Int(state)));
+            // the sign is the only block with data
+            assert_eq!(block.get("nbt").is_some(), (*x, *y, *z) == (1, 0, 0));
+        }
+    }
+}
@@ -1,5 +1,8 @@
 use ui::application::Application;
 
+mod circuit;
+mod export;
+mod project;
 mod ui;
 
 #[macro_export]
@@ -0,0 +1,5 @@
+//! The native project file format.
+//!
+//! Like the circuit model this does not depend on the user interface.
+mod project_file;
+pub use project_file::*;
@@ -0,0 +1,594 @@
+use std::{collections::HashMap, fmt::Display};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::circuit::{
+    Annotation, AnnotationAnchor, Circuit, Component, ComponentType, Orientation, PortRef,
+    RuleSettings, Subcircuit, SubcircuitId, SubcircuitLibrary, TextAlignment,
+};
+
+/// Migrations of older project files, the n-th entry migrates a file of version n + 1 to version n + 2.
+/// Every change of the format adds a migration here, so archived designs keep opening.
+const MIGRATIONS: &[fn(Value) -> Value] = &[
+    add_subcircuits,
+    add_pin_directions,
+    add_annotations,
+    add_rule_settings,
+    add_view_settings,
+];
+
+/// Version 2 added user-defined components
+fn add_subcircuits(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("subcircuits".into(), Value::Array(Vec::new()));
+    }
+    value
+}
+
+/// Version 3 gave pins a direction and a width, the pins of older designs are bidirectional single bits
+fn add_pin_directions(mut value: Value) -> Value {
+    let pin = serde_json::json!({ "Pin": ["Bidirectional", 1] });
+    let update = |components: Option<&mut Value>| {
+        let Some(components) = components.and_then(Value::as_array_mut) else {
+            return;
+        };
+        for component_type in components
+            .iter_mut()
+            .filter_map(|c| c.get_mut("component_type"))
+            .filter(|t| *t == "Pin")
+        {
+            *component_type = pin.clone();
+        }
+    };
+    update(value.get_mut("components"));
+    if let Some(subcircuits) = value.get_mut("subcircuits").and_then(Value::as_array_mut) {
+        for subcircuit in subcircuits {
+            update(subcircuit.get_mut("components"));
+        }
+    }
+    value
+}
+
+/// Version 4 added text annotations
+fn add_annotations(mut value: Value) -> Value {
+    let add = |circuit: &mut Value| {
+        if let Some(object) = circuit.as_object_mut() {
+            object.insert("annotations".into(), Value::Array(Vec::new()));
+        }
+    };
+    add(&mut value);
+    if let Some(subcircuits) = value.get_mut("subcircuits").and_then(Value::as_array_mut) {
+        subcircuits.iter_mut().for_each(add);
+    }
+    value
+}
+
+/// Version 5 added the configuration of the design rules, older designs check every rule with its default severity
+fn add_rule_settings(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("rules".into(), Value::Object(serde_json::Map::new()));
+    }
+    value
+}
+
+/// Version 6 saved the zoom, position and grid of the workspace, older designs keep the current view
+fn add_view_settings(mut value: Value) -> Value {
+    if let Some(object) = value.as_object_mut() {
+        object.insert("view".into(), Value::Null);
+    }
+    value
+}
+
+/// Version of the project files written by this version of the application
+#[allow(clippy::cast_possible_truncation)]
+pub const PROJECT_FILE_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+pub const PROJECT_FILE_EXTENSION: &str = "rda";
+
+#[derive(Debug)]
+pub enum ProjectFileError {
+    Json(serde_json::Error),
+    MissingVersion,
+    /// The file was written by a newer version of the application
+    UnsupportedVersion(u64),
+    /// A wire references a component or port that does not exist
+    InvalidWire(usize),
+    /// The component could not be created
+    InvalidComponent(usize, String),
+    /// The circuit of the subcircuit with the given name is not valid
+    InvalidSubcircuit(String, Box<ProjectFileError>),
+    /// An annotation is attached to a component or wire that does not exist
+    InvalidAnnotation(usize),
+}
+
+impl Display for ProjectFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "The project file is not valid: {e}"),
+            Self::MissingVersion => write!(f, "The project file has no version"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "The project file has version {version}, but only versions up to {PROJECT_FILE_VERSION} are supported"
+            ),
+            Self::InvalidWire(wire) => write!(f, "Wire {wire} connects ports that do not exist"),
+            Self::InvalidComponent(component, e) => {
+                write!(f, "Component {component} could not be created: {e}")
+            }
+            Self::InvalidSubcircuit(name, e) => write!(f, "Subcircuit {name}: {e}"),
+            Self::InvalidAnnotation(annotation) => write!(
+                f,
+                "Annotation {annotation} is attached to something that does not exist"
+            ),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ProjectFileError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// A placed component. Ports, size and timing are not saved, they are taken from the component library when loading.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentData {
+    pub component_type: ComponentType,
+    pub position: (f64, f64),
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inverted_inputs: Vec<bool>,
+    #[serde(default, skip_serializing_if = "Orientation::is_identity")]
+    pub orientation: Orientation,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String,
+}
+
+/// A port, referencing the component by its index in [`ProjectFile::components`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortData {
+    pub component: usize,
+    pub port: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WireData {
+    pub from: PortData,
+    pub to: PortData,
+    /// The corners of the wire, empty for a straight wire
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<(f64, f64)>,
+}
+
+/// What an annotation is attached to, referencing it by its index in the components or wires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnchorData {
+    Component(usize),
+    Wire(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationData {
+    pub text: String,
+    /// Relative to the anchor if there is one
+    pub position: (f64, f64),
+    pub font_size: u8,
+    #[serde(default)]
+    pub alignment: TextAlignment,
+    pub color: String,
+    #[serde(default)]
+    pub rotation: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<AnchorData>,
+}
+
+/// How the workspace showed the design when it was saved
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ViewData {
+    pub scale: f64,
+    pub translation: (f64, f64),
+    pub grid_visible: bool,
+    /// Distance between two grid lines in design coordinates
+    pub grid_spacing: f64,
+}
+
+/// The content of a circuit as it is saved
+type CircuitData = (Vec<ComponentData>, Vec<WireData>, Vec<AnnotationData>);
+
+/// A user-defined component, its ports are its pins
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubcircuitData {
+    pub id: SubcircuitId,
+    pub name: String,
+    pub components: Vec<ComponentData>,
+    pub wires: Vec<WireData>,
+    #[serde(default)]
+    pub annotations: Vec<AnnotationData>,
+}
+
+/// The content of a project file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectFile {
+    pub version: u32,
+    pub components: Vec<ComponentData>,
+    pub wires: Vec<WireData>,
+    /// The user-defined components, a subcircuit only contains instances of the subcircuits before it
+    #[serde(default)]
+    pub subcircuits: Vec<SubcircuitData>,
+    #[serde(default)]
+    pub annotations: Vec<AnnotationData>,
+    /// The severities of the design rules the project configures differently from their defaults
+    #[serde(default)]
+    pub rules: RuleSettings,
+    /// The zoom, position and grid of the workspace, `None` if the file does not save them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<ViewData>,
+}
+
+impl ProjectFile {
+    /// Describes the circuit and the user-defined components it can contain
+    pub fn from_circuit(circuit: &Circuit, subcircuits: &SubcircuitLibrary) -> Self {
+        let (components, wires, annotations) = Self::describe(circuit);
+        Self {
+            version: PROJECT_FILE_VERSION,
+            components,
+            wires,
+            subcircuits: subcircuits
+                .iter()
+                .map(|(id, subcircuit)| {
+                    let (components, wires, annotations) = Self::describe(subcircuit.get_circuit());
+                    SubcircuitData {
+                        id,
+                        name: subcircuit.get_name().to_owned(),
+                        components,
+                        wires,
+                        annotations,
+                    }
+                })
+                .collect(),
+            annotations,
+            rules: RuleSettings::default(),
+            view: None,
+        }
+    }
+    pub fn with_rules(self, rules: RuleSettings) -> Self {
+        Self { rules, ..self }
+    }
+    pub fn with_view(self, view: ViewData) -> Self {
+        Self {
+            view: Some(view),
+            ..self
+        }
+    }
+    fn describe(circuit: &Circuit) -> CircuitData {
+        let indices: HashMap<_, _> = circuit
+            .get_components()
+            .iter()
+            .enumerate()
+            .map(|(index, c)| (c.get_id(), index))
+            .collect();
+        let port_data = |port: PortRef| {
+            indices.get(&port.component).map(|component| PortData {
+                component: *component,
+                port: port.port,
+            })
+        };
+        let components = circuit
+            .get_components()
+            .iter()
+            .map(|c| ComponentData {
+                component_type: c.get_component_type(),
+                position: c.get_position(),
+                inverted_inputs: c.get_inverted_inputs().to_vec(),
+                orientation: c.get_orientation(),
+                label: c.get_label().to_owned(),
+            })
+            .collect();
+        let wires = circuit
+            .get_wires()
+            .iter()
+            .filter_map(|w| {
+                Some(WireData {
+                    from: port_data(w.get_from())?,
+                    to: port_data(w.get_to())?,
+                    path: w.get_path().to_vec(),
+                })
+            })
+            .collect();
+        let wire_indices: HashMap<_, _> = circuit
+            .get_wires()
+            .iter()
+            .enumerate()
+            .map(|(index, w)| (w.get_id(), index))
+            .collect();
+        // annotations whose anchor no longer exists are left out
+        let annotations = circuit
+            .get_annotations()
+            .iter()
+            .filter_map(|a| {
+                let anchor = match a.get_anchor() {
+                    Some(AnnotationAnchor::Component(id)) => {
+                        Some(AnchorData::Component(*indices.get(&id)?))
+                    }
+                    Some(AnnotationAnchor::Wire(id)) => {
+                        Some(AnchorData::Wire(*wire_indices.get(&id)?))
+                    }
+                    None => None,
+                };
+                Some(AnnotationData {
+                    text: a.get_text().to_owned(),
+                    position: a.get_position(),
+                    font_size: a.get_font_size(),
+                    alignment: a.get_alignment(),
+                    color: a.get_color().to_owned(),
+                    rotation: a.get_rotation(),
+                    anchor,
+                })
+            })
+            .collect();
+        (components, wires, annotations)
+    }
+
+    /// Creates the circuit and the user-defined components described by the file.
+    /// `create` completes a component read from the file with the definition from the component library.
+    pub fn into_circuit<E: std::fmt::Debug>(
+        self,
+        create: impl Fn(Component, &SubcircuitLibrary) -> Result<Component, E>,
+    ) -> Result<(Circuit, SubcircuitLibrary), ProjectFileError> {
+        let mut subcircuits = SubcircuitLibrary::new();
+        for data in self.subcircuits {
+            let content = (data.components, data.wires, data.annotations);
+            let circuit = Self::build(content, |c| create(c, &subcircuits))
+                .map_err(|e| ProjectFileError::InvalidSubcircuit(data.name.clone(), Box::new(e)))?;
+            subcircuits.restore(data.id, Subcircuit::new(data.name, circuit));
+        }
+        let content = (self.components, self.wires, self.annotations);
+        let circuit = Self::build(content, |c| create(c, &subcircuits))?;
+        Ok((circuit, subcircuits))
+    }
+    fn build<E: std::fmt::Debug>(
+        (components, wires, annotations): CircuitData,
+        create: impl Fn(Component) -> Result<Component, E>,
+    ) -> Result<Circuit, ProjectFileError> {
+        let mut circuit = Circuit::new();
+        let mut ids = Vec::with_capacity(components.len());
+        for (index, data) in components.into_iter().enumerate() {
+            let component =
+                Component::new(data.component_type, data.position, (0.0, 0.0), Vec::new())
+                    .with_inverted_inputs(data.inverted_inputs)
+                    .with_orientation(data.orientation)
+                    .with_label(data.label);
+            let component = create(component)
+                .map_err(|e| ProjectFileError::InvalidComponent(index, format!("{e:?}")))?;
+            ids.push(circuit.add_component(component));
+        }
+        for (index, wire) in wires.iter().enumerate() {
+            let port = |port: PortData| {
+                ids.get(port.component)
+                    .map(|id| PortRef::new(*id, port.port))
+                    .ok_or(ProjectFileError::InvalidWire(index))
+            };
+            circuit
+                .add_routed_wire(port(wire.from)?, port(wire.to)?, wire.path.clone())
+                .ok_or(ProjectFileError::InvalidWire(index))?;
+        }
+        let wire_ids: Vec<_> = circuit.get_wires().iter().map(|w| w.get_id()).collect();
+        for (index, data) in annotations.into_iter().enumerate() {
+            let anchor = match data.anchor {
+                Some(AnchorData::Component(component)) => ids
+                    .get(component)
+                    .map(|id| Some(AnnotationAnchor::Component(*id))),
+                Some(AnchorData::Wire(wire)) => wire_ids
+                    .get(wire)
+                    .map(|id| Some(AnnotationAnchor::Wire(*id))),
+                None => Some(None),
+            }
+            .ok_or(ProjectFileError::InvalidAnnotation(index))?;
+            circuit.add_annotation(
+                Annotation::new(data.text, data.position)
+                    .with_font_size(data.font_size)
+                    .with_alignment(data.alignment)
+                    .with_color(data.color)
+                    .with_rotation(data.rotation)
+                    .with_anchor(anchor),
+            );
+        }
+        Ok(circuit)
+    }
+
+    pub fn to_json(&self) -> Result<String, ProjectFileError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+    /// Writes the file on a single line, e.g. to share a part of a design as text
+    pub fn to_compact_json(&self) -> Result<String, ProjectFileError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    /// Reads a project file of the current or any older version
+    pub fn from_json(json: &str) -> Result<Self, ProjectFileError> {
+        let value: Value = serde_json::from_str(json)?;
+        Ok(serde_json::from_value(Self::migrate(value)?)?)
+    }
+
+    /// Migrates the file to the current version
+    fn migrate(mut value: Value) -> Result<Value, ProjectFileError> {
+        let version = value
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or(ProjectFileError::MissingVersion)?;
+        if version == 0 || version > u64::from(PROJECT_FILE_VERSION) {
+            return Err(ProjectFileError::UnsupportedVersion(version));
+        }
+        #[allow(clippy::cast_possible_truncation)]
+        for migration in &MIGRATIONS[version as usize - 1..] {
+            value = migration(value);
+        }
+        if let Some(object) = value.as_object_mut() {
+            object.insert("version".into(), PROJECT_FILE_VERSION.into());
+        }
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit::{ConnectionKind, ConnectionPoint, DesignRule, PinDirection, Severity};
+
+    /// Completes a component read from a file with a single port, like the component library does
+    fn create(component: Component, _: &SubcircuitLibrary) -> Result<Component, String> {
+        let kind = match component.get_component_type() {
+            ComponentType::Lever | ComponentType::Pin(PinDirection::Input, _) => {
+                ConnectionKind::Output
+            }
+            _ => ConnectionKind::Input,
+        };
+        let port = ConnectionPoint::new(0.0, 0.0, [true; 4], kind);
+        Ok(Component::new(
+            component.get_component_type(),
+            component.get_position(),
+            (25.0, 25.0),
+            vec![port],
+        )
+        .with_orientation(component.get_orientation())
+        .with_label(component.get_label().to_owned()))
+    }
+
+    fn load(json: &str) -> ProjectFile {
+        ProjectFile::from_json(json).unwrap()
+    }
+
+    #[test]
+    fn saved_designs_load_unchanged() {
+        let mut inner = Circuit::new();
+        inner.add_component(
+            create(
+                Component::new(
+                    ComponentType::Pin(PinDirection::Input, 1),
+                    (0.0, 0.0),
+                    (0.0, 0.0),
+                    Vec::new(),
+                ),
+                &SubcircuitLibrary::new(),
+            )
+            .unwrap()
+            .with_label("in".to_owned()),
+        );
+        let mut subcircuits = SubcircuitLibrary::new();
+        let id = subcircuits.add(Subcircuit::new("Inner".to_owned(), inner));
+
+        let mut circuit = Circuit::new();
+        let mut add = |component_type, position| {
+            let component = Component::new(component_type, position, (0.0, 0.0), Vec::new());
+            let component = create(component, &subcircuits).unwrap();
+            circuit.add_component(component)
+        };
+        let lever = add(ComponentType::Lever, (0.0, 0.0));
+        let lamp = add(ComponentType::Lamp, (100.0, 50.0));
+        let instance = add(ComponentType::Subcircuit(id), (100.0, 150.0));
+        let wire = circuit
+            .add_routed_wire(
+                PortRef::new(lever, 0),
+                PortRef::new(lamp, 0),
+                vec![(50.0, 0.0), (50.0, 50.0)],
+            )
+            .unwrap();
+        circuit.add_wire(PortRef::new(lever, 0), PortRef::new(instance, 0));
+        circuit.add_annotation(
+            Annotation::new("enable".to_owned(), (0.0, -10.0))
+                .with_anchor(Some(AnnotationAnchor::Wire(wire))),
+        );
+        circuit
+            .add_annotation(Annotation::new("Notes".to_owned(), (-50.0, -50.0)).with_rotation(1));
+
+        let mut rules = RuleSettings::default();
+        rules.set_severity(DesignRule::FloatingInput, None);
+        rules.set_severity(DesignRule::UnconnectedPin, Some(Severity::Error));
+        let view = ViewData {
+            scale: 2.0,
+            translation: (-30.0, 40.0),
+            grid_visible: false,
+            grid_spacing: 12.5,
+        };
+        let file = ProjectFile::from_circuit(&circuit, &subcircuits)
+            .with_rules(rules)
+            .with_view(view);
+
+        let loaded = load(&file.to_json().unwrap());
+        assert_eq!(loaded, file);
+        let (loaded_circuit, loaded_subcircuits) = loaded.into_circuit(create).unwrap();
+        assert_eq!(loaded_circuit, circuit);
+        assert_eq!(loaded_subcircuits, subcircuits);
+    }
+
+    #[test]
+    fn version_1_gets_subcircuits_and_bidirectional_pins() {
+        let file = load(
+            r#"{"version": 1, "components": [{"component_type": "Pin", "position": [0, 0]}], "wires": []}"#,
+        );
+        assert_eq!(file.version, PROJECT_FILE_VERSION);
+        assert!(file.subcircuits.is_empty());
+        assert_eq!(
+            file.components[0].component_type,
+            ComponentType::Pin(PinDirection::Bidirectional, 1)
+        );
+    }
+
+    #[test]
+    fn version_2_gets_bidirectional_pins_in_subcircuits() {
+        let file = load(
+            r#"{"version": 2, "components": [], "wires": [], "subcircuits": [
+                {"id": 0, "name": "Inner", "components": [{"component_type": "Pin", "position": [0, 0]}], "wires": []}
+            ]}"#,
+        );
+        assert_eq!(
+            file.subcircuits[0].components[0].component_type,
+            ComponentType::Pin(PinDirection::Bidirectional, 1)
+        );
+    }
+
+    #[test]
+    fn version_3_gets_annotations() {
+        let file = load(
+            r#"{"version": 3, "components": [{"component_type": {"Pin": ["Output", 4]}, "position": [0, 0]}],
+                "wires": [], "subcircuits": [{"id": 0, "name": "Inner", "components": [], "wires": []}]}"#,
+        );
+        assert_eq!(
+            file.components[0].component_type,
+            ComponentType::Pin(PinDirection::Output, 4)
+        );
+        assert!(file.annotations.is_empty());
+        assert!(file.subcircuits[0].annotations.is_empty());
+    }
+
+    #[test]
+    fn version_4_gets_the_default_rule_settings() {
+        let file = load(
+            r##"{"version": 4, "components": [], "wires": [], "subcircuits": [], "annotations": [
+                {"text": "Adder", "position": [0, 0], "font_size": 16, "color": "#000000"}
+            ]}"##,
+        );
+        assert_eq!(file.annotations[0].text, "Adder");
+        assert_eq!(file.rules, RuleSettings::default());
+    }
+
+    #[test]
+    fn version_5_keeps_the_current_view() {
+        let file = load(
+            r#"{"version": 5, "components": [], "wires": [], "subcircuits": [], "annotations": [],
+                "rules": {"FloatingInput": null}}"#,
+        );
+        assert_eq!(file.rules.get_severity(DesignRule::FloatingInput), None);
+        assert_eq!(file.view, None);
+    }
+
+    #[test]
+    fn newer_versions_are_refused() {
+        let json = format!(
+            r#"{{"version": {}, "components": [], "wires": []}}"#,
+            PROJECT_FILE_VERSION + 1
+        );
+        assert!(matches!(
+            ProjectFile::from_json(&json),
+            Err(ProjectFileError::UnsupportedVersion(_))
+        ));
+    }
+}
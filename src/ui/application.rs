@@ -1,19 +1,77 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::BTreeSet, rc::Rc};
 
+use gloo::timers::callback::Interval;
 use stylist::{css, style, yew::Global};
 use yew::prelude::*;
 
-use crate::impl_display_with_debug;
+use crate::{
+    circuit::{
+        check_circuit, get_port_pins, map_port_pins, reroute_wires, route_wire, Annotation,
+        AnnotationId, Circuit, ComponentId, ComponentType, DesignRule, Edit, EditHistory,
+        Orientation, PortRef, Problem, PropertyValue, RuleSettings, Severity, Simulation,
+        Subcircuit, SubcircuitId, SubcircuitLibrary, WireId, GAME_TICK_MS,
+    },
+    export::{check_layout, place_and_route, ExportFormat, RoutedLayout},
+    impl_display_with_debug,
+    project::{ProjectFile, ViewData, PROJECT_FILE_EXTENSION},
+};
 
 use super::{
-    canvas::CanvasElement, component_list::ComponentList, console_option::ConsoleOption,
-    keybard_input_handler::KeyboardInputHandler, main_header::MainHeader, toolbar::Toolbar,
-    workspace::Workspace,
+    canvas::CanvasElement,
+    clipboard,
+    component_list::ComponentList,
+    console_option::ConsoleOption,
+    file_io,
+    keybard_input_handler::KeyboardInputHandler,
+    layout_preview::LayoutPreview,
+    main_header::MainHeader,
+    problems_panel::ProblemsPanel,
+    property_inspector::PropertyInspector,
+    toolbar::Toolbar,
+    workspace::{GridSettings, ViewTransform, Workarea, Workspace, ZOOM_STEP},
 };
 
 #[derive(Clone)]
 pub enum CallbackReason {
     ToolChanged(Option<CanvasElement>),
+    Command(Command),
+    /// The circuit was edited in the workspace
+    CircuitChanged,
+    /// The workspace was zoomed or panned
+    ViewChanged,
+    /// A property of a placed component was edited in the property inspector
+    SetProperty {
+        component: ComponentId,
+        name: String,
+        value: PropertyValue,
+    },
+    /// A problem was picked in the problems panel
+    ShowProblem(Problem),
+    /// A design rule was configured in the problems panel, `None` turns it off
+    SetRuleSeverity(DesignRule, Option<Severity>),
+}
+
+/// The steps of drawing a wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireTool {
+    PickSource,
+    /// The source was clicked, the wire is drawn to the next port clicked
+    PickTarget(PortRef),
+}
+
+/// The annotation whose text is edited in place
+#[derive(Debug, Clone, PartialEq)]
+pub enum TextEditing {
+    /// A new annotation, added to the circuit once its text is confirmed
+    New(Annotation),
+    Existing(AnnotationId),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimulationStatus {
+    Stopped,
+    Paused,
+    Running,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -24,7 +82,7 @@ pub enum Command {
     Save,
     _SaveAs,
     _Import,
-    _Export,
+    Export(ExportFormat),
     Print,
     Close,
     Exit,
@@ -32,6 +90,7 @@ pub enum Command {
     // Edit
     Undo,
     Redo,
+    SetHistoryDepth,
     Copy,
     Cut,
     Paste,
@@ -42,6 +101,7 @@ pub enum Command {
     ZoomOut,
     FitWindow,
     ToggleGrid,
+    SetGridSpacing,
 
     // Tools
     ExitCurrentTool,
@@ -51,6 +111,13 @@ pub enum Command {
     MirrorHorizontal,
     MakeConnections,
     PlaceText,
+    PreviewLayout,
+    ClosePreview,
+    CheckLayout,
+    RenamePin,
+    CreateSubcircuit,
+    EditSubcircuit,
+    CloseSubcircuit,
     // - Simulation
     _StartSimulation,
     _RunSimulaiton,
@@ -64,16 +131,106 @@ impl_display_with_debug!(Command);
 pub enum ApplicationMsg {
     Callback(CallbackReason),
     Command(Command),
+    SimulationStep,
+    /// A project file was read, with its file name and content
+    ProjectOpened((String, String)),
+    /// The system clipboard was read for pasting, `None` if it could not be read
+    ClipboardRead(Option<String>),
 }
 
-#[derive(Default, Clone, PartialEq)]
+/// A subcircuit opened for editing and the circuit it was opened from
+#[derive(Clone, PartialEq)]
+pub struct OpenSubcircuit {
+    pub id: SubcircuitId,
+    /// The circuit the subcircuit was opened from
+    pub circuit: Circuit,
+    pub history: EditHistory,
+    pub view: ViewTransform,
+}
+
+#[derive(Clone, PartialEq)]
 pub struct ApplicationState {
     pub tool_active: Option<CanvasElement>,
+    /// The wire tool, `None` while no wires are drawn
+    pub wire_tool: Option<WireTool>,
+    /// Whether clicking places a text annotation
+    pub text_tool: bool,
+    pub text_editing: Option<TextEditing>,
+    /// The design or subcircuit that is currently being edited
+    pub circuit: Circuit,
+    /// The user-defined components of the design
+    pub subcircuits: SubcircuitLibrary,
+    /// The subcircuits opened for editing, the innermost last
+    pub editing: Vec<OpenSubcircuit>,
+    /// The edits of the circuit that can be undone
+    pub history: EditHistory,
+    /// The components selected in the workspace
+    pub selection: BTreeSet<ComponentId>,
+    /// The wires selected in the workspace
+    pub selected_wires: BTreeSet<WireId>,
+    /// The annotations selected in the workspace
+    pub selected_annotations: BTreeSet<AnnotationId>,
+    /// The copied components following the mouse until they are pasted
+    pub pasting: Option<Circuit>,
+    /// The zoom and position of the workspace
+    pub view: ViewTransform,
+    pub grid: GridSettings,
+    /// The running or paused simulation of the circuit, `None` while editing
+    pub simulation: Option<Simulation>,
+    /// The design rules the circuit is checked against
+    pub rules: RuleSettings,
+    /// The problem picked in the problems panel, its components and wires are highlighted
+    pub problem: Option<Problem>,
+    /// The name the design is saved under
+    pub file_name: String,
+}
+
+impl Default for ApplicationState {
+    fn default() -> Self {
+        Self {
+            tool_active: None,
+            wire_tool: None,
+            text_tool: false,
+            text_editing: None,
+            circuit: Circuit::default(),
+            subcircuits: SubcircuitLibrary::default(),
+            editing: Vec::new(),
+            history: EditHistory::default(),
+            selection: BTreeSet::new(),
+            selected_wires: BTreeSet::new(),
+            selected_annotations: BTreeSet::new(),
+            pasting: None,
+            view: ViewTransform::default(),
+            grid: GridSettings::default(),
+            simulation: None,
+            rules: RuleSettings::default(),
+            problem: None,
+            file_name: format!("design.{PROJECT_FILE_EXTENSION}"),
+        }
+    }
+}
+
+/// A placed and routed design kept to check it against the redstone rules
+struct CheckedLayout {
+    /// The circuit being edited when the design was routed, the problems are outdated once it changes
+    circuit: Circuit,
+    /// The circuit with its subcircuits inlined, which the layout realises
+    flat: Circuit,
+    routed: RoutedLayout,
+    problems: Vec<Problem>,
 }
 
 pub struct Application {
     application_state: Rc<RefCell<ApplicationState>>,
     _keyboard_handler: KeyboardInputHandler,
+    /// Steps the simulation while it is running
+    simulation_interval: Option<Interval>,
+    /// The placed and routed design shown above the workspace
+    layout_preview: Option<RoutedLayout>,
+    /// The last routed design with its redstone problems
+    checked_layout: Option<CheckedLayout>,
+    /// The last copied components as text, pasted if the browser does not allow reading the system clipboard
+    clipboard: Option<String>,
 }
 
 impl Component for Application {
@@ -88,27 +245,63 @@ impl Component for Application {
         Self {
             application_state: Rc::default(),
             _keyboard_handler: keyboard_handler,
+            simulation_interval: None,
+            layout_preview: None,
+            checked_layout: None,
+            clipboard: None,
         }
     }
-    fn update(&mut self, _ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
         match msg {
             ApplicationMsg::Callback(_reason) => match _reason {
                 CallbackReason::ToolChanged(tool) => {
-                    self.application_state.borrow_mut().tool_active = tool;
+                    let mut state = self.application_state.borrow_mut();
+                    state.tool_active = tool;
+                    state.wire_tool = None;
+                    state.text_tool = false;
+                    state.pasting = None;
+                    false
                 }
-            },
-            ApplicationMsg::Command(cmd) => {
-                log::debug!("Command issued: {}", cmd);
-
-                match cmd {
-                    Command::ExitCurrentTool => {
-                        self.application_state.borrow_mut().tool_active = None;
+                CallbackReason::Command(cmd) => self.handle_command(ctx, cmd),
+                CallbackReason::CircuitChanged => true,
+                // the canvases draw the current view every frame
+                CallbackReason::ViewChanged => false,
+                CallbackReason::SetProperty {
+                    component,
+                    name,
+                    value,
+                } => self.set_property(component, &name, &value),
+                CallbackReason::ShowProblem(problem) => {
+                    let mut state = self.application_state.borrow_mut();
+                    // the problem is moved to the center of the workspace
+                    let (x, y) = state.view.to_screen(problem.get_location());
+                    let (width, height) = Self::get_workarea_size();
+                    state.view.pan((width / 2.0 - x, height / 2.0 - y));
+                    state.problem = Some(problem);
+                    true
+                }
+                CallbackReason::SetRuleSeverity(rule, severity) => {
+                    {
+                        let mut state = self.application_state.borrow_mut();
+                        state.rules.set_severity(rule, severity);
+                        state.problem = None;
                     }
-                    _ => {},
+                    self.recheck_layout();
+                    true
+                }
+            },
+            ApplicationMsg::Command(cmd) => self.handle_command(ctx, cmd),
+            ApplicationMsg::SimulationStep => {
+                if let Some(simulation) = self.application_state.borrow_mut().simulation.as_mut() {
+                    simulation.advance_ticks(1);
                 }
+                false
+            }
+            ApplicationMsg::ProjectOpened((file_name, content)) => {
+                self.open_project(file_name, &content)
             }
+            ApplicationMsg::ClipboardRead(text) => self.start_pasting(text),
         }
-        false
     }
 
     fn view(&self, ctx: &yew::Context<Self>) -> Html {
@@ -116,11 +309,12 @@ impl Component for Application {
         let page = style!(
             r#"
             display: grid;
-            grid-template-columns: 225px auto;
-            grid-template-rows: 25px 50px 40fr;
-            grid-template-areas: "main_header main_header"
-                                 "toolbar toolbar"
-                                 "component_list workspace";
+            grid-template-columns: 225px auto 225px;
+            grid-template-rows: 25px 50px 40fr 25fr;
+            grid-template-areas: "main_header main_header main_header"
+                                 "toolbar toolbar toolbar"
+                                 "component_list workspace property_inspector"
+                                 "component_list workspace problems";
             width: 100%;
             height: 100%;
         "#
@@ -159,23 +353,952 @@ impl Component for Application {
         "#
         )
         .unwrap_to_console();
+        let property_inspector = style!(
+            r#"
+            grid-area: property_inspector;
+        "#
+        )
+        .unwrap_to_console();
+        let problems_panel = style!(
+            r#"
+            grid-area: problems;
+        "#
+        )
+        .unwrap_to_console();
 
+        let simulation_status = self.get_simulation_status();
+        let (can_undo, can_redo) = self.get_history_status();
+        let can_delete = self.can_delete();
+        let can_copy = self.can_copy();
+        let can_orient = {
+            let state = self.application_state.borrow();
+            state.tool_active.is_some()
+                || state.simulation.is_none()
+                    && !(state.selection.is_empty() && state.selected_annotations.is_empty())
+        };
+        let can_rename_pin = self.get_selected_pin().is_some();
+        let can_edit_subcircuit = self.get_selected_subcircuit().is_some();
+        let editing_subcircuit = {
+            let state = self.application_state.borrow();
+            state.simulation.is_none() && !state.editing.is_empty()
+        };
+        let subcircuits = Rc::new(self.application_state.borrow().subcircuits.clone());
+        let inspected = self.get_single_selection().and_then(|component| {
+            let properties = component
+                .try_as_redstone_component(&subcircuits)
+                .ok()?
+                .get_properties();
+            Some((component, properties))
+        });
+        let (inspected_component, inspected_title, properties) = match inspected {
+            Some((component, properties)) => (
+                Some(component.get_id()),
+                component.get_component_type().to_string(),
+                properties,
+            ),
+            None => (None, String::new(), Vec::new()),
+        };
         let main_header_callback = ctx.link().callback(ApplicationMsg::Callback);
         let toolbar_callback = ctx.link().callback(ApplicationMsg::Callback);
         let component_list_callback = ctx.link().callback(ApplicationMsg::Callback);
         let workspace_callback = ctx.link().callback(ApplicationMsg::Callback);
+        let preview_callback = ctx.link().callback(ApplicationMsg::Callback);
+        let inspector_callback = ctx.link().callback(ApplicationMsg::Callback);
+        let problems_callback = ctx.link().callback(ApplicationMsg::Callback);
+        let (problems, rules, highlighted, layout_checked) = {
+            let state = self.application_state.borrow();
+            let mut problems = check_circuit(&state.circuit, &state.rules);
+            // the layout is only checked on request, its problems are shown until the circuit changes
+            let checked = self
+                .checked_layout
+                .as_ref()
+                .filter(|checked| checked.circuit == state.circuit);
+            if let Some(checked) = checked {
+                problems.extend(checked.problems.iter().cloned());
+                problems.sort_by_key(Problem::get_severity);
+            }
+            (
+                problems,
+                state.rules.clone(),
+                state.problem.clone(),
+                checked.is_some(),
+            )
+        };
 
         html! {
             <div id ="page">
                 <Global css={ css!(r#"font-family: Arial,"Microsoft YaHei";"#) }/>
 
                 <div class={page}>
-                    <MainHeader callback={ main_header_callback } class={ main_header }/>
-                    <Toolbar callback={ toolbar_callback } class={toolbar}/>
-                    <ComponentList callback={ component_list_callback }class={component_list}/>
+                    <MainHeader callback={ main_header_callback } class={ main_header } { simulation_status } { can_undo } { can_redo } { can_delete } { can_copy } { can_orient } { can_rename_pin } { can_edit_subcircuit } { editing_subcircuit }/>
+                    <Toolbar callback={ toolbar_callback } class={toolbar} { simulation_status } { can_undo } { can_redo } { can_delete } { can_copy } { can_orient }/>
+                    <ComponentList callback={ component_list_callback } class={ component_list } { subcircuits }/>
                     <Workspace callback={ workspace_callback } application_state={ self.application_state.clone() } class={ workspace }/>
+                    <PropertyInspector callback={ inspector_callback } class={ property_inspector } component={ inspected_component } title={ inspected_title } { properties }/>
+                    <ProblemsPanel callback={ problems_callback } class={ problems_panel } { problems } { rules } { highlighted } { layout_checked }/>
                 </div>
+                if let Some(routed) = self.layout_preview.clone() {
+                    <LayoutPreview callback={ preview_callback } { routed }/>
+                }
             </div>
         }
     }
 }
+
+impl Application {
+    /// Executes the command and returns whether the application has to be rerendered
+    fn handle_command(&mut self, ctx: &yew::Context<Self>, cmd: Command) -> bool {
+        log::debug!("Command issued: {}", cmd);
+
+        match cmd {
+            Command::ExitCurrentTool => {
+                let mut state = self.application_state.borrow_mut();
+                state.tool_active = None;
+                state.wire_tool = None;
+                state.text_tool = false;
+                state.text_editing = None;
+                state.pasting = None;
+                state.selection.clear();
+                state.selected_wires.clear();
+                state.selected_annotations.clear();
+                state.problem = None;
+                true
+            }
+            Command::MakeConnections => {
+                let mut state = self.application_state.borrow_mut();
+                if state.simulation.is_some() {
+                    return false;
+                }
+                state.tool_active = None;
+                state.pasting = None;
+                state.text_tool = false;
+                state.wire_tool = Some(WireTool::PickSource);
+                true
+            }
+            Command::PlaceText => {
+                let mut state = self.application_state.borrow_mut();
+                if state.simulation.is_some() {
+                    return false;
+                }
+                state.tool_active = None;
+                state.pasting = None;
+                state.wire_tool = None;
+                state.text_tool = true;
+                true
+            }
+            Command::_StartSimulation => {
+                self.simulation_interval = None;
+                let mut state = self.application_state.borrow_mut();
+                state.tool_active = None;
+                state.wire_tool = None;
+                state.text_tool = false;
+                state.text_editing = None;
+                state.pasting = None;
+                let circuit = state.subcircuits.flatten(&state.circuit);
+                if !circuit.get_width_mismatches().is_empty() {
+                    drop(state);
+                    gloo::dialogs::alert(
+                        "The circuit can't be simulated while wires connect ports of different widths",
+                    );
+                    return true;
+                }
+                state.simulation = Some(Simulation::new(&circuit));
+                true
+            }
+            Command::_RunSimulaiton => {
+                // Running toggles between running and paused
+                if self.simulation_interval.is_some() {
+                    self.simulation_interval = None;
+                } else if self.application_state.borrow().simulation.is_some() {
+                    let link = ctx.link().clone();
+                    self.simulation_interval = Some(Interval::new(GAME_TICK_MS, move || {
+                        link.send_message(ApplicationMsg::SimulationStep);
+                    }));
+                }
+                true
+            }
+            Command::_StopSimulation => {
+                self.simulation_interval = None;
+                self.application_state.borrow_mut().simulation = None;
+                true
+            }
+            Command::_StepSimulaiton => {
+                if let Some(simulation) = self.application_state.borrow_mut().simulation.as_mut() {
+                    simulation.advance_ticks(1);
+                }
+                false
+            }
+            Command::_RestartSimulation => {
+                let mut state = self.application_state.borrow_mut();
+                if state.simulation.is_some() {
+                    state.simulation =
+                        Some(Simulation::new(&state.subcircuits.flatten(&state.circuit)));
+                }
+                false
+            }
+            Command::_PauseAtChangeSimulation => {
+                if let Some(simulation) = self.application_state.borrow_mut().simulation.as_mut() {
+                    simulation.run_until_change();
+                }
+                false
+            }
+            Command::Open => {
+                let callback = ctx.link().callback(ApplicationMsg::ProjectOpened);
+                file_io::open_text(&format!(".{PROJECT_FILE_EXTENSION},.json"), callback)
+                    .unwrap_to_console();
+                false
+            }
+            Command::Save => {
+                self.save_project();
+                false
+            }
+            Command::_SaveAs => {
+                let file_name = self.application_state.borrow().file_name.clone();
+                if let Some(file_name) = gloo::dialogs::prompt("Save as", Some(&file_name)) {
+                    let file_name = if file_name.contains('.') {
+                        file_name
+                    } else {
+                        format!("{file_name}.{PROJECT_FILE_EXTENSION}")
+                    };
+                    self.application_state.borrow_mut().file_name = file_name;
+                    self.save_project();
+                }
+                false
+            }
+            Command::Export(format) => {
+                self.export(format);
+                true
+            }
+            Command::Undo | Command::Redo => {
+                let mut state = self.application_state.borrow_mut();
+                // the simulated circuit must not change under the simulation
+                if state.simulation.is_some() {
+                    return false;
+                }
+                let ApplicationState {
+                    circuit, history, ..
+                } = &mut *state;
+                if cmd == Command::Undo {
+                    history.undo(circuit)
+                } else {
+                    history.redo(circuit)
+                }
+            }
+            Command::Delete => {
+                if !self.can_delete() {
+                    return false;
+                }
+                let mut state = self.application_state.borrow_mut();
+                let ApplicationState {
+                    circuit,
+                    history,
+                    selection,
+                    selected_wires,
+                    selected_annotations,
+                    ..
+                } = &mut *state;
+                let ids: Vec<_> = std::mem::take(selection).into_iter().collect();
+                let wire_ids: Vec<_> = std::mem::take(selected_wires).into_iter().collect();
+                history.begin_group();
+                for id in std::mem::take(selected_annotations) {
+                    if let Some(annotation) = circuit.get_annotation(id).cloned() {
+                        history.apply(circuit, Edit::RemoveAnnotation(annotation));
+                    }
+                }
+                for edit in Edit::remove_components(circuit, &ids, &wire_ids) {
+                    history.apply(circuit, edit);
+                }
+                history.end_group();
+                true
+            }
+            Command::Copy => {
+                self.copy();
+                false
+            }
+            Command::Cut => {
+                if !self.copy() {
+                    return false;
+                }
+                // only what was copied is deleted, the wires attached to the components go with them
+                {
+                    let mut state = self.application_state.borrow_mut();
+                    let copied = Self::get_copied_annotations(&state);
+                    state.selected_annotations.retain(|id| copied.contains(id));
+                    state.selected_wires.clear();
+                }
+                self.handle_command(ctx, Command::Delete)
+            }
+            Command::Paste => {
+                if self.application_state.borrow().simulation.is_none() {
+                    clipboard::read_text(ctx.link().callback(ApplicationMsg::ClipboardRead));
+                }
+                false
+            }
+            Command::ZoomIn | Command::ZoomOut => {
+                let factor = if cmd == Command::ZoomIn {
+                    ZOOM_STEP
+                } else {
+                    1.0 / ZOOM_STEP
+                };
+                let (width, height) = Self::get_workarea_size();
+                self.application_state
+                    .borrow_mut()
+                    .view
+                    .zoom_at(factor, (width / 2.0, height / 2.0));
+                true
+            }
+            Command::ToggleGrid => {
+                self.application_state.borrow_mut().grid.toggle_visible();
+                false
+            }
+            Command::SetGridSpacing => {
+                let spacing = self.application_state.borrow().grid.get_spacing();
+                if let Some(spacing) =
+                    gloo::dialogs::prompt("Distance between grid lines", Some(&spacing.to_string()))
+                {
+                    let set = spacing
+                        .trim()
+                        .parse()
+                        .is_ok_and(|s| self.application_state.borrow_mut().grid.set_spacing(s));
+                    if !set {
+                        gloo::dialogs::alert(&format!("{spacing} is not a positive distance"));
+                    }
+                }
+                false
+            }
+            Command::FitWindow => {
+                let mut state = self.application_state.borrow_mut();
+                match state.circuit.get_bounds() {
+                    Some(bounds) => state.view.fit(bounds, Self::get_workarea_size()),
+                    None => state.view = ViewTransform::default(),
+                }
+                true
+            }
+            Command::RotateLeft => self.orient(Orientation::rotated_left),
+            Command::RotateRight => self.orient(Orientation::rotated_right),
+            Command::MirrorHorizontal => self.orient(Orientation::mirrored_horizontal),
+            Command::MirrorVertical => self.orient(Orientation::mirrored_vertical),
+            Command::SetHistoryDepth => {
+                let depth = self.application_state.borrow().history.get_depth();
+                if let Some(depth) = gloo::dialogs::prompt(
+                    "Number of edits that can be undone",
+                    Some(&depth.to_string()),
+                ) {
+                    match depth.trim().parse() {
+                        Ok(depth) => self.application_state.borrow_mut().history.set_depth(depth),
+                        Err(_) => {
+                            gloo::dialogs::alert(&format!("{depth} is not a number of edits"))
+                        }
+                    }
+                }
+                true
+            }
+            Command::PreviewLayout => {
+                self.layout_preview = Some(self.route_design());
+                true
+            }
+            Command::ClosePreview => self.layout_preview.take().is_some(),
+            Command::CheckLayout => {
+                self.route_design();
+                true
+            }
+            Command::RenamePin => self.rename_pin(),
+            Command::CreateSubcircuit => self.create_subcircuit(),
+            Command::EditSubcircuit => self.open_subcircuit(),
+            Command::CloseSubcircuit => {
+                let mut state = self.application_state.borrow_mut();
+                let Some(open) = state.editing.last() else {
+                    return false;
+                };
+                if state.simulation.is_some() {
+                    return false;
+                }
+                if state.subcircuits.uses(&state.circuit, open.id) {
+                    drop(state);
+                    gloo::dialogs::alert("A subcircuit can't contain an instance of itself");
+                    return false;
+                }
+                Self::close_subcircuit(&mut state);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Turns the component about to be placed or else every selected component and annotation.
+    /// Returns whether anything was turned.
+    fn orient(&mut self, turn: impl Fn(&Orientation) -> Orientation) -> bool {
+        let mut state = self.application_state.borrow_mut();
+        if let Some(tool) = state.tool_active.take() {
+            let orientation = turn(&tool.get_orientation());
+            state.tool_active = Some(tool.with_orientation(orientation));
+            return true;
+        }
+        if state.simulation.is_some() {
+            return false;
+        }
+        let ApplicationState {
+            circuit,
+            history,
+            selection,
+            selected_annotations,
+            grid,
+            ..
+        } = &mut *state;
+        history.begin_group();
+        // text is only rotated, never mirrored
+        let turned = turn(&Orientation::IDENTITY);
+        for id in selected_annotations
+            .iter()
+            .filter(|_| !turned.is_mirrored())
+        {
+            if let Some(before) = circuit.get_annotation(*id).cloned() {
+                let rotation = before.get_rotation() + turned.get_rotation();
+                let after = before.clone().with_rotation(rotation);
+                history.apply(circuit, Edit::ReplaceAnnotation { before, after });
+            }
+        }
+        for id in selection.iter() {
+            if let Some(before) = circuit.get_component(*id).cloned() {
+                let orientation = turn(&before.get_orientation());
+                let after = before.clone().with_orientation(orientation);
+                history.apply(circuit, Edit::ReplaceComponent { before, after });
+            }
+        }
+        let ids: Vec<_> = selection.iter().copied().collect();
+        for edit in reroute_wires(circuit, &ids, grid.get_spacing()) {
+            history.apply(circuit, edit);
+        }
+        history.end_group();
+        !selection.is_empty() || !selected_annotations.is_empty()
+    }
+
+    /// Returns the annotations copied with the selected components: the selected annotations that are free
+    /// or attached to the copy and all annotations attached to the selected components or the wires between them
+    fn get_copied_annotations(state: &ApplicationState) -> Vec<AnnotationId> {
+        let ids: Vec<_> = state.selection.iter().copied().collect();
+        let attached = state.circuit.get_attached_annotations(&ids);
+        let free = state.selected_annotations.iter().copied().filter(|id| {
+            state
+                .circuit
+                .get_annotation(*id)
+                .is_some_and(|a| a.get_anchor().is_none())
+        });
+        free.chain(attached).collect()
+    }
+
+    /// Puts the selected components, the wires between them and their annotations on the clipboard.
+    /// Returns whether they were copied.
+    fn copy(&mut self) -> bool {
+        if !self.can_copy() {
+            return false;
+        }
+        let state = self.application_state.borrow();
+        let ids: Vec<_> = state.selection.iter().copied().collect();
+        let annotations = Self::get_copied_annotations(&state);
+        let fragment = state.circuit.get_fragment(&ids, &annotations);
+        match ProjectFile::from_circuit(&fragment, &state.subcircuits).to_compact_json() {
+            Ok(text) => {
+                clipboard::write_text(&text).unwrap_to_console();
+                self.clipboard = Some(text);
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to copy the selection: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Lets the copied components follow the mouse until they are pasted.
+    /// Text on the system clipboard that is no design falls back to the last copied components.
+    fn start_pasting(&mut self, text: Option<String>) -> bool {
+        // instances are pasted as instances of the subcircuits of this design
+        let subcircuits = self.application_state.borrow().subcircuits.clone();
+        let fragment = text.iter().chain(self.clipboard.iter()).find_map(|text| {
+            ProjectFile::from_json(text)
+                .and_then(|project| project.into_circuit(|c, _| c.try_rebuild(&subcircuits)))
+                .map(|(circuit, _)| circuit)
+                .ok()
+        });
+        let Some(fragment) = fragment else {
+            gloo::dialogs::alert("The clipboard does not contain any components");
+            return false;
+        };
+        let mut state = self.application_state.borrow_mut();
+        if state.simulation.is_some() {
+            return false;
+        }
+        // a whole design is pasted relative to its top left corner like a copied part
+        let ids: Vec<_> = fragment
+            .get_components()
+            .iter()
+            .map(|c| c.get_id())
+            .collect();
+        let annotations: Vec<_> = fragment
+            .get_annotations()
+            .iter()
+            .map(|a| a.get_id())
+            .collect();
+        state.pasting = Some(fragment.get_fragment(&ids, &annotations));
+        state.tool_active = None;
+        state.wire_tool = None;
+        state.text_tool = false;
+        true
+    }
+
+    /// Places and routes the design and checks the layout against the redstone rules
+    fn route_design(&mut self) -> RoutedLayout {
+        let state = self.application_state.borrow();
+        let flat = state.subcircuits.flatten(&state.circuit);
+        let routed = place_and_route(&flat);
+        self.checked_layout = Some(CheckedLayout {
+            circuit: state.circuit.clone(),
+            problems: check_layout(&routed, &flat, &state.rules),
+            flat,
+            routed: routed.clone(),
+        });
+        routed
+    }
+    /// Checks the last routed design again after the rules changed
+    fn recheck_layout(&mut self) {
+        let state = self.application_state.borrow();
+        if let Some(checked) = self.checked_layout.as_mut() {
+            checked.problems = check_layout(&checked.routed, &checked.flat, &state.rules);
+        }
+    }
+
+    /// Downloads the design realised with redstone in the given structure format
+    fn export(&mut self, format: ExportFormat) {
+        let routed = self.route_design();
+        let state = self.application_state.borrow();
+        if !routed.get_unrouted_nets().is_empty() {
+            gloo::dialogs::alert(&format!(
+                "{} nets could not be routed and are missing in the export",
+                routed.get_unrouted_nets().len()
+            ));
+        }
+        let stem = state
+            .file_name
+            .rsplit_once('.')
+            .map_or(state.file_name.as_str(), |(stem, _)| stem);
+        #[allow(clippy::cast_possible_truncation)]
+        let time = js_sys::Date::now() as i64;
+        match format.export(routed.get_layout(), stem, time) {
+            Ok(content) => {
+                let file_name = format!("{stem}.{}", format.get_extension());
+                file_io::download_bytes(&file_name, &content, "application/octet-stream")
+                    .unwrap_to_console();
+            }
+            Err(e) => {
+                log::error!("Failed to export the design: {}", e);
+                gloo::dialogs::alert(&format!("Failed to export the design:\n{e}"));
+            }
+        }
+    }
+
+    /// Downloads the whole design as a project file, including the subcircuits being edited
+    fn save_project(&self) {
+        let mut design = self.application_state.borrow().clone();
+        while let Some(open) = design.editing.last() {
+            // the same check as closing the subcircuit, the saved design could not be flattened otherwise
+            if design.subcircuits.uses(&design.circuit, open.id) {
+                gloo::dialogs::alert(
+                    "A subcircuit being edited contains an instance of itself, remove it before saving",
+                );
+                return;
+            }
+            Self::close_subcircuit(&mut design);
+        }
+        let view = ViewData {
+            scale: design.view.get_scale(),
+            translation: design.view.get_translation(),
+            grid_visible: design.grid.is_visible(),
+            grid_spacing: design.grid.get_spacing(),
+        };
+        match ProjectFile::from_circuit(&design.circuit, &design.subcircuits)
+            .with_rules(design.rules)
+            .with_view(view)
+            .to_json()
+        {
+            Ok(json) => {
+                file_io::download_text(&design.file_name, &json, "application/json")
+                    .unwrap_to_console();
+            }
+            Err(e) => log::error!("Failed to save the project: {}", e),
+        }
+    }
+
+    /// Replaces the design with the content of a project file and returns whether it was opened
+    fn open_project(&mut self, file_name: String, content: &str) -> bool {
+        let design = ProjectFile::from_json(content).and_then(|project| {
+            let (rules, view) = (project.rules.clone(), project.view);
+            Ok((
+                project.into_circuit(|c, lib| c.try_rebuild(lib))?,
+                rules,
+                view,
+            ))
+        });
+        match design {
+            Ok(((circuit, subcircuits), rules, view)) => {
+                self.simulation_interval = None;
+                let mut state = self.application_state.borrow_mut();
+                state.tool_active = None;
+                state.simulation = None;
+                state.circuit = circuit;
+                state.subcircuits = subcircuits;
+                state.rules = rules;
+                if let Some(view) = view {
+                    state.view = ViewTransform::new(view.scale, view.translation);
+                    state.grid = GridSettings::new(view.grid_visible, view.grid_spacing);
+                }
+                state.editing.clear();
+                state.history.clear();
+                state.selection.clear();
+                state.selected_wires.clear();
+                state.selected_annotations.clear();
+                state.problem = None;
+                state.wire_tool = None;
+                state.text_tool = false;
+                state.text_editing = None;
+                state.pasting = None;
+                state.file_name = file_name;
+                true
+            }
+            Err(e) => {
+                log::error!("Failed to open {}: {}", file_name, e);
+                gloo::dialogs::alert(&format!("Failed to open {file_name}:\n{e}"));
+                false
+            }
+        }
+    }
+
+    /// Turns the selected components into a new subcircuit and replaces them by an instance of it.
+    /// The selected pins become the ports of the subcircuit. Returns whether the subcircuit was created.
+    fn create_subcircuit(&mut self) -> bool {
+        if !self.can_copy() {
+            return false;
+        }
+        let Some(name) = gloo::dialogs::prompt("Name of the subcircuit", Some("Subcircuit")) else {
+            return false;
+        };
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return false;
+        }
+        let mut state = self.application_state.borrow_mut();
+        let ApplicationState {
+            circuit,
+            history,
+            selection,
+            selected_wires,
+            selected_annotations,
+            subcircuits,
+            grid,
+            ..
+        } = &mut *state;
+        let ids: Vec<_> = selection.iter().copied().collect();
+        let pins = get_port_pins(ids.iter().filter_map(|id| circuit.get_component(*id)));
+        if pins.is_empty() {
+            drop(state);
+            gloo::dialogs::alert(
+                "The selection needs pins that become the ports of the subcircuit",
+            );
+            return false;
+        }
+        let origin = ids
+            .iter()
+            .filter_map(|id| circuit.get_component(*id))
+            .map(|c| c.get_position())
+            .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1)))
+            .unwrap_or_default();
+        // the ports outside of the selection wired to each of the selected pins
+        let outside: Vec<_> = circuit
+            .get_wires()
+            .iter()
+            .filter_map(|w| {
+                let (from, to) = (w.get_from(), w.get_to());
+                match (ids.contains(&from.component), ids.contains(&to.component)) {
+                    (true, false) => Some((from.component, to)),
+                    (false, true) => Some((to.component, from)),
+                    _ => None,
+                }
+            })
+            .filter_map(|(pin, port)| Some((pins.iter().position(|p| *p == pin)?, port)))
+            .collect();
+        let id = subcircuits.add(Subcircuit::new(name, circuit.get_fragment(&ids, &[])));
+        let instance = match ComponentType::Subcircuit(id).try_as_canvas_element(subcircuits) {
+            Ok(element) => element.to_component(origin),
+            Err(e) => {
+                log::error!("Failed to create the subcircuit: {:?}", e);
+                return false;
+            }
+        };
+        history.begin_group();
+        for edit in Edit::remove_components(circuit, &ids, &[]) {
+            history.apply(circuit, edit);
+        }
+        let instance = circuit.add_component(instance);
+        if let Some(component) = circuit.get_component(instance) {
+            history.record(Edit::AddComponent(component.clone()));
+        }
+        for (port, outer) in outside {
+            let inner = PortRef::new(instance, port);
+            let path = route_wire(circuit, inner, outer, grid.get_spacing()).unwrap_or_default();
+            if let Some(wire) = circuit
+                .add_routed_wire(inner, outer, path)
+                .and_then(|id| circuit.get_wire(id))
+            {
+                history.record(Edit::AddWire(wire.clone()));
+            }
+        }
+        history.end_group();
+        *selection = BTreeSet::from([instance]);
+        selected_wires.clear();
+        selected_annotations.clear();
+        true
+    }
+
+    /// Returns the only selected component, `None` while simulating
+    fn get_single_selection(&self) -> Option<crate::circuit::Component> {
+        let state = self.application_state.borrow();
+        if state.simulation.is_some() || state.selection.len() != 1 {
+            return None;
+        }
+        let id = state.selection.first()?;
+        state.circuit.get_component(*id).cloned()
+    }
+    /// Returns the subcircuit of the only selected component if it is an instance of one
+    fn get_selected_subcircuit(&self) -> Option<SubcircuitId> {
+        match self.get_single_selection()?.get_component_type() {
+            ComponentType::Subcircuit(id) => Some(id),
+            _ => None,
+        }
+    }
+    /// Returns the only selected component if it is a pin
+    fn get_selected_pin(&self) -> Option<crate::circuit::Component> {
+        self.get_single_selection()
+            .filter(|c| matches!(c.get_component_type(), ComponentType::Pin(..)))
+    }
+
+    /// Asks for a new name of the selected pin and returns whether it was renamed
+    fn rename_pin(&mut self) -> bool {
+        let Some(before) = self.get_selected_pin() else {
+            return false;
+        };
+        let Some(label) = gloo::dialogs::prompt("Name of the pin", Some(before.get_label())) else {
+            return false;
+        };
+        let after = before.clone().with_label(label.trim().to_owned());
+        let mut state = self.application_state.borrow_mut();
+        let ApplicationState {
+            circuit, history, ..
+        } = &mut *state;
+        history.apply(circuit, Edit::ReplaceComponent { before, after });
+        true
+    }
+
+    /// Sets a property of a placed component and returns whether it changed.
+    /// Wires follow their ports to the rebuilt component, wires to ports it no longer has are removed
+    /// and the others are routed again.
+    fn set_property(&mut self, id: ComponentId, name: &str, value: &PropertyValue) -> bool {
+        let mut state = self.application_state.borrow_mut();
+        let ApplicationState {
+            circuit,
+            history,
+            subcircuits,
+            simulation,
+            grid,
+            ..
+        } = &mut *state;
+        if simulation.is_some() {
+            return false;
+        }
+        let Some(before) = circuit.get_component(id).cloned() else {
+            return false;
+        };
+        let Ok(component) = before.try_as_redstone_component(subcircuits) else {
+            return false;
+        };
+        let accepted = component
+            .get_properties()
+            .iter()
+            .any(|p| p.get_name() == name && p.accepts(value));
+        let Some(configured) = accepted
+            .then(|| component.apply_property(before.clone(), name, value))
+            .flatten()
+        else {
+            return false;
+        };
+        let Ok(rebuilt) = configured.try_rebuild(subcircuits) else {
+            return false;
+        };
+        let after = configured.rebuilt_as(rebuilt);
+        if after == before {
+            return false;
+        }
+        let ports = before.map_ports_to(&after);
+        let edits = Edit::replace_ports(circuit, before, after, &ports);
+        history.begin_group();
+        for edit in edits {
+            history.apply(circuit, edit);
+        }
+        for edit in reroute_wires(circuit, &[id], grid.get_spacing()) {
+            history.apply(circuit, edit);
+        }
+        history.end_group();
+        true
+    }
+
+    /// Opens the subcircuit of the selected instance for editing and returns whether it was opened
+    fn open_subcircuit(&mut self) -> bool {
+        let Some(id) = self.get_selected_subcircuit() else {
+            return false;
+        };
+        let mut state = self.application_state.borrow_mut();
+        let Some(content) = state.subcircuits.get(id).map(|s| s.get_circuit().clone()) else {
+            return false;
+        };
+        let mut history = state.history.clone();
+        history.clear();
+        let open = OpenSubcircuit {
+            id,
+            circuit: std::mem::replace(&mut state.circuit, content),
+            history: std::mem::replace(&mut state.history, history),
+            view: state.view,
+        };
+        state.editing.push(open);
+        state.tool_active = None;
+        state.wire_tool = None;
+        state.text_tool = false;
+        state.text_editing = None;
+        state.pasting = None;
+        state.selection.clear();
+        state.selected_wires.clear();
+        state.selected_annotations.clear();
+        state.problem = None;
+        true
+    }
+
+    /// Stores the innermost subcircuit being edited and returns to the circuit it was opened from.
+    /// The instances of the subcircuit everywhere in the design get its new ports and the edit history starts over.
+    fn close_subcircuit(state: &mut ApplicationState) {
+        let Some(open) = state.editing.pop() else {
+            return;
+        };
+        let edited = std::mem::replace(&mut state.circuit, open.circuit);
+        let ports = match state.subcircuits.replace_circuit(open.id, edited.clone()) {
+            Some(before) => map_port_pins(&before, &edited),
+            None => Vec::new(),
+        };
+        state.history = open.history;
+        state.view = open.view;
+        state.tool_active = None;
+        state.wire_tool = None;
+        state.text_tool = false;
+        state.text_editing = None;
+        state.pasting = None;
+        state.selection.clear();
+        state.selected_wires.clear();
+        state.selected_annotations.clear();
+        state.problem = None;
+
+        let ApplicationState {
+            circuit,
+            history,
+            subcircuits,
+            editing,
+            grid,
+            ..
+        } = state;
+        let spacing = grid.get_spacing();
+        // the library is not part of the history, undoing an edit from before could not restore the old ports
+        Self::update_instances(circuit, subcircuits, open.id, &ports, spacing);
+        history.clear();
+        for outer in editing.iter_mut() {
+            Self::update_instances(&mut outer.circuit, subcircuits, open.id, &ports, spacing);
+            outer.history.clear();
+        }
+        let others: Vec<_> = subcircuits.iter().map(|(id, _)| id).collect();
+        for other in others {
+            let Some(mut content) = subcircuits.get(other).map(|s| s.get_circuit().clone()) else {
+                continue;
+            };
+            Self::update_instances(&mut content, subcircuits, open.id, &ports, spacing);
+            subcircuits.replace_circuit(other, content);
+        }
+    }
+
+    /// Rebuilds every instance of the subcircuit in the circuit, moves the wires to the ports given by `ports`,
+    /// removes the wires to ports that no longer exist and routes the others again
+    fn update_instances(
+        circuit: &mut Circuit,
+        subcircuits: &SubcircuitLibrary,
+        id: SubcircuitId,
+        ports: &[Option<usize>],
+        spacing: f64,
+    ) {
+        let instances: Vec<_> = circuit
+            .get_components()
+            .iter()
+            .filter(|c| c.get_component_type() == ComponentType::Subcircuit(id))
+            .cloned()
+            .collect();
+        let ids: Vec<_> = instances.iter().map(|c| c.get_id()).collect();
+        for before in instances {
+            let Ok(rebuilt) = before.try_rebuild(subcircuits) else {
+                continue;
+            };
+            let after = before.clone().rebuilt_as(rebuilt);
+            // wires between two instances are moved one end at a time
+            for edit in Edit::replace_ports(circuit, before, after, ports) {
+                edit.apply(circuit);
+            }
+        }
+        for edit in reroute_wires(circuit, &ids, spacing) {
+            edit.apply(circuit);
+        }
+    }
+
+    /// Returns whether there are edits to undo and to redo, editing is locked while simulating
+    fn get_history_status(&self) -> (bool, bool) {
+        let state = self.application_state.borrow();
+        let editable = state.simulation.is_none();
+        (
+            editable && state.history.can_undo(),
+            editable && state.history.can_redo(),
+        )
+    }
+
+    /// Returns whether there are selected components, wires or annotations that can be deleted
+    fn can_delete(&self) -> bool {
+        let state = self.application_state.borrow();
+        state.simulation.is_none()
+            && !(state.selection.is_empty()
+                && state.selected_wires.is_empty()
+                && state.selected_annotations.is_empty())
+    }
+
+    fn get_workarea_size() -> (f64, f64) {
+        (
+            f64::from(Workarea::get_width()),
+            f64::from(Workarea::get_height()),
+        )
+    }
+
+    /// Returns whether there are selected components that can be copied
+    fn can_copy(&self) -> bool {
+        let state = self.application_state.borrow();
+        state.simulation.is_none() && !state.selection.is_empty()
+    }
+
+    fn get_simulation_status(&self) -> SimulationStatus {
+        if self.application_state.borrow().simulation.is_none() {
+            SimulationStatus::Stopped
+        } else if self.simulation_interval.is_some() {
+            SimulationStatus::Running
+        } else {
+            SimulationStatus::Paused
+        }
+    }
+}
@@ -1,77 +1,138 @@
 use wasm_bindgen::JsValue;
 use web_sys::CanvasRenderingContext2d;
 
-use crate::ui::connection_point::ConnectionPoint;
+use crate::{
+    circuit::{Component, ComponentType, Orientation, Timing},
+    ui::connection_point::ConnectionPoint,
+};
 
 use super::renderer::CanvasContextRenderer;
 
 #[derive(Clone)]
 pub struct CanvasElement {
     element: Box<dyn CanvasContextRenderer>,
-    position: (f64, f64),
+    component_type: ComponentType,
+    /// The height in the default orientation
     height: f64,
+    /// The width in the default orientation
     width: f64,
+    /// The connection points in the default orientation
     connection_points: Vec<ConnectionPoint>,
+    orientation: Orientation,
+    inverted_inputs: Vec<bool>,
+    timing: Timing,
+    label: String,
 }
 
 #[allow(clippy::module_name_repetitions)]
 pub trait IntoCanvasElement {
-    fn into_canvas_element(self, position: (f64, f64)) -> CanvasElement;
+    fn into_canvas_element(self: Box<Self>) -> CanvasElement;
 }
 
 impl CanvasElement {
     pub fn new(
         element: Box<dyn CanvasContextRenderer>,
-        position: (f64, f64),
+        component_type: ComponentType,
         height: f64,
         width: f64,
         connection_points: Vec<ConnectionPoint>,
     ) -> Self {
         Self {
             element,
-            position,
+            component_type,
             height,
             width,
             connection_points: connection_points.to_vec(),
+            orientation: Orientation::IDENTITY,
+            inverted_inputs: Vec::new(),
+            timing: Timing::INSTANT,
+            label: String::new(),
         }
     }
-    pub fn render(&self, ctx: &CanvasRenderingContext2d) -> Result<(), JsValue> {
-        self.render_at_position(ctx, self.position)
-    }
     pub fn render_at_position(
         &self,
         ctx: &CanvasRenderingContext2d,
         position: (f64, f64),
     ) -> Result<(), JsValue> {
-        self.element.render_at_position(ctx, position)
+        if self.orientation.is_identity() {
+            return self.element.render_at_position(ctx, position);
+        }
+        // the image is drawn in its default orientation into a turned coordinate system
+        let size = (self.width, self.height);
+        let origin = self.orientation.transform_point((0.0, 0.0), size);
+        let x_axis = self.orientation.transform_point((1.0, 0.0), size);
+        let y_axis = self.orientation.transform_point((0.0, 1.0), size);
+        ctx.save();
+        ctx.transform(
+            x_axis.0 - origin.0,
+            x_axis.1 - origin.1,
+            y_axis.0 - origin.0,
+            y_axis.1 - origin.1,
+            position.0 + origin.0,
+            position.1 + origin.1,
+        )?;
+        let result = self.element.render_at_position(ctx, (0.0, 0.0));
+        ctx.restore();
+        result
         // self.connection_points
         //     .iter()
         //     .for_each(|e| e.render_at_position(ctx, position))
     }
 
-    // pub fn set_position(&mut self, position: (f64, f64)) {
-    //     self.position = position;
-    // }
-    pub fn get_position(&self) -> (f64, f64) {
-        self.position
-    }
-    pub fn get_height(&self) -> f64 {
-        self.height
-    }
-    pub fn get_width(&self) -> f64 {
-        self.width
+    pub fn with_inverted_inputs(self, inverted_inputs: Vec<bool>) -> Self {
+        Self {
+            inverted_inputs,
+            ..self
+        }
     }
-    pub fn at_position(&self, position: (f64, f64)) -> Self {
+    pub fn with_orientation(self, orientation: Orientation) -> Self {
         Self {
-            element: self.element.clone(),
-            position,
-            width: self.width,
-            height: self.height,
-            connection_points: self.connection_points.clone(),
+            orientation,
+            ..self
         }
     }
-    pub const fn get_connection_points(&self) -> &Vec<ConnectionPoint> {
-        &self.connection_points
+    pub const fn get_orientation(&self) -> Orientation {
+        self.orientation
+    }
+    pub fn with_timing(self, timing: Timing) -> Self {
+        Self { timing, ..self }
+    }
+    pub fn with_label(self, label: String) -> Self {
+        Self { label, ..self }
+    }
+    pub const fn get_component_type(&self) -> ComponentType {
+        self.component_type
+    }
+    /// Returns the connection points as the element is oriented
+    pub fn get_connection_points(&self) -> Vec<ConnectionPoint> {
+        let size = (self.width, self.height);
+        self.connection_points
+            .iter()
+            .map(|cp| cp.oriented(self.orientation, size))
+            .collect()
+    }
+    /// Returns whether the element still shows the given component, i.e. its type and configuration did not change
+    pub fn matches(&self, component: &Component) -> bool {
+        self.component_type == component.get_component_type()
+            && self.inverted_inputs == component.get_inverted_inputs()
+            && self.timing == component.get_timing()
+            && self.orientation == component.get_orientation()
+            && self.label == component.get_label()
+            // the ports of a subcircuit change when it is edited
+            && self.get_connection_points() == component.get_ports()
+    }
+    /// Creates the circuit component described by this element, placed at the given position
+    pub fn to_component(&self, position: (f64, f64)) -> Component {
+        Component::new(
+            self.component_type,
+            position,
+            (self.width, self.height),
+            self.connection_points.clone(),
+        )
+        .with_orientation(self.orientation)
+        .with_inverted_inputs(self.inverted_inputs.clone())
+        .with_timing(self.timing)
+        .with_label(self.label.clone())
     }
 }
 
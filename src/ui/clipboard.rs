@@ -0,0 +1,47 @@
+use js_sys::{Function, Promise, Reflect};
+use wasm_bindgen::{closure::Closure, prelude::wasm_bindgen, JsCast, JsValue};
+use yew::Callback;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = writeText)]
+    fn write_clipboard_text(text: &str) -> Result<Promise, JsValue>;
+    #[wasm_bindgen(catch, js_namespace = ["navigator", "clipboard"], js_name = readText)]
+    fn read_clipboard_text() -> Result<Promise, JsValue>;
+}
+
+/// Puts the text on the system clipboard, so it can be pasted in other tabs and applications
+pub fn write_text(text: &str) -> Result<(), JsValue> {
+    let on_fulfilled = Closure::once_into_js(|_: JsValue| {});
+    let on_rejected = Closure::once_into_js(|e: JsValue| {
+        log::error!("Failed to write to the clipboard: {:?}", e);
+    });
+    then(&write_clipboard_text(text)?, &on_fulfilled, &on_rejected)
+}
+
+/// Emits the text on the system clipboard, or `None` if the browser does not allow reading it
+pub fn read_text(callback: Callback<Option<String>>) {
+    let Ok(promise) = read_clipboard_text() else {
+        callback.emit(None);
+        return;
+    };
+    let on_fulfilled = {
+        let callback = callback.clone();
+        Closure::once_into_js(move |text: JsValue| callback.emit(text.as_string()))
+    };
+    let on_rejected = Closure::once_into_js(move |e: JsValue| {
+        log::warn!("Failed to read the clipboard: {:?}", e);
+        callback.emit(None);
+    });
+    if let Err(e) = then(&promise, &on_fulfilled, &on_rejected) {
+        log::error!("Failed to read the clipboard: {:?}", e);
+    }
+}
+
+/// Calls `then` on the promise with callbacks that were handed over to JavaScript,
+/// so they are freed once the promise settles
+fn then(promise: &Promise, on_fulfilled: &JsValue, on_rejected: &JsValue) -> Result<(), JsValue> {
+    let then: Function = Reflect::get(promise, &JsValue::from_str("then"))?.dyn_into()?;
+    then.call2(promise, on_fulfilled, on_rejected)?;
+    Ok(())
+}
@@ -1,18 +1,26 @@
-use stylist::style;
 /**
  * This struct shows a list of available components in the application
  */
-use yew::{html, Callback, Classes, Properties};
+use std::{rc::Rc, str::FromStr};
+use stylist::style;
+
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::{html, Callback, Classes, Event, Properties};
 
-use crate::ui::{
-    application::CallbackReason,
-    canvas::IntoCanvasElement,
-    components::{
-        logic_gate::{LogicGate, LogicGateType},
-        pin::Pin,
+use crate::{
+    circuit::{
+        ButtonType, ComparatorMode, PinDirection, SequentialType, SubcircuitLibrary,
+        DEFAULT_GATE_INPUTS, MAX_BUS_WIDTH, MAX_CLOCK_PERIOD, MAX_GATE_INPUTS, MAX_PULSE_LENGTH,
+        MAX_REPEATER_DELAY, MIN_CLOCK_PERIOD,
+    },
+    ui::{
+        application::CallbackReason,
+        canvas::IntoCanvasElement,
+        components::logic_gate::{LogicGate, LogicGateType},
+        console_option::ConsoleOption,
+        redstone_component::ComponentType,
     },
-    console_option::ConsoleOption,
-    redstone_component::ComponentType,
 };
 
 use super::component_list_item::ComponentListItem;
@@ -22,42 +30,96 @@ pub struct ComponentListProps {
     #[prop_or_default]
     pub class: Classes,
     pub callback: Callback<CallbackReason>,
+    /// The user-defined components of the design
+    #[prop_or_default]
+    pub subcircuits: Rc<SubcircuitLibrary>,
 }
 
 pub enum ComponentListMsg {
     ComponentClicked(ComponentType),
+    /// Sets the number of inputs of newly placed gates
+    SetGateInputs(usize),
+    /// Sets the number of bits of newly placed pins, splitters and mergers
+    SetBusWidth(u8),
+    /// Sets the delay of newly placed repeaters
+    SetRepeaterDelay(u8),
+    /// Sets the period of newly placed clocks, rounded to an even number of ticks
+    SetClockPeriod(u8),
+    /// Sets the pulse length of newly placed monostables
+    SetPulseLength(u8),
 }
 
-pub struct ComponentList {}
+pub struct ComponentList {
+    gate_inputs: usize,
+    bus_width: u8,
+    repeater_delay: u8,
+    clock_period: u8,
+    pulse_length: u8,
+}
+
+impl ComponentList {
+    /// Creates the callback of a number input, changes that aren't a number are ignored
+    fn on_number_change<T: FromStr + 'static>(
+        ctx: &yew::Context<Self>,
+        msg: fn(T) -> ComponentListMsg,
+    ) -> Callback<Event> {
+        ctx.link().batch_callback(move |e: Event| {
+            let input = e.target()?.dyn_into::<HtmlInputElement>().ok()?;
+            input.value().parse().ok().map(msg)
+        })
+    }
+}
 
 impl yew::html::Component for ComponentList {
     type Message = ComponentListMsg;
     type Properties = ComponentListProps;
 
     fn create(_ctx: &yew::Context<Self>) -> Self {
-        Self {}
+        Self {
+            gate_inputs: DEFAULT_GATE_INPUTS,
+            bus_width: 1,
+            repeater_delay: 1,
+            clock_period: 10,
+            pulse_length: 1,
+        }
     }
     fn update(&mut self, _ctx: &yew::Context<Self>, _msg: Self::Message) -> bool {
         use ComponentListMsg::*;
         match _msg {
-            ComponentClicked(component_type) => match component_type {
-                ComponentType::LogicGate(gate_type) => {
-                    _ctx.props().callback.emit(CallbackReason::ToolChanged(Some(
-                        LogicGate::try_from(gate_type)
-                            .unwrap_to_console()
-                            .into_canvas_element((0.0, 0.0)),
-                    )));
-                }
-                ComponentType::Pin => {
-                    _ctx.props().callback.emit(CallbackReason::ToolChanged(Some(
-                        Pin::new()
-                            .unwrap_to_console()
-                            .into_canvas_element((0.0, 0.0)),
-                    )))
-                }
-            },
+            ComponentClicked(component_type) => {
+                let element = match component_type {
+                    ComponentType::LogicGate(gate_type) => {
+                        LogicGate::new_with_inputs(gate_type, self.gate_inputs)
+                            .map(|gate| Box::new(gate).into_canvas_element())
+                    }
+                    _ => component_type.try_as_canvas_element(&_ctx.props().subcircuits),
+                };
+                _ctx.props().callback.emit(CallbackReason::ToolChanged(Some(
+                    element.unwrap_to_console(),
+                )));
+                false
+            }
+            SetGateInputs(inputs) => {
+                self.gate_inputs = inputs.clamp(DEFAULT_GATE_INPUTS, MAX_GATE_INPUTS);
+                true
+            }
+            SetBusWidth(width) => {
+                self.bus_width = width.clamp(1, MAX_BUS_WIDTH);
+                true
+            }
+            SetRepeaterDelay(delay) => {
+                self.repeater_delay = delay.clamp(1, MAX_REPEATER_DELAY);
+                true
+            }
+            SetClockPeriod(period) => {
+                self.clock_period = period.clamp(MIN_CLOCK_PERIOD, MAX_CLOCK_PERIOD) & !1;
+                true
+            }
+            SetPulseLength(length) => {
+                self.pulse_length = length.clamp(1, MAX_PULSE_LENGTH);
+                true
+            }
         }
-        false
     }
 
     fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
@@ -65,6 +127,7 @@ impl yew::html::Component for ComponentList {
         let style_component_list_outer = style!(
             r#"
             height: 100%;
+            overflow-y: auto;
             border-right: 1px solid gray;
         "#
         )
@@ -79,16 +142,99 @@ impl yew::html::Component for ComponentList {
         "#
         )
         .unwrap();
+        let style_number_input = style!(
+            r#"
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+            padding: 7.5px;
+            border-bottom: 1px solid #eee;
+            user-select: none;
+        "#
+        )
+        .unwrap();
+        let style_heading = style!(
+            r#"
+            grid-column: 1 / -1;
+            padding-top: 10px;
+            font-weight: bold;
+            user-select: none;
+        "#
+        )
+        .unwrap();
         let onclick = ctx.link().callback(ComponentListMsg::ComponentClicked);
+        let subcircuits = ctx.props().subcircuits.clone();
+        let on_gate_inputs_change = Self::on_number_change(ctx, ComponentListMsg::SetGateInputs);
+        let on_bus_width_change = Self::on_number_change(ctx, ComponentListMsg::SetBusWidth);
+        let on_repeater_delay_change =
+            Self::on_number_change(ctx, ComponentListMsg::SetRepeaterDelay);
+        let on_clock_period_change = Self::on_number_change(ctx, ComponentListMsg::SetClockPeriod);
+        let on_pulse_length_change = Self::on_number_change(ctx, ComponentListMsg::SetPulseLength);
         html! {
             <div class={ classes }>
+                <label class={ style_number_input.clone() }>
+                    { "Gate inputs" }
+                    <input type="number" min={ DEFAULT_GATE_INPUTS.to_string() } max={ MAX_GATE_INPUTS.to_string() } value={ self.gate_inputs.to_string() } onchange={ on_gate_inputs_change } />
+                </label>
+                <label class={ style_number_input.clone() }>
+                    { "Bus width" }
+                    <input type="number" min="1" max={ MAX_BUS_WIDTH.to_string() } value={ self.bus_width.to_string() } onchange={ on_bus_width_change } />
+                </label>
+                <label class={ style_number_input.clone() }>
+                    { "Repeater delay" }
+                    <input type="number" min="1" max={ MAX_REPEATER_DELAY.to_string() } value={ self.repeater_delay.to_string() } onchange={ on_repeater_delay_change } />
+                </label>
+                <label class={ style_number_input.clone() }>
+                    { "Clock period" }
+                    <input type="number" step="2" min={ MIN_CLOCK_PERIOD.to_string() } max={ MAX_CLOCK_PERIOD.to_string() } value={ self.clock_period.to_string() } onchange={ on_clock_period_change } />
+                </label>
+                <label class={ style_number_input }>
+                    { "Pulse length" }
+                    <input type="number" min="1" max={ MAX_PULSE_LENGTH.to_string() } value={ self.pulse_length.to_string() } onchange={ on_pulse_length_change } />
+                </label>
                 <div class={ style_component_list_inner }>
+                    <span class={ style_heading.clone() }>{ "Logic" }</span>
                     <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::And) } onclick={ onclick.clone() } />
                     <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::Or) } onclick={ onclick.clone() } />
                     <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::Xor) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::Xnor) } onclick={ onclick.clone() } />
                     <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::Nand) } onclick={ onclick.clone() } />
                     <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::Nor) } onclick={ onclick.clone() } />
-                    <ComponentListItem component_type={ ComponentType::Pin } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::Not) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::LogicGate(LogicGateType::Buffer) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Pin(PinDirection::Input, self.bus_width) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Pin(PinDirection::Output, self.bus_width) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Pin(PinDirection::Bidirectional, self.bus_width) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Splitter(self.bus_width.max(2)) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Merger(self.bus_width.max(2)) } onclick={ onclick.clone() } />
+                    <span class={ style_heading.clone() }>{ "Sequential" }</span>
+                    <ComponentListItem component_type={ ComponentType::Sequential(SequentialType::SrLatch) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Sequential(SequentialType::DLatch) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Sequential(SequentialType::DFlipFlop) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Sequential(SequentialType::TFlipFlop) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Sequential(SequentialType::JkFlipFlop) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Clock(self.clock_period) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Monostable(self.pulse_length) } onclick={ onclick.clone() } />
+                    <span class={ style_heading.clone() }>{ "Redstone" }</span>
+                    <ComponentListItem component_type={ ComponentType::Torch } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Repeater(self.repeater_delay) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Comparator(ComparatorMode::Compare) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Comparator(ComparatorMode::Subtract) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Observer } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Lever } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Button(ButtonType::Stone) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Button(ButtonType::Wood) } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::PressurePlate } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Lamp } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::Piston } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::StickyPiston } onclick={ onclick.clone() } />
+                    <ComponentListItem component_type={ ComponentType::NoteBlock } onclick={ onclick.clone() } />
+                    if !subcircuits.is_empty() {
+                        <span class={ style_heading }>{ "Subcircuits" }</span>
+                    }
+                    { for subcircuits.iter().map(|(id, _)| html! {
+                        <ComponentListItem component_type={ ComponentType::Subcircuit(id) } onclick={ onclick.clone() } subcircuits={ subcircuits.clone() } />
+                    }) }
                 </div>
             </div>
         }
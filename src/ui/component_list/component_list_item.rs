@@ -1,10 +1,12 @@
+use std::rc::Rc;
+
 use stylist::style;
 use yew::{html, Callback, Properties};
 use yew_icons::IconId;
 
-use crate::ui::{
-    console_option::ConsoleOption,
-    redstone_component::ComponentType,
+use crate::{
+    circuit::SubcircuitLibrary,
+    ui::{console_option::ConsoleOption, redstone_component::ComponentType},
 };
 
 #[derive(Debug, Clone, PartialEq, Properties)]
@@ -16,6 +18,9 @@ pub struct ComponentListItemProps {
     #[prop_or_default]
     pub onclick: Callback<ComponentType>,
     pub component_type: ComponentType,
+    /// The definitions of the user-defined components that can be listed
+    #[prop_or_default]
+    pub subcircuits: Rc<SubcircuitLibrary>,
 }
 
 pub struct ComponentListItem;
@@ -60,7 +65,9 @@ impl yew::html::Component for ComponentListItem {
         let onclick = ctx.props().onclick.clone();
         let component_type = ctx.props().component_type;
 
-        let component = component_type.try_as_component().unwrap_to_console();
+        let component = component_type
+            .try_as_component(&ctx.props().subcircuits)
+            .unwrap_to_console();
 
         let title = if ctx.props().title.is_empty() {
             component.get_component_list_item_title()
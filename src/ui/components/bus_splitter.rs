@@ -0,0 +1,187 @@
+use stylist::style;
+use wasm_bindgen::JsValue;
+use yew::html;
+
+use crate::{
+    circuit::{Component, Property, PropertyValue, Timing, MAX_BUS_WIDTH},
+    ui::{
+        canvas::{CanvasContextRenderer, CanvasElement, CanvasSVGImage, IntoCanvasElement},
+        connection_point::{ConnectionKind, ConnectionPoint},
+        console_option::ConsoleOption,
+        redstone_component::{ComponentType, RedstoneComponent},
+    },
+};
+
+const STROKE: &str = "fill: none; stroke: rgb(0, 0, 0); stroke-width: 2px;";
+const BUS_STROKE: &str = "fill: none; stroke: rgb(0, 0, 0); stroke-width: 4px;";
+const TEXT: &str = "font-family: Arial; font-size: 9px;";
+const WIDTH: f64 = 50.0;
+/// Distance between the bits
+const PORT_SPACING: f64 = 25.0;
+
+#[derive(Clone)]
+/// A splitter fanning a bus out into its bits or a merger joining bits into a bus, drawn as a bar with the bus on
+/// one side and a numbered line for every bit on the other
+pub struct BusSplitter {
+    component_type: ComponentType,
+    image: CanvasSVGImage,
+}
+
+impl BusSplitter {
+    pub fn new(component_type: ComponentType) -> Result<Self, JsValue> {
+        let image = CanvasSVGImage::new(Self::get_svg_string(component_type))?;
+        Ok(Self {
+            component_type,
+            image,
+        })
+    }
+
+    const fn get_width(component_type: ComponentType) -> u8 {
+        match component_type {
+            ComponentType::Splitter(width) | ComponentType::Merger(width) => width,
+            _ => 1,
+        }
+    }
+    const fn is_merger(component_type: ComponentType) -> bool {
+        matches!(component_type, ComponentType::Merger(_))
+    }
+    fn get_height(component_type: ComponentType) -> f64 {
+        f64::from(Self::get_width(component_type) + 1) * PORT_SPACING
+    }
+    fn get_bit_y(bit: u8) -> f64 {
+        f64::from(bit + 1) * PORT_SPACING
+    }
+
+    fn get_svg_string(component_type: ComponentType) -> String {
+        let width = Self::get_width(component_type);
+        let height = Self::get_height(component_type);
+        // the bus enters on the left of a splitter and leaves on the right of a merger
+        let (bus_x, bits_x, anchor) = if Self::is_merger(component_type) {
+            (25, 0, "start")
+        } else {
+            (0, 25, "end")
+        };
+        let mut bits = format!(
+            "M25,{} V{} ",
+            PORT_SPACING / 2.0,
+            height - PORT_SPACING / 2.0
+        );
+        let mut labels = String::new();
+        for bit in 0..width {
+            let y = Self::get_bit_y(bit);
+            bits.push_str(&format!("M{bits_x},{y} h25 "));
+            let x = if anchor == "end" { 48 } else { 2 };
+            labels.push_str(&format!(
+                r#"<text x="{x}" y="{}" text-anchor="{anchor}" style="{TEXT}">{bit}</text>"#,
+                y - 3.0
+            ));
+        }
+        format!(
+            r#"
+            <svg width="{WIDTH}" height="{height}" xmlns="http://www.w3.org/2000/svg">
+                <path style="{STROKE}" d="{bits}"></path>
+                <path style="{BUS_STROKE}" d="M{bus_x},{PORT_SPACING} h25"></path>
+                {labels}
+            </svg>"#
+        )
+    }
+}
+
+impl RedstoneComponent for BusSplitter {
+    fn get_connection_points(&self) -> Vec<ConnectionPoint> {
+        let width = Self::get_width(self.component_type);
+        let merger = Self::is_merger(self.component_type);
+        let (bus_kind, bit_kind) = if merger {
+            (ConnectionKind::Output, ConnectionKind::Input)
+        } else {
+            (ConnectionKind::Input, ConnectionKind::Output)
+        };
+        let side = |left: bool| {
+            if left {
+                (0.0, [false, false, false, true])
+            } else {
+                (WIDTH, [false, true, false, false])
+            }
+        };
+        let (bus_x, bus_directions) = side(!merger);
+        let bus =
+            ConnectionPoint::new(bus_x, PORT_SPACING, bus_directions, bus_kind).with_width(width);
+        let (bits_x, bits_directions) = side(merger);
+        let bits = (0..width).map(|bit| {
+            ConnectionPoint::new(bits_x, Self::get_bit_y(bit), bits_directions, bit_kind)
+        });
+        // the bus comes first on a splitter and last on a merger, in the direction of the signal
+        if merger {
+            bits.chain(std::iter::once(bus)).collect()
+        } else {
+            std::iter::once(bus).chain(bits).collect()
+        }
+    }
+    fn get_component_type(&self) -> ComponentType {
+        self.component_type
+    }
+    fn get_component_list_item_title(&self) -> String {
+        format!("{}", self.component_type)
+    }
+    fn get_timing(&self) -> Timing {
+        // the bits of a bus run side by side
+        Timing::INSTANT
+    }
+    fn get_component_list_item_icon(&self) -> yew::Html {
+        let style_image = style!(
+            r#"
+            align-self: center;
+            max-height: 50px;
+        "#
+        )
+        .unwrap_to_console();
+        html! {
+            <img class={ style_image } src={ self.image.get_url() } />
+        }
+    }
+    fn get_properties(&self) -> Vec<Property> {
+        vec![Property::integer(
+            "Width",
+            2..=u32::from(MAX_BUS_WIDTH),
+            u32::from(Self::get_width(self.component_type)),
+        )]
+    }
+    fn apply_property(
+        &self,
+        component: Component,
+        name: &str,
+        value: &PropertyValue,
+    ) -> Option<Component> {
+        let PropertyValue::Integer(width) = value else {
+            return None;
+        };
+        let width = u8::try_from(*width).ok().filter(|_| name == "Width")?;
+        let component_type = if Self::is_merger(self.component_type) {
+            ComponentType::Merger(width)
+        } else {
+            ComponentType::Splitter(width)
+        };
+        Some(component.with_component_type(component_type))
+    }
+}
+
+impl CanvasContextRenderer for BusSplitter {
+    fn render_at_position(
+        &self,
+        ctx: &web_sys::CanvasRenderingContext2d,
+        position: (f64, f64),
+    ) -> Result<(), JsValue> {
+        ctx.draw_image_with_html_image_element(&self.image.image, position.0, position.1)
+    }
+}
+
+impl IntoCanvasElement for BusSplitter {
+    fn into_canvas_element(self: Box<Self>) -> CanvasElement {
+        let connection_points = self.get_connection_points();
+        let component_type = self.get_component_type();
+        let timing = self.get_timing();
+        let height = Self::get_height(component_type);
+        CanvasElement::new(self, component_type, height, WIDTH, connection_points)
+            .with_timing(timing)
+    }
+}
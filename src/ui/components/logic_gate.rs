@@ -1,118 +1,136 @@
-use std::fmt::Display;
-
 use stylist::style;
 use wasm_bindgen::JsValue;
 use yew::html;
 
-use crate::ui::{
-    canvas::{CanvasContextRenderer, CanvasSVGImage},
-    console_option::ConsoleOption,
-    redstone_component::{ComponentType, RedstoneComponent},
+use crate::{
+    circuit::{Component, Property, PropertyValue, PulseBehaviour, Timing},
+    ui::{
+        canvas::{CanvasContextRenderer, CanvasSVGImage},
+        console_option::ConsoleOption,
+        redstone_component::{ComponentType, RedstoneComponent},
+    },
 };
 
 use super::super::{
     canvas::{CanvasElement, IntoCanvasElement},
-    connection_point::ConnectionPoint,
+    connection_point::{ConnectionKind, ConnectionPoint},
 };
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum LogicGateType {
-    And,
-    Or,
-    Xor,
-    Nand,
-    Nor,
-}
+pub use crate::circuit::LogicGateType;
+
+/// Width of a gate including its input and output lines
+const GATE_WIDTH: f64 = 125.0;
+/// Height a gate grows by with every input
+const INPUT_SPACING: f64 = 50.0;
+const INPUTS_PROPERTY: &str = "Inputs";
+/// Prefix of the properties inverting an input, followed by the number of the input
+const INVERT_PROPERTY: &str = "Invert input ";
 
 impl LogicGateType {
-    fn get_svg_string(&self, inputs_inverted: (bool, bool)) -> String {
+    fn get_svg_string(&self, inputs_inverted: &[bool]) -> String {
         let (text, text_x) = self.get_svg_text();
-        let (input_path1, input_path2) = self.get_input_svg_path(inputs_inverted);
-        let output_path = self.get_output_svg_path();
+        let height = LogicGate::get_height(inputs_inverted.len());
+        let output_y = height / 2.0;
+        let mut inputs = String::new();
+        for (input, inverted) in inputs_inverted.iter().enumerate() {
+            let y = LogicGate::get_input_y(input);
+            inputs.push_str(&if *inverted {
+                format!("M0,{y} H15 A5,5,0,0,0,25,{y} A5,5,0,0,0,15,{y} ")
+            } else {
+                format!("M0,{y} H25 ")
+            });
+        }
+        let output = if self.has_inverted_output() {
+            format!("M100,{output_y} A5,5,0,0,0,110,{output_y} A5,5,0,0,0,100,{output_y} M110,{output_y} H125")
+        } else {
+            format!("M100,{output_y} H125")
+        };
         format!(
             r#"
-            <svg width="125" height="100" xmlns="http://www.w3.org/2000/svg">
-                <path style="fill: none; stroke: rgb(0, 0, 0); stroke-width: 2px;" d="M0,25 {input_path1} V1 H100 V50 {output_path} V99 H25 V75 {input_path2} V25"></path>
+            <svg width="125" height="{height}" xmlns="http://www.w3.org/2000/svg">
+                <path style="fill: none; stroke: rgb(0, 0, 0); stroke-width: 2px;" d="M25,1 H100 V{bottom} H25 Z {inputs}{output}"></path>
                 <text x="{text_x}" y="25" style="font-family: Arial; font-size: 20px;">{text}</text>
-            </svg>"#
+            </svg>"#,
+            bottom = height - 1.0,
         )
     }
     const fn get_svg_text(&self) -> (&'static str, i32) {
-        use LogicGateType::{And, Nand, Nor, Or, Xor};
+        use LogicGateType::{And, Buffer, Nand, Nor, Not, Or, Xnor, Xor};
         match self {
             And | Nand => ("&amp;", 75),
             Or | Nor => ("&gt;=1", 55),
-            Xor => ("=1", 65),
-        }
-    }
-
-    const fn get_input_svg_path(
-        &self,
-        inputs_inverted: (bool, bool),
-    ) -> (&'static str, &'static str) {
-        let input1 = if !inputs_inverted.0 {
-            "H25"
-        } else {
-            "H15 A5,5,0,0,0,25,25 A5,5,0,0,0,15,25 M25,25"
-        };
-        let input2 = if !inputs_inverted.1 {
-            "H0 H25"
-        } else {
-            "A5,5,0,0,0,15,75 H0 M15,75 A5,5,0,0,0,25,75"
-        };
-        (input1, input2)
-    }
-
-    const fn get_output_svg_path(&self) -> &'static str {
-        use LogicGateType::{And, Nand, Nor, Or, Xor};
-        match self {
-            And | Or | Xor => "H125 H100",
-            Nand | Nor => "A5,5,0,0,0,110,50 A5,5,0,0,0,100,50 M110,50 H125 M100,50",
+            Xor | Xnor => ("=1", 65),
+            Not | Buffer => ("1", 80),
         }
     }
 }
 
-impl Display for LogicGateType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{self:?} Gate")
-    }
-}
-
 #[derive(Clone)]
-/// A Simple 2 input 1 ouput logic gate
+/// A logic gate with one output and up to [`crate::circuit::MAX_GATE_INPUTS`] inputs, each of which can be inverted
 pub struct LogicGate {
     gate_type: LogicGateType,
     image: CanvasSVGImage,
-    _inputs_inverted: (bool, bool),
+    inputs_inverted: Vec<bool>,
 }
 impl LogicGate {
     pub fn new(gate_type: LogicGateType) -> Result<Self, JsValue> {
-        Self::new_with_inverted_inputs(gate_type, (false, false))
+        Self::new_with_inverted_inputs(gate_type, Vec::new())
+    }
+    /// Creates a gate with the given number of inputs, none of them inverted
+    pub fn new_with_inputs(gate_type: LogicGateType, inputs: usize) -> Result<Self, JsValue> {
+        Self::new_with_inverted_inputs(gate_type, vec![false; inputs])
     }
 
+    /// Creates a gate with an input for every entry, which is inverted if the entry is set.
+    /// The number of inputs is brought into the range the gate type allows.
     pub fn new_with_inverted_inputs(
         gate_type: LogicGateType,
-        inputs_inverted: (bool, bool),
+        mut inputs_inverted: Vec<bool>,
     ) -> Result<Self, JsValue> {
-        let image = CanvasSVGImage::new(gate_type.get_svg_string(inputs_inverted))?;
+        let range = gate_type.get_input_range();
+        let inputs = inputs_inverted.len().clamp(*range.start(), *range.end());
+        inputs_inverted.resize(inputs, false);
+        let image = CanvasSVGImage::new(gate_type.get_svg_string(&inputs_inverted))?;
         Ok(Self {
             gate_type,
             image,
-            _inputs_inverted: inputs_inverted,
+            inputs_inverted,
         })
     }
 
-    const CONNECTION_POINTS: &[ConnectionPoint] = &[
-        ConnectionPoint::new(0.0, 25.0, [true, false, false, true]),
-        ConnectionPoint::new(0.0, 75.0, [false, false, true, true]),
-        ConnectionPoint::new(125.0, 50.0, [true, true, true, false]),
-    ];
+    #[allow(clippy::cast_precision_loss)]
+    fn get_height(inputs: usize) -> f64 {
+        inputs as f64 * INPUT_SPACING
+    }
+    #[allow(clippy::cast_precision_loss)]
+    fn get_input_y(input: usize) -> f64 {
+        (input as f64).mul_add(INPUT_SPACING, INPUT_SPACING / 2.0)
+    }
 }
 
 impl RedstoneComponent for LogicGate {
     fn get_connection_points(&self) -> Vec<ConnectionPoint> {
-        Self::CONNECTION_POINTS.to_vec()
+        let inputs = self.inputs_inverted.len();
+        let last = inputs.saturating_sub(1);
+        let mut points: Vec<_> = (0..inputs)
+            .map(|input| {
+                // the outer inputs can also be reached from above and below
+                let directions = [input == 0, false, input == last, true];
+                ConnectionPoint::new(
+                    0.0,
+                    Self::get_input_y(input),
+                    directions,
+                    ConnectionKind::Input,
+                )
+            })
+            .collect();
+        points.push(ConnectionPoint::new(
+            GATE_WIDTH,
+            Self::get_height(inputs) / 2.0,
+            [true, true, true, false],
+            ConnectionKind::Output,
+        ));
+        points
     }
     fn get_component_type(&self) -> ComponentType {
         ComponentType::LogicGate(self.gate_type)
@@ -120,6 +138,14 @@ impl RedstoneComponent for LogicGate {
     fn get_component_list_item_title(&self) -> String {
         format!("{}", self.gate_type)
     }
+    fn get_timing(&self) -> Timing {
+        // every inverted input needs an additional torch in front of the gate
+        let inverted = u8::from(self.inputs_inverted.contains(&true));
+        Timing::new(
+            self.gate_type.get_delay() + inverted,
+            PulseBehaviour::Transport,
+        )
+    }
     fn get_component_list_item_icon(&self) -> yew::Html {
         let style_image = style!(
             r#"
@@ -132,6 +158,46 @@ impl RedstoneComponent for LogicGate {
             <img class={ style_image } src={ self.image.get_url() } />
         }
     }
+    fn get_properties(&self) -> Vec<Property> {
+        let range = self.gate_type.get_input_range();
+        let to_u32 = |inputs: usize| u32::try_from(inputs).unwrap_or(u32::MAX);
+        let mut properties = Vec::new();
+        if range.start() != range.end() {
+            properties.push(Property::integer(
+                INPUTS_PROPERTY,
+                to_u32(*range.start())..=to_u32(*range.end()),
+                to_u32(self.inputs_inverted.len()),
+            ));
+        }
+        properties.extend(
+            self.inputs_inverted
+                .iter()
+                .enumerate()
+                .map(|(input, inverted)| {
+                    Property::bool(&format!("{INVERT_PROPERTY}{}", input + 1), *inverted)
+                }),
+        );
+        properties
+    }
+    fn apply_property(
+        &self,
+        component: Component,
+        name: &str,
+        value: &PropertyValue,
+    ) -> Option<Component> {
+        let mut inputs_inverted = self.inputs_inverted.clone();
+        match value {
+            PropertyValue::Integer(inputs) if name == INPUTS_PROPERTY => {
+                inputs_inverted.resize(usize::try_from(*inputs).ok()?, false);
+            }
+            PropertyValue::Bool(inverted) => {
+                let input: usize = name.strip_prefix(INVERT_PROPERTY)?.parse().ok()?;
+                *inputs_inverted.get_mut(input.checked_sub(1)?)? = *inverted;
+            }
+            _ => return None,
+        }
+        Some(component.with_inverted_inputs(inputs_inverted))
+    }
 }
 
 impl CanvasContextRenderer for LogicGate {
@@ -152,8 +218,14 @@ impl TryFrom<LogicGateType> for LogicGate {
 }
 
 impl IntoCanvasElement for LogicGate {
-    fn into_canvas_element(self, position: (f64, f64)) -> CanvasElement {
+    fn into_canvas_element(self: Box<Self>) -> CanvasElement {
         let connection_points = self.get_connection_points();
-        CanvasElement::new(Box::new(self), position, 100.0, 125.0, connection_points)
+        let component_type = self.get_component_type();
+        let inverted_inputs = self.inputs_inverted.clone();
+        let timing = self.get_timing();
+        let height = Self::get_height(inverted_inputs.len());
+        CanvasElement::new(self, component_type, height, GATE_WIDTH, connection_points)
+            .with_inverted_inputs(inverted_inputs)
+            .with_timing(timing)
     }
 }
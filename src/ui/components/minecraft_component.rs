@@ -0,0 +1,273 @@
+use stylist::style;
+use wasm_bindgen::JsValue;
+use yew::html;
+
+use crate::{
+    circuit::{
+        ButtonType, ComparatorMode, Component, Property, PropertyValue, PulseBehaviour, Timing,
+        MAX_REPEATER_DELAY,
+    },
+    ui::{
+        canvas::{CanvasContextRenderer, CanvasElement, CanvasSVGImage, IntoCanvasElement},
+        connection_point::{ConnectionKind, ConnectionPoint},
+        console_option::ConsoleOption,
+        redstone_component::{ComponentType, RedstoneComponent},
+    },
+};
+
+const STROKE: &str = "fill: none; stroke: rgb(0, 0, 0); stroke-width: 2px;";
+const TEXT: &str = "font-family: Arial; font-size: 20px;";
+const HEIGHT: f64 = 50.0;
+const COMPARATOR_MODE_NAMES: &[&str] = &["Compare", "Subtract"];
+const BUTTON_TYPE_NAMES: &[&str] = &["Stone", "Wood"];
+
+#[derive(Clone)]
+/// A component of the game itself, e.g. a torch, a repeater or a lamp
+pub struct MinecraftComponent {
+    component_type: ComponentType,
+    image: CanvasSVGImage,
+}
+
+impl MinecraftComponent {
+    pub fn new(component_type: ComponentType) -> Result<Self, JsValue> {
+        let image = CanvasSVGImage::new(Self::get_svg_string(component_type))?;
+        Ok(Self {
+            component_type,
+            image,
+        })
+    }
+
+    /// Components with an input and an output are wider than sources and sinks
+    const fn get_width(component_type: ComponentType) -> f64 {
+        match component_type {
+            ComponentType::Torch
+            | ComponentType::Repeater(_)
+            | ComponentType::Comparator(_)
+            | ComponentType::Observer => 100.0,
+            _ => 75.0,
+        }
+    }
+    const fn has_input(component_type: ComponentType) -> bool {
+        !component_type.is_user_input()
+    }
+    const fn has_output(component_type: ComponentType) -> bool {
+        !matches!(
+            component_type,
+            ComponentType::Lamp
+                | ComponentType::Piston
+                | ComponentType::StickyPiston
+                | ComponentType::NoteBlock
+        )
+    }
+
+    fn get_svg_string(component_type: ComponentType) -> String {
+        let width = Self::get_width(component_type);
+        let body = match component_type {
+            ComponentType::Torch => format!(
+                r#"<path style="{STROKE}" d="M0,25 H42 M58,25 H100 M46,46 V20 H54 V46 Z"></path>
+                <circle cx="50" cy="12" r="7" style="fill: rgb(255, 60, 0);"></circle>"#
+            ),
+            ComponentType::Repeater(delay) => format!(
+                r#"<path style="{STROKE}" d="M0,25 H25 M75,25 H100 M25,10 H75 V40 H25 Z M32,15 L50,25 L32,35 Z"></path>
+                <text x="56" y="32" style="{TEXT}">{delay}</text>"#
+            ),
+            ComponentType::Comparator(mode) => {
+                let symbol = match mode {
+                    ComparatorMode::Compare => "&#8805;",
+                    ComparatorMode::Subtract => "&#8722;",
+                };
+                format!(
+                    r#"<path style="{STROKE}" d="M0,25 H25 M75,25 H100 M50,40 V50 M25,10 H75 V40 H25 Z M32,15 L50,25 L32,35 Z"></path>
+                    <text x="55" y="32" style="{TEXT}">{symbol}</text>"#
+                )
+            }
+            ComponentType::Observer => format!(
+                r#"<path style="{STROKE}" d="M0,25 H25 M75,25 H100 M25,5 H75 V45 H25 Z M33,17 H43 M57,17 H67 M38,33 H62"></path>"#
+            ),
+            ComponentType::Lever => format!(
+                r#"<path style="{STROKE}" d="M50,25 H75 M5,5 H50 V45 H5 Z M15,38 H40 M27,38 L38,14"></path>"#
+            ),
+            ComponentType::Button(_) => format!(
+                r#"<path style="{STROKE}" d="M50,25 H75 M5,5 H50 V45 H5 Z M18,19 H37 V31 H18 Z"></path>"#
+            ),
+            ComponentType::PressurePlate => format!(
+                r#"<path style="{STROKE}" d="M50,25 H75 M5,5 H50 V45 H5 Z M11,33 H44 V39 H11 Z"></path>"#
+            ),
+            ComponentType::Lamp => format!(
+                r#"<path style="{STROKE}" d="M0,25 H25 M25,5 H70 V45 H25 Z M39,16 L56,34 M56,16 L39,34"></path>
+                <circle cx="47.5" cy="25" r="12" style="{STROKE}"></circle>"#
+            ),
+            ComponentType::Piston | ComponentType::StickyPiston => {
+                let head = if component_type == ComponentType::StickyPiston {
+                    "rgb(100, 180, 80)"
+                } else {
+                    "none"
+                };
+                format!(
+                    r#"<path style="{STROKE}" d="M0,25 H25 M25,16 H70 V45 H25 Z M47.5,12 V16"></path>
+                    <rect x="25" y="5" width="45" height="7" style="fill: {head}; stroke: rgb(0, 0, 0); stroke-width: 2px;"></rect>"#
+                )
+            }
+            ComponentType::NoteBlock => format!(
+                r#"<path style="{STROKE}" d="M0,25 H25 M25,5 H70 V45 H25 Z"></path>
+                <text x="38" y="33" style="{TEXT}">&#9834;</text>"#
+            ),
+            ComponentType::LogicGate(_)
+            | ComponentType::Pin(..)
+            | ComponentType::Sequential(_)
+            | ComponentType::Clock(_)
+            | ComponentType::Monostable(_)
+            | ComponentType::Subcircuit(_)
+            | ComponentType::Splitter(_)
+            | ComponentType::Merger(_) => String::new(),
+        };
+        format!(
+            r#"
+            <svg width="{width}" height="{HEIGHT}" xmlns="http://www.w3.org/2000/svg">
+                {body}
+            </svg>"#
+        )
+    }
+}
+
+impl RedstoneComponent for MinecraftComponent {
+    fn get_connection_points(&self) -> Vec<ConnectionPoint> {
+        let width = Self::get_width(self.component_type);
+        let mut points = Vec::new();
+        if Self::has_input(self.component_type) {
+            points.push(ConnectionPoint::new(
+                0.0,
+                HEIGHT / 2.0,
+                [true, false, true, true],
+                ConnectionKind::Input,
+            ));
+        }
+        // the side of a comparator faces downwards
+        if matches!(self.component_type, ComponentType::Comparator(_)) {
+            points.push(ConnectionPoint::new(
+                width / 2.0,
+                HEIGHT,
+                [false, false, true, false],
+                ConnectionKind::Input,
+            ));
+        }
+        if Self::has_output(self.component_type) {
+            points.push(ConnectionPoint::new(
+                width,
+                HEIGHT / 2.0,
+                [true, true, true, false],
+                ConnectionKind::Output,
+            ));
+        }
+        points
+    }
+    fn get_component_type(&self) -> ComponentType {
+        self.component_type
+    }
+    fn get_component_list_item_title(&self) -> String {
+        format!("{}", self.component_type)
+    }
+    fn get_timing(&self) -> Timing {
+        match self.component_type {
+            ComponentType::Torch => Timing::torch(),
+            ComponentType::Repeater(delay) => Timing::repeater(delay),
+            ComponentType::Comparator(_) => Timing::comparator(),
+            ComponentType::Observer => Timing::new(1, PulseBehaviour::Transport),
+            _ => Timing::INSTANT,
+        }
+    }
+    fn get_component_list_item_icon(&self) -> yew::Html {
+        let style_image = style!(
+            r#"
+            align-self: center;
+            height: 50px;
+        "#
+        )
+        .unwrap_to_console();
+        html! {
+            <img class={ style_image } src={ self.image.get_url() } />
+        }
+    }
+    fn get_properties(&self) -> Vec<Property> {
+        match self.component_type {
+            ComponentType::Repeater(delay) => vec![Property::integer(
+                "Delay",
+                1..=u32::from(MAX_REPEATER_DELAY),
+                u32::from(delay),
+            )],
+            ComponentType::Comparator(mode) => vec![Property::choice(
+                "Mode",
+                COMPARATOR_MODE_NAMES,
+                ComparatorMode::ALL
+                    .iter()
+                    .position(|m| *m == mode)
+                    .unwrap_or_default(),
+            )],
+            ComponentType::Button(button_type) => vec![Property::choice(
+                "Material",
+                BUTTON_TYPE_NAMES,
+                ButtonType::ALL
+                    .iter()
+                    .position(|b| *b == button_type)
+                    .unwrap_or_default(),
+            )],
+            ComponentType::Piston | ComponentType::StickyPiston => vec![Property::bool(
+                "Sticky",
+                self.component_type == ComponentType::StickyPiston,
+            )],
+            _ => Vec::new(),
+        }
+    }
+    fn apply_property(
+        &self,
+        component: Component,
+        name: &str,
+        value: &PropertyValue,
+    ) -> Option<Component> {
+        let component_type = match (self.component_type, name, value) {
+            (ComponentType::Repeater(_), "Delay", PropertyValue::Integer(delay)) => {
+                ComponentType::Repeater(u8::try_from(*delay).ok()?)
+            }
+            (ComponentType::Comparator(_), "Mode", PropertyValue::Enum(index)) => {
+                ComponentType::Comparator(*ComparatorMode::ALL.get(*index)?)
+            }
+            (ComponentType::Button(_), "Material", PropertyValue::Enum(index)) => {
+                ComponentType::Button(*ButtonType::ALL.get(*index)?)
+            }
+            (
+                ComponentType::Piston | ComponentType::StickyPiston,
+                "Sticky",
+                PropertyValue::Bool(sticky),
+            ) => {
+                if *sticky {
+                    ComponentType::StickyPiston
+                } else {
+                    ComponentType::Piston
+                }
+            }
+            _ => return None,
+        };
+        Some(component.with_component_type(component_type))
+    }
+}
+
+impl CanvasContextRenderer for MinecraftComponent {
+    fn render_at_position(
+        &self,
+        ctx: &web_sys::CanvasRenderingContext2d,
+        position: (f64, f64),
+    ) -> Result<(), JsValue> {
+        ctx.draw_image_with_html_image_element(&self.image.image, position.0, position.1)
+    }
+}
+
+impl IntoCanvasElement for MinecraftComponent {
+    fn into_canvas_element(self: Box<Self>) -> CanvasElement {
+        let connection_points = self.get_connection_points();
+        let component_type = self.get_component_type();
+        let timing = self.get_timing();
+        let width = Self::get_width(component_type);
+        CanvasElement::new(self, component_type, HEIGHT, width, connection_points)
+            .with_timing(timing)
+    }
+}
@@ -1,2 +1,6 @@
+pub mod bus_splitter;
 pub mod logic_gate;
+pub mod minecraft_component;
 pub mod pin;
+pub mod sequential_block;
+pub mod subcircuit_block;
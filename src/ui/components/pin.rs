@@ -2,29 +2,65 @@ use stylist::style;
 use wasm_bindgen::JsValue;
 use yew::html;
 
-use crate::ui::{
-    canvas::{CanvasContextRenderer, CanvasElement, CanvasSVGImage, IntoCanvasElement},
-    connection_point::ConnectionPoint,
-    console_option::ConsoleOption,
-    redstone_component::RedstoneComponent,
+use crate::{
+    circuit::{Component, PinDirection, Property, PropertyValue, Timing, MAX_BUS_WIDTH},
+    ui::{
+        canvas::{CanvasContextRenderer, CanvasElement, CanvasSVGImage, IntoCanvasElement},
+        connection_point::ConnectionPoint,
+        console_option::ConsoleOption,
+        redstone_component::{ComponentType, RedstoneComponent},
+    },
 };
 
+const DIRECTION_NAMES: &[&str] = &["Input", "Output", "Bidirectional"];
+
+/// A named port of the circuit, drawn as a circle for bidirectional pins, an arrow for input pins and a ring for
+/// output pins. Buses are marked with a slash and their width.
 #[derive(Debug, Clone)]
 pub struct Pin {
+    direction: PinDirection,
+    width: u8,
+    label: String,
     image: CanvasSVGImage,
 }
 
 impl Pin {
-    pub fn new() -> Result<Self, JsValue> {
-        let image = CanvasSVGImage::new(Pin::get_svg_string())?;
-        Ok(Self { image })
+    pub fn new(direction: PinDirection, width: u8, label: &str) -> Result<Self, JsValue> {
+        let image = CanvasSVGImage::new(Pin::get_svg_string(direction, width))?;
+        Ok(Self {
+            direction,
+            width,
+            label: label.to_owned(),
+            image,
+        })
     }
 
-    fn get_svg_string() -> String {
+    fn get_svg_string(direction: PinDirection, width: u8) -> String {
+        let head = match direction {
+            PinDirection::Input => {
+                r#"<path style="fill: black; stroke: rgb(0, 0, 0); stroke-width: 2px;" d="M1,19 L11,25 L1,31 Z"></path>"#
+            }
+            PinDirection::Output => {
+                r#"<circle cx="6" cy="25" r="5" style="fill: white; stroke: rgb(0, 0, 0); stroke-width: 2px;"></circle>"#
+            }
+            PinDirection::Bidirectional => {
+                r#"<path style="fill: black; stroke: rgb(0, 0, 0); stroke-width: 2px;" d="M1,25 A5,5,0,0,0,11,25 A5,5,0,0,0,0,25"></path>"#
+            }
+        };
+        let bus = if width > 1 {
+            format!(
+                r#"<path style="stroke: rgb(0, 0, 0); stroke-width: 1px;" d="M15,29 L21,21"></path>
+                <text x="18" y="40" text-anchor="middle" style="font-family: Arial; font-size: 9px;">{width}</text>"#
+            )
+        } else {
+            String::new()
+        };
         format!(
             r#"
         <svg width="25" height="50" xmlns="http://www.w3.org/2000/svg">
-            <path style="fill: black; stroke: rgb(0, 0, 0); stroke-width: 2px;" d="M1,25 A5,5,0,0,0,11,25 A5,5,0,0,0,0,25 H25"></path>
+            {head}
+            <path style="stroke: rgb(0, 0, 0); stroke-width: 2px;" d="M11,25 H25"></path>
+            {bus}
         </svg>
         "#
         )
@@ -32,18 +68,23 @@ impl Pin {
 }
 
 impl RedstoneComponent for Pin {
-    fn get_component_type(&self) -> crate::ui::redstone_component::ComponentType {
-        crate::ui::redstone_component::ComponentType::Pin
+    fn get_component_type(&self) -> ComponentType {
+        ComponentType::Pin(self.direction, self.width)
     }
     fn get_component_list_item_title(&self) -> String {
-        "Pin".into()
+        format!("{}", self.get_component_type())
+    }
+    fn get_timing(&self) -> Timing {
+        Timing::INSTANT
     }
-    fn get_connection_points(&self) -> Vec<crate::ui::connection_point::ConnectionPoint> {
+    fn get_connection_points(&self) -> Vec<ConnectionPoint> {
         vec![ConnectionPoint::new(
             25.0,
-            0.0,
+            25.0,
             [false, true, false, false],
-        )]
+            self.direction.get_connection_kind(),
+        )
+        .with_width(self.width)]
     }
     fn get_component_list_item_icon(&self) -> yew::Html {
         let style_image = style!(
@@ -57,6 +98,36 @@ impl RedstoneComponent for Pin {
             <img class={ style_image } src={ self.image.get_url() } />
         } //TODO
     }
+    fn get_properties(&self) -> Vec<Property> {
+        let direction = PinDirection::ALL
+            .iter()
+            .position(|d| *d == self.direction)
+            .unwrap_or_default();
+        vec![
+            Property::text("Name", &self.label),
+            Property::choice("Direction", DIRECTION_NAMES, direction),
+            Property::integer("Width", 1..=u32::from(MAX_BUS_WIDTH), u32::from(self.width)),
+        ]
+    }
+    fn apply_property(
+        &self,
+        component: Component,
+        name: &str,
+        value: &PropertyValue,
+    ) -> Option<Component> {
+        match (name, value) {
+            ("Name", PropertyValue::Text(label)) => {
+                Some(component.with_label(label.trim().to_owned()))
+            }
+            ("Direction", PropertyValue::Enum(direction)) => Some(component.with_component_type(
+                ComponentType::Pin(*PinDirection::ALL.get(*direction)?, self.width),
+            )),
+            ("Width", PropertyValue::Integer(width)) => Some(component.with_component_type(
+                ComponentType::Pin(self.direction, u8::try_from(*width).ok()?),
+            )),
+            _ => None,
+        }
+    }
 }
 
 impl CanvasContextRenderer for Pin {
@@ -65,13 +136,22 @@ impl CanvasContextRenderer for Pin {
         ctx: &web_sys::CanvasRenderingContext2d,
         position: (f64, f64),
     ) -> Result<(), JsValue> {
-        ctx.draw_image_with_html_image_element(&self.image.image, position.0, position.1 - 25.0)
+        ctx.draw_image_with_html_image_element(&self.image.image, position.0, position.1)?;
+        // the label is drawn above the pin, it may be wider than the pin
+        if !self.label.is_empty() {
+            ctx.set_fill_style(&JsValue::from_str("black"));
+            ctx.set_font("12px Arial");
+            ctx.fill_text(&self.label, position.0, position.1 + 14.0)?;
+        }
+        Ok(())
     }
 }
 
 impl IntoCanvasElement for Pin {
-    fn into_canvas_element(self, position: (f64, f64)) -> crate::ui::canvas::CanvasElement {
+    fn into_canvas_element(self: Box<Self>) -> CanvasElement {
         let connection_points = self.get_connection_points();
-        CanvasElement::new(Box::new(self), position, 50.0, 25.0, connection_points)
+        let component_type = self.get_component_type();
+        let timing = self.get_timing();
+        CanvasElement::new(self, component_type, 50.0, 25.0, connection_points).with_timing(timing)
     }
 }
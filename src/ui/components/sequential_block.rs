@@ -0,0 +1,235 @@
+use stylist::style;
+use wasm_bindgen::JsValue;
+use yew::html;
+
+use crate::{
+    circuit::{
+        Component, Property, PropertyValue, PulseBehaviour, SequentialType, Timing,
+        MAX_CLOCK_PERIOD, MAX_PULSE_LENGTH, MIN_CLOCK_PERIOD,
+    },
+    ui::{
+        canvas::{CanvasContextRenderer, CanvasElement, CanvasSVGImage, IntoCanvasElement},
+        connection_point::{ConnectionKind, ConnectionPoint},
+        console_option::ConsoleOption,
+        redstone_component::{ComponentType, RedstoneComponent},
+    },
+};
+
+const STROKE: &str = "fill: none; stroke: rgb(0, 0, 0); stroke-width: 2px;";
+const TEXT: &str = "font-family: Arial; font-size: 16px;";
+const WIDTH: f64 = 100.0;
+/// Height the block grows by with every input
+const PORT_SPACING: f64 = 50.0;
+const SEQUENTIAL_NAMES: &[&str] = &[
+    "SR Latch",
+    "D Latch",
+    "D Flip-Flop",
+    "T Flip-Flop",
+    "JK Flip-Flop",
+];
+
+#[derive(Clone)]
+/// A latch, flip-flop, clock or monostable, drawn as a box with its inputs on the left and its outputs on the right
+pub struct SequentialBlock {
+    component_type: ComponentType,
+    image: CanvasSVGImage,
+}
+
+impl SequentialBlock {
+    pub fn new(component_type: ComponentType) -> Result<Self, JsValue> {
+        let image = CanvasSVGImage::new(Self::get_svg_string(component_type))?;
+        Ok(Self {
+            component_type,
+            image,
+        })
+    }
+
+    /// Returns the labels of the inputs, the clock is labelled `>`
+    const fn get_inputs(component_type: ComponentType) -> &'static [&'static str] {
+        match component_type {
+            ComponentType::Sequential(sequential_type) => sequential_type.get_inputs(),
+            ComponentType::Monostable(_) => &[""],
+            _ => &[],
+        }
+    }
+    /// Returns the labels of the outputs, flip-flops output their value and its inverse
+    const fn get_outputs(component_type: ComponentType) -> &'static [&'static str] {
+        match component_type {
+            ComponentType::Sequential(_) => &["Q", "Q&#772;"],
+            _ => &[""],
+        }
+    }
+    #[allow(clippy::cast_precision_loss)]
+    fn get_height(component_type: ComponentType) -> f64 {
+        let ports = Self::get_inputs(component_type)
+            .len()
+            .max(Self::get_outputs(component_type).len());
+        ports as f64 * PORT_SPACING
+    }
+    #[allow(clippy::cast_precision_loss)]
+    fn get_port_y(port: usize) -> f64 {
+        (port as f64).mul_add(PORT_SPACING, PORT_SPACING / 2.0)
+    }
+
+    fn get_svg_string(component_type: ComponentType) -> String {
+        let height = Self::get_height(component_type);
+        let mut path = format!("M25,1 H75 V{} H25 Z ", height - 1.0);
+        let mut labels = String::new();
+        for (input, label) in Self::get_inputs(component_type).iter().enumerate() {
+            let y = Self::get_port_y(input);
+            path.push_str(&format!("M0,{y} H25 "));
+            if *label == ">" {
+                path.push_str(&format!("M25,{} L34,{y} L25,{} ", y - 7.0, y + 7.0));
+            } else {
+                labels.push_str(&format!(
+                    r#"<text x="29" y="{}" style="{TEXT}">{label}</text>"#,
+                    y + 6.0
+                ));
+            }
+        }
+        for (output, label) in Self::get_outputs(component_type).iter().enumerate() {
+            let y = Self::get_port_y(output);
+            path.push_str(&format!("M75,{y} H100 "));
+            labels.push_str(&format!(
+                r#"<text x="71" y="{}" text-anchor="end" style="{TEXT}">{label}</text>"#,
+                y + 6.0
+            ));
+        }
+        match component_type {
+            // a square wave
+            ComponentType::Clock(_) => path.push_str("M33,32 H41 V18 H50 V32 H59 V18 H67"),
+            // a single pulse
+            ComponentType::Monostable(_) => path.push_str("M33,32 H43 V18 H57 V32 H67"),
+            _ => {}
+        }
+        format!(
+            r#"
+            <svg width="{WIDTH}" height="{height}" xmlns="http://www.w3.org/2000/svg">
+                <path style="{STROKE}" d="{path}"></path>
+                {labels}
+            </svg>"#
+        )
+    }
+}
+
+impl RedstoneComponent for SequentialBlock {
+    fn get_connection_points(&self) -> Vec<ConnectionPoint> {
+        let inputs = Self::get_inputs(self.component_type).len();
+        let last = inputs.saturating_sub(1);
+        let mut points: Vec<_> = (0..inputs)
+            .map(|input| {
+                // the outer inputs can also be reached from above and below
+                let directions = [input == 0, false, input == last, true];
+                ConnectionPoint::new(
+                    0.0,
+                    Self::get_port_y(input),
+                    directions,
+                    ConnectionKind::Input,
+                )
+            })
+            .collect();
+        let outputs = Self::get_outputs(self.component_type).len();
+        points.extend((0..outputs).map(|output| {
+            let directions = [output == 0, true, output == outputs - 1, false];
+            ConnectionPoint::new(
+                WIDTH,
+                Self::get_port_y(output),
+                directions,
+                ConnectionKind::Output,
+            )
+        }));
+        points
+    }
+    fn get_component_type(&self) -> ComponentType {
+        self.component_type
+    }
+    fn get_component_list_item_title(&self) -> String {
+        format!("{}", self.component_type)
+    }
+    fn get_timing(&self) -> Timing {
+        match self.component_type {
+            ComponentType::Sequential(sequential_type) => {
+                Timing::new(sequential_type.get_delay(), PulseBehaviour::Transport)
+            }
+            // the pulse is cut by a comparator
+            ComponentType::Monostable(_) => Timing::comparator(),
+            _ => Timing::INSTANT,
+        }
+    }
+    fn get_component_list_item_icon(&self) -> yew::Html {
+        let style_image = style!(
+            r#"
+            align-self: center;
+            height: 50px;
+        "#
+        )
+        .unwrap_to_console();
+        html! {
+            <img class={ style_image } src={ self.image.get_url() } />
+        }
+    }
+    fn get_properties(&self) -> Vec<Property> {
+        match self.component_type {
+            ComponentType::Sequential(sequential_type) => {
+                let index = SequentialType::ALL
+                    .iter()
+                    .position(|s| *s == sequential_type)
+                    .unwrap_or_default();
+                vec![Property::choice("Type", SEQUENTIAL_NAMES, index)]
+            }
+            ComponentType::Clock(period) => vec![Property::integer(
+                "Period",
+                u32::from(MIN_CLOCK_PERIOD)..=u32::from(MAX_CLOCK_PERIOD),
+                u32::from(period),
+            )],
+            ComponentType::Monostable(length) => vec![Property::integer(
+                "Pulse length",
+                1..=u32::from(MAX_PULSE_LENGTH),
+                u32::from(length),
+            )],
+            _ => Vec::new(),
+        }
+    }
+    fn apply_property(
+        &self,
+        component: Component,
+        name: &str,
+        value: &PropertyValue,
+    ) -> Option<Component> {
+        let component_type = match (self.component_type, name, value) {
+            (ComponentType::Sequential(_), "Type", PropertyValue::Enum(index)) => {
+                ComponentType::Sequential(*SequentialType::ALL.get(*index)?)
+            }
+            // clocks are on for half of their period
+            (ComponentType::Clock(_), "Period", PropertyValue::Integer(period)) => {
+                ComponentType::Clock(u8::try_from(*period).ok()?.max(MIN_CLOCK_PERIOD) & !1)
+            }
+            (ComponentType::Monostable(_), "Pulse length", PropertyValue::Integer(length)) => {
+                ComponentType::Monostable(u8::try_from(*length).ok()?)
+            }
+            _ => return None,
+        };
+        Some(component.with_component_type(component_type))
+    }
+}
+
+impl CanvasContextRenderer for SequentialBlock {
+    fn render_at_position(
+        &self,
+        ctx: &web_sys::CanvasRenderingContext2d,
+        position: (f64, f64),
+    ) -> Result<(), JsValue> {
+        ctx.draw_image_with_html_image_element(&self.image.image, position.0, position.1)
+    }
+}
+
+impl IntoCanvasElement for SequentialBlock {
+    fn into_canvas_element(self: Box<Self>) -> CanvasElement {
+        let connection_points = self.get_connection_points();
+        let component_type = self.get_component_type();
+        let timing = self.get_timing();
+        let height = Self::get_height(component_type);
+        CanvasElement::new(self, component_type, height, WIDTH, connection_points)
+            .with_timing(timing)
+    }
+}
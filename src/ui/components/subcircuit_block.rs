@@ -0,0 +1,178 @@
+use stylist::style;
+use wasm_bindgen::JsValue;
+use yew::html;
+
+use crate::{
+    circuit::{Subcircuit, SubcircuitId, Timing},
+    ui::{
+        canvas::{CanvasContextRenderer, CanvasElement, CanvasSVGImage, IntoCanvasElement},
+        connection_point::{ConnectionKind, ConnectionPoint},
+        console_option::ConsoleOption,
+        redstone_component::{ComponentType, RedstoneComponent},
+    },
+};
+
+const STROKE: &str = "fill: none; stroke: rgb(0, 0, 0); stroke-width: 2px;";
+const TEXT: &str = "font-family: Arial; font-size: 14px;";
+const PORT_TEXT: &str = "font-family: Arial; font-size: 10px;";
+const WIDTH: f64 = 100.0;
+/// Height the block grows by with every port on its longer side
+const PORT_SPACING: f64 = 25.0;
+
+#[derive(Clone)]
+/// An instance of a user-defined component, drawn as a box with its name, the input pins on the left and the output
+/// pins on the right, each named after its pin
+pub struct SubcircuitBlock {
+    id: SubcircuitId,
+    name: String,
+    /// The kind of every port in the order of the pins of the subcircuit
+    kinds: Vec<ConnectionKind>,
+    /// The number of bits of every port
+    widths: Vec<u8>,
+    image: CanvasSVGImage,
+}
+
+impl SubcircuitBlock {
+    pub fn new(id: SubcircuitId, subcircuit: &Subcircuit) -> Result<Self, JsValue> {
+        let name = subcircuit.get_name().to_owned();
+        let kinds = subcircuit.get_port_kinds();
+        let labels = subcircuit.get_port_labels();
+        let widths = subcircuit.get_port_widths();
+        let image = CanvasSVGImage::new(Self::get_svg_string(&name, &kinds, &labels))?;
+        Ok(Self {
+            id,
+            name,
+            kinds,
+            widths,
+            image,
+        })
+    }
+
+    /// Returns the position of every port along its side of the block, counted separately for inputs and outputs
+    fn get_port_rows(kinds: &[ConnectionKind]) -> Vec<usize> {
+        let (mut inputs, mut outputs) = (0, 0);
+        kinds
+            .iter()
+            .map(|kind| {
+                let side = if *kind == ConnectionKind::Output {
+                    &mut outputs
+                } else {
+                    &mut inputs
+                };
+                *side += 1;
+                *side - 1
+            })
+            .collect()
+    }
+    #[allow(clippy::cast_precision_loss)]
+    fn get_height(kinds: &[ConnectionKind]) -> f64 {
+        let outputs = kinds
+            .iter()
+            .filter(|k| **k == ConnectionKind::Output)
+            .count();
+        let rows = outputs.max(kinds.len() - outputs).max(2);
+        (rows + 1) as f64 * PORT_SPACING
+    }
+    #[allow(clippy::cast_precision_loss)]
+    fn get_port_y(row: usize) -> f64 {
+        (row + 1) as f64 * PORT_SPACING
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    fn get_svg_string(name: &str, kinds: &[ConnectionKind], labels: &[&str]) -> String {
+        let height = Self::get_height(kinds);
+        let name = Self::escape(name);
+        let mut path = format!("M20,1 H80 V{} H20 Z ", height - 1.0);
+        let mut port_labels = String::new();
+        for ((kind, row), label) in kinds.iter().zip(Self::get_port_rows(kinds)).zip(labels) {
+            let y = Self::get_port_y(row);
+            let (x, anchor) = if *kind == ConnectionKind::Output {
+                path.push_str(&format!("M80,{y} H100 "));
+                (77, "end")
+            } else {
+                path.push_str(&format!("M0,{y} H20 "));
+                (23, "start")
+            };
+            port_labels.push_str(&format!(
+                r#"<text x="{x}" y="{}" text-anchor="{anchor}" style="{PORT_TEXT}">{}</text>"#,
+                y + 3.0,
+                Self::escape(label)
+            ));
+        }
+        format!(
+            r#"
+            <svg width="{WIDTH}" height="{height}" xmlns="http://www.w3.org/2000/svg">
+                <path style="{STROKE}" d="{path}"></path>
+                {port_labels}
+                <text x="50" y="16" text-anchor="middle" style="{TEXT}">{name}</text>
+            </svg>"#
+        )
+    }
+}
+
+impl RedstoneComponent for SubcircuitBlock {
+    fn get_connection_points(&self) -> Vec<ConnectionPoint> {
+        self.kinds
+            .iter()
+            .zip(Self::get_port_rows(&self.kinds))
+            .zip(&self.widths)
+            .map(|((kind, row), width)| {
+                let y = Self::get_port_y(row);
+                if *kind == ConnectionKind::Output {
+                    ConnectionPoint::new(WIDTH, y, [false, true, false, false], *kind)
+                } else {
+                    ConnectionPoint::new(0.0, y, [false, false, false, true], *kind)
+                }
+                .with_width(*width)
+            })
+            .collect()
+    }
+    fn get_component_type(&self) -> ComponentType {
+        ComponentType::Subcircuit(self.id)
+    }
+    fn get_component_list_item_title(&self) -> String {
+        self.name.clone()
+    }
+    fn get_timing(&self) -> Timing {
+        // the components inside are simulated instead
+        Timing::INSTANT
+    }
+    fn get_component_list_item_icon(&self) -> yew::Html {
+        let style_image = style!(
+            r#"
+            align-self: center;
+            max-height: 50px;
+        "#
+        )
+        .unwrap_to_console();
+        html! {
+            <img class={ style_image } src={ self.image.get_url() } />
+        }
+    }
+}
+
+impl CanvasContextRenderer for SubcircuitBlock {
+    fn render_at_position(
+        &self,
+        ctx: &web_sys::CanvasRenderingContext2d,
+        position: (f64, f64),
+    ) -> Result<(), JsValue> {
+        ctx.draw_image_with_html_image_element(&self.image.image, position.0, position.1)
+    }
+}
+
+impl IntoCanvasElement for SubcircuitBlock {
+    fn into_canvas_element(self: Box<Self>) -> CanvasElement {
+        let connection_points = self.get_connection_points();
+        let component_type = self.get_component_type();
+        let timing = self.get_timing();
+        let height = Self::get_height(&self.kinds);
+        CanvasElement::new(self, component_type, height, WIDTH, connection_points)
+            .with_timing(timing)
+    }
+}
@@ -0,0 +1,72 @@
+use std::{cell::RefCell, rc::Rc};
+
+use gloo::{
+    file::{callbacks::FileReader, File},
+    utils::document,
+};
+use js_sys::{Array, Uint8Array};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{Blob, BlobPropertyBag, HtmlAnchorElement, HtmlInputElement, Url};
+use yew::Callback;
+
+/// Offers the content to the user as a download with the given file name
+pub fn download_text(file_name: &str, content: &str, mime_type: &str) -> Result<(), JsValue> {
+    download(
+        file_name,
+        &Array::of1(&JsValue::from_str(content)),
+        mime_type,
+    )
+}
+
+/// Offers the bytes to the user as a download with the given file name
+pub fn download_bytes(file_name: &str, content: &[u8], mime_type: &str) -> Result<(), JsValue> {
+    download(
+        file_name,
+        &Array::of1(&Uint8Array::from(content)),
+        mime_type,
+    )
+}
+
+fn download(file_name: &str, parts: &Array, mime_type: &str) -> Result<(), JsValue> {
+    let mut options = BlobPropertyBag::new();
+    options.type_(mime_type);
+    let blob = Blob::new_with_blob_sequence_and_options(parts, &options)?;
+    let url = Url::create_object_url_with_blob(&blob)?;
+    let anchor: HtmlAnchorElement = document().create_element("a")?.dyn_into()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    Url::revoke_object_url(&url)
+}
+
+/// Lets the user pick a file and emits its name and content once it has been read
+pub fn open_text(accept: &str, callback: Callback<(String, String)>) -> Result<(), JsValue> {
+    let input: HtmlInputElement = document().create_element("input")?.dyn_into()?;
+    input.set_type("file");
+    input.set_accept(accept);
+
+    // The reader is cancelled when dropped, so it is kept alive by the change handler
+    let reader: Rc<RefCell<Option<FileReader>>> = Rc::default();
+    let onchange = {
+        let input = input.clone();
+        Closure::<dyn FnMut(web_sys::Event)>::new(move |_| {
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            let file = File::from(file);
+            let file_name = file.name();
+            let callback = callback.clone();
+            *reader.borrow_mut() = Some(gloo::file::callbacks::read_as_text(
+                &file,
+                move |result| match result {
+                    Ok(content) => callback.emit((file_name, content)),
+                    Err(e) => log::error!("Failed to read {}: {:?}", file_name, e),
+                },
+            ));
+        })
+        .into_js_value()
+    };
+    input.add_event_listener_with_callback("change", onchange.unchecked_ref())?;
+    input.click();
+    Ok(())
+}
@@ -71,6 +71,14 @@ impl KeyboardInputHandler {
         let alt = event.alt_key();
         let shift = event.shift_key();
         let key = event.key().to_uppercase();
+        // typing into a text field is no shortcut
+        let editing = event
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::Element>().ok())
+            .is_some_and(|e| matches!(e.tag_name().as_str(), "INPUT" | "TEXTAREA"));
+        if editing {
+            return;
+        }
         let cmd = SHORTCUTS
             .iter()
             .find(|s| s.ctrl == ctrl && s.alt == alt && s.shift == shift && s.key == key)
@@ -112,11 +120,14 @@ const SHORTCUTS: &[KeyboardShortcut] = &[
     KeyboardShortcut::new(false, true, false, "F4", Command::Exit),
     KeyboardShortcut::new(true, false, false, "Z", Command::Undo),
     KeyboardShortcut::new(true, false, false, "Y", Command::Redo),
+    KeyboardShortcut::new(true, false, true, "Z", Command::Redo),
     KeyboardShortcut::new(true, false, false, "C", Command::Copy),
     KeyboardShortcut::new(true, false, false, "V", Command::Paste),
     KeyboardShortcut::new(true, false, false, "X", Command::Cut),
     KeyboardShortcut::new(false, false, false, "DELETE", Command::Delete),
     KeyboardShortcut::new(true, false, false, "+", Command::ZoomIn),
+    // Ctrl+= is Ctrl++ without shift on most keyboards
+    KeyboardShortcut::new(true, false, false, "=", Command::ZoomIn),
     KeyboardShortcut::new(true, false, false, "-", Command::ZoomOut),
     KeyboardShortcut::new(true, false, true, "F", Command::FitWindow),
     KeyboardShortcut::new(true, false, true, "G", Command::ToggleGrid),
@@ -0,0 +1,180 @@
+use stylist::style;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::{html, Callback, Component, Properties};
+
+use crate::export::{BlockLayout, RoutedLayout};
+
+use super::{
+    application::{CallbackReason, Command},
+    canvas::{Canvas, CanvasRenderer},
+    console_option::ConsoleOption,
+};
+
+/// Size of a block in the preview in pixels
+const BLOCK_PIXELS: i32 = 12;
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub callback: Callback<CallbackReason>,
+    pub routed: RoutedLayout,
+}
+
+pub enum LayoutPreviewMsg {
+    /// Shows the level above (1) or below (-1) the current one
+    ChangeLevel(i32),
+}
+
+/// Shows a placed and routed layout from above, one level of blocks at a time
+pub struct LayoutPreview {
+    level: i32,
+}
+
+impl Component for LayoutPreview {
+    type Message = LayoutPreviewMsg;
+    type Properties = Props;
+
+    fn create(ctx: &yew::Context<Self>) -> Self {
+        // start at the level of the logic, above the floor
+        let level = ctx
+            .props()
+            .routed
+            .get_layout()
+            .get_bounds()
+            .map_or(0, |(min, _)| min.1 + 1);
+        Self { level }
+    }
+
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            LayoutPreviewMsg::ChangeLevel(change) => {
+                let Some((min, max)) = ctx.props().routed.get_layout().get_bounds() else {
+                    return false;
+                };
+                self.level = (self.level + change).clamp(min.1, max.1);
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let style_overlay = style!(
+            r#"
+            position: fixed;
+            top: 50px;
+            left: 50px;
+            right: 50px;
+            bottom: 50px;
+            padding: 10px;
+            background: #f9f9f9;
+            border: 1px solid gray;
+            border-radius: 5px;
+            z-index: 3;
+            display: flex;
+            flex-direction: column;
+            gap: 10px;
+        "#
+        )
+        .unwrap_to_console();
+        let style_controls = style!(
+            r#"
+            display: flex;
+            gap: 10px;
+            align-items: center;
+        "#
+        )
+        .unwrap_to_console();
+        let style_view = style!(
+            r#"
+            overflow: auto;
+            flex: 1;
+            background: #fff;
+        "#
+        )
+        .unwrap_to_console();
+
+        let routed = &ctx.props().routed;
+        let (width, _, length) = routed.get_layout().get_size();
+        #[allow(clippy::cast_possible_wrap)]
+        let (width, height) = (width as i32 * BLOCK_PIXELS, length as i32 * BLOCK_PIXELS);
+        let renderer = LayerRenderer {
+            layout: routed.get_layout().clone(),
+            level: self.level,
+        };
+        let unrouted = routed.get_unrouted_nets().len();
+        let on_close = ctx
+            .props()
+            .callback
+            .reform(|_| CallbackReason::Command(Command::ClosePreview));
+
+        html! {
+            <div class={ style_overlay }>
+                <div class={ style_controls }>
+                    <button onclick={ ctx.link().callback(|_| LayoutPreviewMsg::ChangeLevel(-1)) }>{ "Level down" }</button>
+                    <span>{ format!("Level y = {}", self.level) }</span>
+                    <button onclick={ ctx.link().callback(|_| LayoutPreviewMsg::ChangeLevel(1)) }>{ "Level up" }</button>
+                    if unrouted > 0 {
+                        <span>{ format!("{unrouted} nets could not be routed") }</span>
+                    }
+                    <button onclick={ on_close }>{ "Close" }</button>
+                </div>
+                <div class={ style_view }>
+                    <Canvas<LayerRenderer> renderer={ Box::new(renderer) } { width } { height }>
+                    </Canvas<LayerRenderer>>
+                </div>
+            </div>
+        }
+    }
+}
+
+/// Draws one level of a layout from above, x to the right and z downwards
+#[derive(Clone, PartialEq)]
+struct LayerRenderer {
+    layout: BlockLayout,
+    level: i32,
+}
+
+impl LayerRenderer {
+    fn get_color(name: &str) -> &'static str {
+        match name {
+            "minecraft:redstone_wire" => "#c00000",
+            "minecraft:repeater" => "#808080",
+            "minecraft:redstone_wall_torch" => "#ff6000",
+            "minecraft:redstone_lamp" => "#e0c060",
+            "minecraft:lever" => "#806040",
+            "minecraft:oak_sign" => "#b8945f",
+            _ => "#c8c8c8",
+        }
+    }
+}
+
+impl CanvasRenderer for LayerRenderer {
+    fn render(&self, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+        let context: CanvasRenderingContext2d = canvas
+            .get_context("2d")?
+            .ok_or_else(JsValue::null)?
+            .dyn_into()?;
+        context.clear_rect(
+            0.0,
+            0.0,
+            f64::from(canvas.width()),
+            f64::from(canvas.height()),
+        );
+        let Some((min, _)) = self.layout.get_bounds() else {
+            return Ok(());
+        };
+        let pixels = f64::from(BLOCK_PIXELS);
+        for ((x, y, z), block) in self.layout.get_blocks() {
+            let (left, top) = (f64::from(x - min.0) * pixels, f64::from(z - min.2) * pixels);
+            if *y == self.level - 1 && block.get_name() != "minecraft:redstone_wire" {
+                // the blocks below are shown as the ground
+                context.set_fill_style(&JsValue::from_str("#eeeeee"));
+                context.fill_rect(left, top, pixels, pixels);
+            } else if *y == self.level {
+                context.set_fill_style(&JsValue::from_str(Self::get_color(block.get_name())));
+                context.fill_rect(left + 1.0, top + 1.0, pixels - 2.0, pixels - 2.0);
+            }
+        }
+        Ok(())
+    }
+}
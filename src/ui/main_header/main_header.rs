@@ -5,12 +5,19 @@ use stylist::style;
 use yew::prelude::*;
 use yew_icons::IconId;
 
-use crate::ui::{application::CallbackReason, bar::BarHorizontal};
+use crate::{
+    export::ExportFormat,
+    ui::{
+        application::{CallbackReason, Command, SimulationStatus},
+        bar::BarHorizontal,
+    },
+};
 
 use super::{
     header_menu::HeaderMenu,
     header_option::{self, HeaderOption},
     menu_option::MenuOption,
+    submenu::Submenu,
 };
 
 pub enum MainHeaderMsg {
@@ -25,6 +32,29 @@ pub struct MainHeaderProps {
     pub class: Classes,
 
     pub callback: Callback<CallbackReason>,
+    #[prop_or(SimulationStatus::Stopped)]
+    pub simulation_status: SimulationStatus,
+    #[prop_or_default]
+    pub can_undo: bool,
+    #[prop_or_default]
+    pub can_redo: bool,
+    #[prop_or_default]
+    pub can_delete: bool,
+    /// Whether there is a selection to copy and cut
+    #[prop_or_default]
+    pub can_copy: bool,
+    /// Whether there is a tool or a selection to rotate and mirror
+    #[prop_or_default]
+    pub can_orient: bool,
+    /// Whether a single pin is selected
+    #[prop_or_default]
+    pub can_rename_pin: bool,
+    /// Whether a single instance of a subcircuit is selected
+    #[prop_or_default]
+    pub can_edit_subcircuit: bool,
+    /// Whether a subcircuit is open for editing
+    #[prop_or_default]
+    pub editing_subcircuit: bool,
 }
 
 pub struct MainHeader {
@@ -97,6 +127,19 @@ impl Component for MainHeader {
 
         let onclick = ctx.link().callback(MainHeaderMsg::Clicked);
         let onhover = ctx.link().callback(MainHeaderMsg::Hovered);
+        let simulation_stopped = ctx.props().simulation_status == SimulationStatus::Stopped;
+        let simulation_paused = ctx.props().simulation_status == SimulationStatus::Paused;
+        let MainHeaderProps {
+            can_undo,
+            can_redo,
+            can_delete,
+            can_copy,
+            can_orient,
+            can_rename_pin,
+            can_edit_subcircuit,
+            editing_subcircuit,
+            ..
+        } = *ctx.props();
 
         html! {
             <div class={ classes }>
@@ -111,13 +154,17 @@ impl Component for MainHeader {
                     <HeaderOption typ={ header_option::HeaderOptionType::File } onclick={ onclick.clone() } onhover={ onhover.clone() } active_header={ self.header_active } />
                         <HeaderMenu width="200px" position_x="0px" active={ self.is_header_active(header_option::HeaderOptionType::File) }>
                             <MenuOption text="New" shortcut="Ctrl+N"/>
-                            <MenuOption text="Open" shortcut="Ctrl+O"/>
+                            <MenuOption text="Open" shortcut="Ctrl+O" callback={ Self::on_command(ctx, Command::Open, true) }/>
                             <BarHorizontal />
-                            <MenuOption text="Save" shortcut="Ctrl+S"/>
-                            <MenuOption text="Save As" shortcut=""/>
+                            <MenuOption text="Save" shortcut="Ctrl+S" callback={ Self::on_command(ctx, Command::Save, true) }/>
+                            <MenuOption text="Save As" shortcut="" callback={ Self::on_command(ctx, Command::_SaveAs, true) }/>
                             <BarHorizontal />
                             <MenuOption text="Import" shortcut=""/>
-                            <MenuOption text="Export" shortcut=""/>
+                            <Submenu text="Export">
+                                { for ExportFormat::ALL.iter().map(|format| html! {
+                                    <MenuOption text={ format.get_name() } callback={ Self::on_command(ctx, Command::Export(*format), true) }/>
+                                }) }
+                            </Submenu>
                             <BarHorizontal />
                             <MenuOption text="Print" shortcut="Ctrl+P"/>
                             <BarHorizontal />
@@ -127,39 +174,49 @@ impl Component for MainHeader {
 
                     <HeaderOption typ={ header_option::HeaderOptionType::Edit } onclick={ onclick.clone() } onhover={ onhover.clone() } active_header={ self.header_active } />
                     <HeaderMenu width="200px" position_x="52px" active={ self.is_header_active(header_option::HeaderOptionType::Edit) }>
-                        <MenuOption text="Undo" shortcut="Ctrl+Z" />
-                        <MenuOption text="Redo" shortcut="Ctrl+Y" />
+                        <MenuOption text="Undo" shortcut="Ctrl+Z" disabled={ !can_undo } callback={ Self::on_command(ctx, Command::Undo, can_undo) }/>
+                        <MenuOption text="Redo" shortcut="Ctrl+Y" disabled={ !can_redo } callback={ Self::on_command(ctx, Command::Redo, can_redo) }/>
+                        <MenuOption text="History Depth" callback={ Self::on_command(ctx, Command::SetHistoryDepth, true) }/>
                         <BarHorizontal />
-                        <MenuOption text="Copy" shortcut="Ctrl+C" />
-                        <MenuOption text="Cut" shortcut="Ctrl+X" />
-                        <MenuOption text="Pase" shortcut="Ctrl+V" />
+                        <MenuOption text="Copy" shortcut="Ctrl+C" disabled={ !can_copy } callback={ Self::on_command(ctx, Command::Copy, can_copy) }/>
+                        <MenuOption text="Cut" shortcut="Ctrl+X" disabled={ !can_copy } callback={ Self::on_command(ctx, Command::Cut, can_copy) }/>
+                        <MenuOption text="Paste" shortcut="Ctrl+V" disabled={ !simulation_stopped } callback={ Self::on_command(ctx, Command::Paste, simulation_stopped) }/>
                         <BarHorizontal />
-                        <MenuOption text="Delete" shortcut="Del" />
+                        <MenuOption text="Delete" shortcut="Del" disabled={ !can_delete } callback={ Self::on_command(ctx, Command::Delete, can_delete) }/>
                     </HeaderMenu>
 
                     <HeaderOption typ={ header_option::HeaderOptionType::View } onclick={ onclick.clone() } onhover={ onhover.clone() } active_header={ self.header_active } />
                     <HeaderMenu width="200px" position_x="95px" active={ self.is_header_active(header_option::HeaderOptionType::View) }>
-                        <MenuOption text="Zoom In" />
-                        <MenuOption text="Zoom Out" />
-                        <MenuOption text="Fit Window" />
-                        <MenuOption text="Show/Hide Grid" />
+                        <MenuOption text="Zoom In" shortcut="Ctrl++" callback={ Self::on_command(ctx, Command::ZoomIn, true) }/>
+                        <MenuOption text="Zoom Out" shortcut="Ctrl+-" callback={ Self::on_command(ctx, Command::ZoomOut, true) }/>
+                        <MenuOption text="Fit Window" shortcut="Ctrl+Shift+F" callback={ Self::on_command(ctx, Command::FitWindow, true) }/>
+                        <MenuOption text="Show/Hide Grid" shortcut="Ctrl+Shift+G" callback={ Self::on_command(ctx, Command::ToggleGrid, true) }/>
+                        <MenuOption text="Grid Spacing" callback={ Self::on_command(ctx, Command::SetGridSpacing, true) }/>
                     </HeaderMenu>
 
                     <HeaderOption typ={ header_option::HeaderOptionType::Tools } onclick={ onclick.clone() } onhover={ onhover.clone() } active_header={ self.header_active } />
                     <HeaderMenu width="250px" position_x="146px" active={ self.is_header_active(header_option::HeaderOptionType::Tools) }>
-                        <MenuOption icon_id={ IconId::LucideRotateCcw } text="Rotate Left" shortcut="Ctrl+R"/>
-                        <MenuOption text="Rotate Right" shortcut="Ctrl+Shift+R"/>
-                        <MenuOption text="Mirror Vertical" shortcut="Ctrl+M"/>
-                        <MenuOption text="Mirror Horizontal" shortcut="Ctrl+Shift+M"/>
+                        <MenuOption icon_id={ IconId::LucideRotateCcw } text="Rotate Left" shortcut="R" disabled={ !can_orient } callback={ Self::on_command(ctx, Command::RotateLeft, can_orient) }/>
+                        <MenuOption text="Rotate Right" shortcut="Shift+R" disabled={ !can_orient } callback={ Self::on_command(ctx, Command::RotateRight, can_orient) }/>
+                        <MenuOption text="Mirror Vertical" shortcut="Ctrl+M" disabled={ !can_orient } callback={ Self::on_command(ctx, Command::MirrorVertical, can_orient) }/>
+                        <MenuOption text="Mirror Horizontal" shortcut="Ctrl+Shift+M" disabled={ !can_orient } callback={ Self::on_command(ctx, Command::MirrorHorizontal, can_orient) }/>
+                        <BarHorizontal />
+                        <MenuOption text="Make Connections" shortcut="Shift+C" disabled={ !simulation_stopped } callback={ Self::on_command(ctx, Command::MakeConnections, simulation_stopped) }/>
+                        <MenuOption text="Add Text" shortcut="Ctrl+T" disabled={ !simulation_stopped } callback={ Self::on_command(ctx, Command::PlaceText, simulation_stopped) }/>
+                        <MenuOption text="Preview Redstone Layout" callback={ Self::on_command(ctx, Command::PreviewLayout, true) }/>
+                        <MenuOption text="Check Redstone Layout" callback={ Self::on_command(ctx, Command::CheckLayout, true) }/>
+                        <MenuOption text="Rename Pin" disabled={ !can_rename_pin } callback={ Self::on_command(ctx, Command::RenamePin, can_rename_pin) }/>
                         <BarHorizontal />
-                        <MenuOption text="Make Connections"/>
+                        <MenuOption text="Create Subcircuit" disabled={ !can_copy } callback={ Self::on_command(ctx, Command::CreateSubcircuit, can_copy) }/>
+                        <MenuOption text="Edit Subcircuit" disabled={ !can_edit_subcircuit } callback={ Self::on_command(ctx, Command::EditSubcircuit, can_edit_subcircuit) }/>
+                        <MenuOption text="Close Subcircuit" disabled={ !editing_subcircuit } callback={ Self::on_command(ctx, Command::CloseSubcircuit, editing_subcircuit) }/>
                         <BarHorizontal />
-                        <MenuOption text="Start Simulation" />
-                        <MenuOption text="Run Simulation" disabled=false />
-                        <MenuOption text="Stop Simulation" disabled=false />
-                        <MenuOption text="Step Simulation" disabled=false />
-                        <MenuOption text="Restart Simulation" disabled=false />
-                        <MenuOption text="Pause At Change" disabled=false />
+                        <MenuOption text="Start Simulation" disabled={ !simulation_stopped } callback={ Self::on_command(ctx, Command::_StartSimulation, simulation_stopped) } />
+                        <MenuOption text="Run Simulation" disabled={ simulation_stopped } callback={ Self::on_command(ctx, Command::_RunSimulaiton, !simulation_stopped) } />
+                        <MenuOption text="Stop Simulation" disabled={ simulation_stopped } callback={ Self::on_command(ctx, Command::_StopSimulation, !simulation_stopped) } />
+                        <MenuOption text="Step Simulation" disabled={ !simulation_paused } callback={ Self::on_command(ctx, Command::_StepSimulaiton, simulation_paused) } />
+                        <MenuOption text="Restart Simulation" disabled={ simulation_stopped } callback={ Self::on_command(ctx, Command::_RestartSimulation, !simulation_stopped) } />
+                        <MenuOption text="Pause At Change" disabled={ !simulation_paused } callback={ Self::on_command(ctx, Command::_PauseAtChangeSimulation, simulation_paused) } />
                     </HeaderMenu>
 
                     <HeaderOption disabled=true typ={ header_option::HeaderOptionType::Options } onclick={ onclick.clone() } onhover={ onhover.clone() } active_header={ self.header_active } />
@@ -171,6 +228,24 @@ impl Component for MainHeader {
 }
 
 impl MainHeader {
+    /// Returns a callback for a menu option that closes the menu and issues the command.
+    /// Disabled options get a callback that does nothing.
+    fn on_command(
+        ctx: &yew::Context<Self>,
+        cmd: Command,
+        enabled: bool,
+    ) -> Option<Callback<MouseEvent>> {
+        if !enabled {
+            return Some(Callback::noop());
+        }
+        let callback = ctx.props().callback.clone();
+        let link = ctx.link().clone();
+        Some(Callback::from(move |_| {
+            link.send_message(MainHeaderMsg::Closed);
+            callback.emit(CallbackReason::Command(cmd));
+        }))
+    }
+
     fn is_header_active(&self, header: header_option::HeaderOptionType) -> bool {
         self.header_active.map_or(false, |h| h == header)
     }
@@ -1,18 +1,104 @@
-use yew::Component;
+use stylist::style;
+use yew::{html, Children, Component, Properties};
 
+use crate::ui::console_option::ConsoleOption;
 
+#[derive(Debug, Clone, PartialEq, Properties)]
+pub struct SubmenuProps {
+    pub text: &'static str,
+    pub children: Children,
+    #[prop_or("250px")]
+    pub width: &'static str,
+    #[prop_or(false)]
+    pub disabled: bool,
+}
+
+pub enum SubmenuMsg {
+    Hovered(bool),
+}
 
+/// A menu option that opens its children to the right while hovered
 pub struct Submenu {
+    open: bool,
 }
 
 impl Component for Submenu {
-    type Message = ();
-    type Properties = ();
+    type Message = SubmenuMsg;
+    type Properties = SubmenuProps;
     fn create(_ctx: &yew::Context<Self>) -> Self {
-        Self {  }
+        Self { open: false }
     }
-    fn view(&self, _ctx: &yew::Context<Self>) -> yew::Html {
-        todo!()
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            SubmenuMsg::Hovered(hovered) => {
+                let open = hovered && !ctx.props().disabled;
+                let changed = self.open != open;
+                self.open = open;
+                changed
+            }
+        }
     }
-    
-}
\ No newline at end of file
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let props = ctx.props();
+        let style_outer = style!(
+            r#"
+            position: relative;
+            padding: 0px 10px;
+            height: 25px;
+            display: flex;
+            flex-direction: column;
+            justify-content: center;
+
+            border-radius: 5px;
+            background: ${background};
+        "#,
+            background = if self.open { "dodgerblue" } else { "inherit" }
+        )
+        .unwrap_to_console();
+        let style_inner = style!(
+            r#"
+            display: grid;
+            grid-template-columns: 2fr 1fr;
+            color: ${txt_color};
+            user-select: none;
+        "#,
+            txt_color = if props.disabled { "gray" } else { "inherit" }
+        )
+        .unwrap_to_console();
+        let style_arrow = style!(
+            r#"
+            text-align: right;
+        "#
+        )
+        .unwrap_to_console();
+        let style_menu = style!(
+            r#"
+            width: ${w};
+            padding: 5px;
+            background: #f9f9f9;
+            border-radius: 5px;
+            position: absolute;
+            top: -5px;
+            left: 100%;
+            z-index: 3;
+        "#,
+            w = props.width
+        )
+        .unwrap_to_console();
+
+        let link = ctx.link();
+        html! {
+            <div class={ style_outer } onmouseenter={ link.callback(|_| SubmenuMsg::Hovered(true)) } onmouseleave={ link.callback(|_| SubmenuMsg::Hovered(false)) }>
+                <div class={ style_inner }>
+                    <span>{ props.text }</span>
+                    <span class={ style_arrow }>{ "\u{25B8}" }</span>
+                </div>
+                if self.open {
+                    <div class={ style_menu }>
+                        { props.children.clone() }
+                    </div>
+                }
+            </div>
+        }
+    }
+}
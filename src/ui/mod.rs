@@ -5,8 +5,13 @@ pub mod toolbar;
 pub mod workspace;
 pub mod bar;
 pub mod canvas;
+pub mod clipboard;
 pub mod connection_point;
 pub mod console_option;
+pub mod file_io;
 pub mod keybard_input_handler;
+pub mod layout_preview;
+pub mod problems_panel;
+pub mod property_inspector;
 pub mod redstone_component;
 pub mod components;
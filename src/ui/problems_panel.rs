@@ -0,0 +1,312 @@
+use stylist::style;
+use yew::{html, Callback, Classes, Component, Properties};
+
+use crate::circuit::{DesignRule, Problem, RuleScope, RuleSettings, Severity};
+
+use super::{
+    application::{CallbackReason, Command},
+    console_option::ConsoleOption,
+};
+
+/// Colors of the severities in the list
+const ERROR_COLOR: &str = "#d01010";
+const WARNING_COLOR: &str = "#c08000";
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    #[prop_or_default]
+    pub class: Classes,
+    pub callback: Callback<CallbackReason>,
+    /// The problems found in the circuit, errors first
+    #[prop_or_default]
+    pub problems: Vec<Problem>,
+    pub rules: RuleSettings,
+    /// The problem whose components are highlighted
+    #[prop_or_default]
+    pub highlighted: Option<Problem>,
+    /// Whether the problems of the redstone layout of the current circuit are listed
+    #[prop_or_default]
+    pub layout_checked: bool,
+}
+
+pub enum ProblemsPanelMsg {
+    /// Switches between the list of problems and the configuration of the rules
+    ToggleRules,
+    /// Lists only the problems of the severity, all if `None`
+    FilterSeverity(Option<Severity>),
+    /// Lists only the problems of the rules with the scope, all if `None`
+    FilterScope(Option<RuleScope>),
+}
+
+/// Lists the problems the design rules found and lets the user configure the rules of the project
+pub struct ProblemsPanel {
+    show_rules: bool,
+    severity: Option<Severity>,
+    scope: Option<RuleScope>,
+}
+
+impl ProblemsPanel {
+    const fn get_color(severity: Severity) -> &'static str {
+        match severity {
+            Severity::Error => ERROR_COLOR,
+            Severity::Warning => WARNING_COLOR,
+        }
+    }
+
+    fn view_filters(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let style_filters = style!(
+            r#"
+            display: flex;
+            flex-wrap: wrap;
+            gap: 5px;
+            padding: 0 7.5px 5px 7.5px;
+            border-bottom: 1px solid #eee;
+        "#
+        )
+        .unwrap_to_console();
+        let severities = [
+            ("All", None),
+            ("Errors", Some(Severity::Error)),
+            ("Warnings", Some(Severity::Warning)),
+        ];
+        let scopes = [None, Some(RuleScope::Circuit), Some(RuleScope::Layout)];
+        html! {
+            <div class={ style_filters }>
+                <span>
+                    { for severities.iter().map(|(name, severity)| {
+                        let severity = *severity;
+                        let onclick = ctx.link().callback(move |_| ProblemsPanelMsg::FilterSeverity(severity));
+                        html! {
+                            <button disabled={ self.severity == severity } { onclick }>{ *name }</button>
+                        }
+                    }) }
+                </span>
+                <span>
+                    { for scopes.iter().map(|scope| {
+                        let scope = *scope;
+                        let onclick = ctx.link().callback(move |_| ProblemsPanelMsg::FilterScope(scope));
+                        html! {
+                            <button disabled={ self.scope == scope } { onclick }>
+                                { scope.map_or_else(|| "All".to_owned(), |s| s.to_string()) }
+                            </button>
+                        }
+                    }) }
+                </span>
+            </div>
+        }
+    }
+
+    fn view_problems(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let style_problem = style!(
+            r#"
+            padding: 5px 7.5px;
+            border-bottom: 1px solid #eee;
+            cursor: pointer;
+            font-size: 13px;
+            &:hover {
+                background: #f0f0f0;
+            }
+        "#
+        )
+        .unwrap_to_console();
+        let style_highlighted = style!(
+            r#"
+            background: #e4eeff;
+        "#
+        )
+        .unwrap_to_console();
+        let style_hint = style!(
+            r#"
+            padding: 7.5px;
+            color: gray;
+        "#
+        )
+        .unwrap_to_console();
+        let problems: Vec<_> = ctx
+            .props()
+            .problems
+            .iter()
+            .filter(|p| self.severity.is_none_or(|s| p.get_severity() == s))
+            .filter(|p| self.scope.is_none_or(|s| p.get_rule().get_scope() == s))
+            .collect();
+        // the layout is only checked on request
+        let unchecked = !ctx.props().layout_checked && self.scope != Some(RuleScope::Circuit);
+        html! {
+            <>
+                if problems.is_empty() {
+                    <div class={ style_hint.clone() }>{ "No problems found" }</div>
+                }
+                { for problems.into_iter().map(|problem| {
+                    let mut classes = Classes::from(style_problem.clone());
+                    if ctx.props().highlighted.as_ref() == Some(problem) {
+                        classes.push(style_highlighted.clone());
+                    }
+                    let severity = problem.get_severity();
+                    let picked = problem.clone();
+                    let onclick = ctx.props().callback.reform(move |_| CallbackReason::ShowProblem(picked.clone()));
+                    html! {
+                        <div class={ classes } title={ problem.get_rule().to_string() } { onclick }>
+                            <b style={ format!("color: {};", Self::get_color(severity)) }>{ format!("{severity}: ") }</b>
+                            { problem.get_message() }
+                        </div>
+                    }
+                }) }
+                if unchecked {
+                    <div class={ style_hint }>{ "Check the layout to find the problems of its redstone" }</div>
+                }
+            </>
+        }
+    }
+
+    fn view_rules(ctx: &yew::Context<Self>) -> yew::Html {
+        let style_scope = style!(
+            r#"
+            padding: 7.5px 7.5px 2.5px 7.5px;
+            font-size: 13px;
+            font-weight: bold;
+        "#
+        )
+        .unwrap_to_console();
+        html! {
+            { for RuleScope::ALL.iter().map(|scope| html! {
+                <>
+                    <div class={ style_scope.clone() }>{ scope.to_string() }</div>
+                    { for DesignRule::ALL.iter().filter(|rule| rule.get_scope() == *scope).map(|rule| Self::view_rule(ctx, *rule)) }
+                </>
+            }) }
+        }
+    }
+
+    fn view_rule(ctx: &yew::Context<Self>, rule: DesignRule) -> yew::Html {
+        let style_rule = style!(
+            r#"
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+            flex-wrap: wrap;
+            gap: 5px;
+            padding: 5px 7.5px;
+            border-bottom: 1px solid #eee;
+            font-size: 13px;
+        "#
+        )
+        .unwrap_to_console();
+        let levels = [
+            ("Off", None),
+            ("Warning", Some(Severity::Warning)),
+            ("Error", Some(Severity::Error)),
+        ];
+        let current = ctx.props().rules.get_severity(rule);
+        html! {
+            <div class={ style_rule }>
+                { rule.to_string() }
+                <span>
+                    { for levels.iter().map(|(name, severity)| {
+                        let severity = *severity;
+                        let onclick = ctx.props().callback.reform(move |_| CallbackReason::SetRuleSeverity(rule, severity));
+                        html! {
+                            <button disabled={ current == severity } { onclick }>{ *name }</button>
+                        }
+                    }) }
+                </span>
+            </div>
+        }
+    }
+}
+
+impl Component for ProblemsPanel {
+    type Message = ProblemsPanelMsg;
+    type Properties = Props;
+
+    fn create(_ctx: &yew::Context<Self>) -> Self {
+        Self {
+            show_rules: false,
+            severity: None,
+            scope: None,
+        }
+    }
+
+    fn update(&mut self, _ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            ProblemsPanelMsg::ToggleRules => {
+                self.show_rules = !self.show_rules;
+                true
+            }
+            ProblemsPanelMsg::FilterSeverity(severity) => {
+                self.severity = severity;
+                true
+            }
+            ProblemsPanelMsg::FilterScope(scope) => {
+                self.scope = scope;
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let mut classes = ctx.props().class.clone();
+        let style_panel = style!(
+            r#"
+            display: flex;
+            flex-direction: column;
+            min-height: 0;
+            border-left: 1px solid gray;
+            border-top: 1px solid gray;
+            user-select: none;
+        "#
+        )
+        .unwrap_to_console();
+        classes.push(style_panel);
+        let style_heading = style!(
+            r#"
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+            padding: 10px 7.5px 5px 7.5px;
+            font-weight: bold;
+        "#
+        )
+        .unwrap_to_console();
+        let style_content = style!(
+            r#"
+            flex: 1;
+            overflow-y: auto;
+        "#
+        )
+        .unwrap_to_console();
+        let problems = &ctx.props().problems;
+        let errors = problems
+            .iter()
+            .filter(|p| p.get_severity() == Severity::Error)
+            .count();
+        let title = format!(
+            "Problems ({errors} errors, {} warnings)",
+            problems.len() - errors
+        );
+        let (toggle, filters, content) = if self.show_rules {
+            ("Problems", html! {}, Self::view_rules(ctx))
+        } else {
+            ("Rules", self.view_filters(ctx), self.view_problems(ctx))
+        };
+        let check_layout = ctx
+            .props()
+            .callback
+            .reform(|_| CallbackReason::Command(Command::CheckLayout));
+
+        html! {
+            <div class={ classes }>
+                <div class={ style_heading }>
+                    { title }
+                    <span>
+                        <button onclick={ check_layout } title="Check the redstone of the exported layout">{ "Check Layout" }</button>
+                        <button onclick={ ctx.link().callback(|_| ProblemsPanelMsg::ToggleRules) }>{ toggle }</button>
+                    </span>
+                </div>
+                { filters }
+                <div class={ style_content }>
+                    { content }
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use stylist::style;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlInputElement;
+use yew::{function_component, html, Callback, Classes, Event, Html, Properties};
+
+use crate::circuit::{ComponentId, Property, PropertyKind, PropertyValue};
+
+use super::{application::CallbackReason, console_option::ConsoleOption};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    #[prop_or_default]
+    pub class: Classes,
+    pub callback: Callback<CallbackReason>,
+    /// The only selected component, `None` if no or several components are selected
+    #[prop_or_default]
+    pub component: Option<ComponentId>,
+    /// The name of the type of the selected component
+    #[prop_or_default]
+    pub title: String,
+    #[prop_or_default]
+    pub properties: Vec<Property>,
+}
+
+/// Shows the properties of the selected component and lets the user edit them
+#[function_component]
+pub fn PropertyInspector(props: &Props) -> Html {
+    let mut classes = props.class.clone();
+    let style_inspector = style!(
+        r#"
+        height: 100%;
+        overflow-y: auto;
+        border-left: 1px solid gray;
+        user-select: none;
+    "#
+    )
+    .unwrap_to_console();
+    classes.push(style_inspector);
+    let style_heading = style!(
+        r#"
+        padding: 10px 7.5px 5px 7.5px;
+        font-weight: bold;
+    "#
+    )
+    .unwrap_to_console();
+    let style_property = style!(
+        r#"
+        display: flex;
+        justify-content: space-between;
+        align-items: center;
+        flex-wrap: wrap;
+        gap: 5px;
+        padding: 7.5px;
+        border-bottom: 1px solid #eee;
+    "#
+    )
+    .unwrap_to_console();
+    let style_hint = style!(
+        r#"
+        padding: 7.5px;
+        color: gray;
+    "#
+    )
+    .unwrap_to_console();
+
+    let Some(component) = props.component else {
+        return html! {
+            <div class={ classes }>
+                <div class={ style_heading }>{ "Properties" }</div>
+                <div class={ style_hint }>{ "Select a single component to edit its properties" }</div>
+            </div>
+        };
+    };
+    let properties = props.properties.iter().map(|property| {
+        let name = property.get_name().to_owned();
+        let callback = props.callback.clone();
+        let set = Callback::from(move |value: PropertyValue| {
+            callback.emit(CallbackReason::SetProperty {
+                component,
+                name: name.clone(),
+                value,
+            });
+        });
+        html! {
+            <label class={ style_property.clone() }>
+                { property.get_name() }
+                { property_input(property, &set) }
+            </label>
+        }
+    });
+
+    html! {
+        <div class={ classes }>
+            <div class={ style_heading }>{ &props.title }</div>
+            if props.properties.is_empty() {
+                <div class={ style_hint }>{ "The component has no properties" }</div>
+            }
+            { for properties }
+        </div>
+    }
+}
+
+/// Creates the input editing the property, `set` receives the new value
+fn property_input(property: &Property, set: &Callback<PropertyValue>) -> Html {
+    let input_of = |e: Event| e.target()?.dyn_into::<HtmlInputElement>().ok();
+    match (property.get_kind(), property.get_value()) {
+        (PropertyKind::Bool, PropertyValue::Bool(checked)) => {
+            let onchange = set.reform(move |e: Event| {
+                PropertyValue::Bool(input_of(e).is_some_and(|input| input.checked()))
+            });
+            html! {
+                <input type="checkbox" checked={ *checked } { onchange } />
+            }
+        }
+        (PropertyKind::Enum(options), PropertyValue::Enum(chosen)) => html! {
+            <span>
+                { for options.iter().enumerate().map(|(index, option)| html! {
+                    <button disabled={ index == *chosen } onclick={ set.reform(move |_| PropertyValue::Enum(index)) }>
+                        { *option }
+                    </button>
+                }) }
+            </span>
+        },
+        (PropertyKind::Integer(range), PropertyValue::Integer(value)) => {
+            let (min, max) = (*range.start(), *range.end());
+            // changes that aren't a number are ignored
+            let set = set.clone();
+            let onchange = Callback::from(move |e: Event| {
+                if let Some(value) = input_of(e).and_then(|input| input.value().parse::<u32>().ok())
+                {
+                    set.emit(PropertyValue::Integer(value.clamp(min, max)));
+                }
+            });
+            html! {
+                <input type="number" min={ min.to_string() } max={ max.to_string() } value={ value.to_string() } { onchange } />
+            }
+        }
+        (PropertyKind::Text, PropertyValue::Text(text)) => {
+            let set = set.clone();
+            let onchange = Callback::from(move |e: Event| {
+                if let Some(input) = input_of(e) {
+                    set.emit(PropertyValue::Text(input.value()));
+                }
+            });
+            html! {
+                <input type="text" value={ text.clone() } { onchange } />
+            }
+        }
+        // a value of the wrong kind is shown but can't be edited
+        (_, value) => html! {
+            <span>{ format!("{value:?}") }</span>
+        },
+    }
+}
@@ -1,10 +1,16 @@
 use wasm_bindgen::JsValue;
 use yew::Html;
 
-use crate::ui::{components::logic_gate::LogicGate};
+pub use crate::circuit::ComponentType;
+use crate::circuit::{Component, Property, PropertyValue, SubcircuitId, SubcircuitLibrary, Timing};
+use crate::ui::components::logic_gate::LogicGate;
 
 use super::{
-    canvas::IntoCanvasElement, components::{logic_gate::LogicGateType, pin::Pin},
+    canvas::{CanvasElement, IntoCanvasElement},
+    components::{
+        bus_splitter::BusSplitter, minecraft_component::MinecraftComponent, pin::Pin,
+        sequential_block::SequentialBlock, subcircuit_block::SubcircuitBlock,
+    },
     connection_point::ConnectionPoint,
 };
 
@@ -13,23 +19,100 @@ pub trait RedstoneComponent: IntoCanvasElement {
     fn get_component_type(&self) -> ComponentType;
     fn get_component_list_item_title(&self) -> String;
     fn get_component_list_item_icon(&self) -> Html;
-}
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-
-pub enum ComponentType {
-    LogicGate(LogicGateType),
-    Pin
+    /// The propagation delay and pulse handling of the component in the game
+    fn get_timing(&self) -> Timing;
+    /// The settings of the placed component that can be edited, with their current values
+    fn get_properties(&self) -> Vec<Property> {
+        Vec::new()
+    }
+    /// Returns the placed component with the named property set, `None` if the component has no such property.
+    /// The component has to be rebuilt afterwards to get its new image, size and ports.
+    fn apply_property(
+        &self,
+        _component: Component,
+        _name: &str,
+        _value: &PropertyValue,
+    ) -> Option<Component> {
+        None
+    }
 }
 
 impl ComponentType {
-    pub fn try_as_component(&self) -> Result<Box<dyn RedstoneComponent>, JsValue> {
+    /// Creates the component of this type, subcircuits are looked up in the library
+    pub fn try_as_component(
+        &self,
+        subcircuits: &SubcircuitLibrary,
+    ) -> Result<Box<dyn RedstoneComponent>, JsValue> {
         match self {
+            ComponentType::LogicGate(gate_type) => Ok(Box::new(LogicGate::new(*gate_type)?)),
+            ComponentType::Pin(direction, width) => Ok(Box::new(Pin::new(*direction, *width, "")?)),
+            ComponentType::Sequential(_)
+            | ComponentType::Clock(_)
+            | ComponentType::Monostable(_) => Ok(Box::new(SequentialBlock::new(*self)?)),
+            ComponentType::Splitter(_) | ComponentType::Merger(_) => {
+                Ok(Box::new(BusSplitter::new(*self)?))
+            }
+            ComponentType::Subcircuit(id) => {
+                Ok(Box::new(Self::get_subcircuit_block(*id, subcircuits)?))
+            }
+            other => Ok(Box::new(MinecraftComponent::new(*other)?)),
+        }
+    }
+    /// Creates the renderable element for this component type
+    pub fn try_as_canvas_element(
+        &self,
+        subcircuits: &SubcircuitLibrary,
+    ) -> Result<CanvasElement, JsValue> {
+        Ok(self.try_as_component(subcircuits)?.into_canvas_element())
+    }
+
+    fn get_subcircuit_block(
+        id: SubcircuitId,
+        subcircuits: &SubcircuitLibrary,
+    ) -> Result<SubcircuitBlock, JsValue> {
+        let subcircuit = subcircuits
+            .get(id)
+            .ok_or_else(|| JsValue::from_str(&format!("Subcircuit {id} does not exist")))?;
+        SubcircuitBlock::new(id, subcircuit)
+    }
+}
+
+impl Component {
+    /// Creates the renderable element of a placed component, including its configuration
+    pub fn try_as_canvas_element(
+        &self,
+        subcircuits: &SubcircuitLibrary,
+    ) -> Result<CanvasElement, JsValue> {
+        let element = self
+            .try_as_redstone_component(subcircuits)?
+            .into_canvas_element();
+        Ok(element
+            .with_orientation(self.get_orientation())
+            .with_label(self.get_label().to_owned()))
+    }
+    /// Creates the component of the placed component's type with its configuration, e.g. to edit its properties
+    pub fn try_as_redstone_component(
+        &self,
+        subcircuits: &SubcircuitLibrary,
+    ) -> Result<Box<dyn RedstoneComponent>, JsValue> {
+        match self.get_component_type() {
             ComponentType::LogicGate(gate_type) => {
-                Ok(Box::new(LogicGate::new(*gate_type)?))
+                Ok(Box::new(LogicGate::new_with_inverted_inputs(
+                    gate_type,
+                    self.get_inverted_inputs().to_vec(),
+                )?))
             }
-            ComponentType::Pin => {
-                Ok(Box::new(Pin::new()?))
+            ComponentType::Pin(direction, width) => {
+                Ok(Box::new(Pin::new(direction, width, self.get_label())?))
             }
+            other => other.try_as_component(subcircuits),
         }
     }
+    /// Recreates the component from the component library, keeping its type, position and configuration.
+    /// Used for components whose ports, size and timing are not known, e.g. when loading a project.
+    pub fn try_rebuild(&self, subcircuits: &SubcircuitLibrary) -> Result<Component, JsValue> {
+        Ok(self
+            .try_as_canvas_element(subcircuits)?
+            .to_component(self.get_position()))
+    }
 }
@@ -1,13 +1,32 @@
 use stylist::style;
-use yew::{html, html_nested, virtual_dom::VChild, Callback, Classes, Component, MouseEvent, Properties};
+use yew::{
+    html, html_nested, virtual_dom::VChild, Callback, Classes, Component, MouseEvent, Properties,
+};
 use yew_icons::{Icon, IconId};
 
-use super::{bar::BarVertical, application::CallbackReason};
+use super::{
+    application::{CallbackReason, Command, SimulationStatus},
+    bar::BarVertical,
+};
 #[derive(Debug, Clone, PartialEq, Properties)]
 pub struct Props {
     #[prop_or_default]
     pub class: Classes,
-    pub callback: Callback<CallbackReason>
+    pub callback: Callback<CallbackReason>,
+    #[prop_or(SimulationStatus::Stopped)]
+    pub simulation_status: SimulationStatus,
+    #[prop_or_default]
+    pub can_undo: bool,
+    #[prop_or_default]
+    pub can_redo: bool,
+    #[prop_or_default]
+    pub can_delete: bool,
+    /// Whether there is a selection to copy and cut
+    #[prop_or_default]
+    pub can_copy: bool,
+    /// Whether there is a tool or a selection to rotate and mirror
+    #[prop_or_default]
+    pub can_orient: bool,
 }
 
 pub struct Toolbar {}
@@ -21,6 +40,9 @@ impl Component for Toolbar {
     }
     fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
         let classes = ctx.props().class.clone();
+        let simulation_status = ctx.props().simulation_status;
+        let simulation_stopped = simulation_status == SimulationStatus::Stopped;
+        let simulation_paused = simulation_status == SimulationStatus::Paused;
         let style_toolbar = style!(
             r#"
             margin-left: 10px;
@@ -32,40 +54,43 @@ impl Component for Toolbar {
         html! {
             <div class={ classes }>
                 <div class={ style_toolbar}>
-                    { Self::get_icon_for_tool(IconId::LucideFilePlus2, "New Project", false) }
-                    { Self::get_icon_for_tool(IconId::LucideFolderOpen, "Open", false) }
-                    { Self::get_icon_for_tool(IconId::LucideFileInput, "Import", false) }
-                    { Self::get_icon_for_tool(IconId::LucideFileOutput, "Export", false) }
-                    { Self::get_icon_for_tool(IconId::LucideSave, "Save", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideFilePlus2, "New Project", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideFolderOpen, "Open", true) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideFileInput, "Import", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideFileOutput, "Export", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideSave, "Save", true) }
                     <BarVertical length="100%" thickness="2px" />
-                    { Self::get_icon_for_tool(IconId::LucideUndo2, "Undo", false) }
-                    { Self::get_icon_for_tool(IconId::LucideRedo2, "Redo", false) }
-                    { Self::get_icon_for_tool(IconId::LucideCopy, "Copy", false) }
-                    { Self::get_icon_for_tool(IconId::LucideScissors, "Cut", false) }
-                    { Self::get_icon_for_tool(IconId::LucideClipboardList, "Paste", false) }
-                    { Self::get_icon_for_tool(IconId::LucideTrash2, "Delete", false) }
-                    // { Self::get_icon_for_tool(IconId::LucideX, "Stop action", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideUndo2, "Undo", ctx.props().can_undo) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideRedo2, "Redo", ctx.props().can_redo) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideCopy, "Copy", ctx.props().can_copy) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideScissors, "Cut", ctx.props().can_copy) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideClipboardList, "Paste", simulation_stopped) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideTrash2, "Delete", ctx.props().can_delete) }
+                    // { Self::get_icon_for_tool(ctx, IconId::LucideX, "Stop action", false) }
                     <BarVertical length="100%" thickness="2px" />
-                    { Self::get_icon_for_tool(IconId::LucideZoomIn, "Zoom in", false) }
-                    { Self::get_icon_for_tool(IconId::LucideZoomOut, "Zoom out", false) }
-                    { Self::get_icon_for_tool(IconId::LucideCrop, "Fit on screen", false) }
-                    { Self::get_icon_for_tool(IconId::LucideGrid, "Toggle Grid", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideZoomIn, "Zoom in", true) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideZoomOut, "Zoom out", true) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideCrop, "Fit on screen", true) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideGrid, "Toggle Grid", true) }
                     <BarVertical length="100%" thickness="2px" />
-                    { Self::get_icon_for_tool(IconId::LucideRotateCcw, "Rotate left", false) }
-                    { Self::get_icon_for_tool(IconId::LucideRotateCw, "Rotate right", false) }
-                    { Self::get_icon_for_tool(IconId::LucideFlipHorizontal2, "Flip horizontal", false) }
-                    { Self::get_icon_for_tool(IconId::LucideFlipVertical2, "Flip vertical", false) }
-                    // { Self::get_icon_for_tool(IconId::LucideGitFork, "Connections", false) }
-                    { Self::get_icon_for_tool(IconId::LucideNetwork, "Connections", false) }
-                    { Self::get_icon_for_tool(IconId::LucideType, "Add Text", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideRotateCcw, "Rotate left", ctx.props().can_orient) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideRotateCw, "Rotate right", ctx.props().can_orient) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideFlipHorizontal2, "Flip horizontal", ctx.props().can_orient) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideFlipVertical2, "Flip vertical", ctx.props().can_orient) }
+                    // { Self::get_icon_for_tool(ctx, IconId::LucideGitFork, "Connections", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideNetwork, "Connections", simulation_stopped) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideType, "Add Text", simulation_stopped) }
                     <BarVertical length="100%" thickness="2px" />
-                    { Self::get_icon_for_tool(IconId::LucidePower, "Start Simulation", false) }
-                    { Self::get_icon_for_tool(IconId::LucidePlay, "Continue Simulation", false) }
-                    { Self::get_icon_for_tool(IconId::LucideStopCircle, "Stop Simulation", false) }
-                    // { Self::get_icon_for_tool(IconId::LucidePause, "Pause Simulation", false) }
-                    { Self::get_icon_for_tool(IconId::LucideSkipForward, "Step Simulation", false) }
-                    { Self::get_icon_for_tool(IconId::LucideRewind, "Restart Simulation", false) }
-                    { Self::get_icon_for_tool(IconId::LucideTimerReset, "Run Simulation until next change", false) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucidePower, "Start Simulation", simulation_stopped) }
+                    if simulation_status == SimulationStatus::Running {
+                        { Self::get_icon_for_tool(ctx, IconId::LucidePause, "Pause Simulation", true) }
+                    } else {
+                        { Self::get_icon_for_tool(ctx, IconId::LucidePlay, "Continue Simulation", simulation_paused) }
+                    }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideStopCircle, "Stop Simulation", !simulation_stopped) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideSkipForward, "Step Simulation", simulation_paused) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideRewind, "Restart Simulation", !simulation_stopped) }
+                    { Self::get_icon_for_tool(ctx, IconId::LucideTimerReset, "Run Simulation until next change", simulation_paused) }
                 </div>
             </div>
         }
@@ -73,7 +98,12 @@ impl Component for Toolbar {
 }
 
 impl Toolbar {
-    fn get_icon_for_tool(icon_id: IconId, title: &'static str, active: bool) -> VChild<Icon> {
+    fn get_icon_for_tool(
+        ctx: &yew::Context<Self>,
+        icon_id: IconId,
+        title: &'static str,
+        active: bool,
+    ) -> VChild<Icon> {
         let mut classes = Classes::with_capacity(2);
         let tool = style!(
             r#"
@@ -97,48 +127,56 @@ impl Toolbar {
         )
         .unwrap();
 
-        let onclick = Self::get_onclick_for_tool(icon_id);
+        let command = Self::get_command_for_tool(icon_id);
         classes.push(tool);
 
-        if onclick.is_none() || !active {
-            classes.push(deactivated);
-        }
+        let onclick: Option<Callback<MouseEvent>> = match command {
+            Some(cmd) if active => Some(
+                ctx.props()
+                    .callback
+                    .reform(move |_| CallbackReason::Command(cmd)),
+            ),
+            _ => {
+                classes.push(deactivated);
+                None
+            }
+        };
 
         html_nested! { <Icon class={ classes } { icon_id } { title } { onclick }/> }
     }
 
     #[allow(clippy::match_same_arms)]
-    ///This is a utility function providing the different onlick functions for the tool icons
-    const fn get_onclick_for_tool(icon_id: IconId) -> Option<Callback<MouseEvent>> {
+    ///This is a utility function providing the commands issued by the tool icons
+    const fn get_command_for_tool(icon_id: IconId) -> Option<Command> {
         match icon_id {
-            //Some(Callback::from(|_| {}))
             IconId::LucideFilePlus2 => None,
-            IconId::LucideFolderOpen => None,
-            IconId::LucideSave => None,
-            IconId::LucideUndo2 => None,
-            IconId::LucideRedo2 => None,
-            IconId::LucideCopy => None,
-            IconId::LucideScissors => None,
-            IconId::LucideClipboardList => None,
-            IconId::LucideTrash2 => None,
+            IconId::LucideFolderOpen => Some(Command::Open),
+            IconId::LucideSave => Some(Command::Save),
+            IconId::LucideUndo2 => Some(Command::Undo),
+            IconId::LucideRedo2 => Some(Command::Redo),
+            IconId::LucideCopy => Some(Command::Copy),
+            IconId::LucideScissors => Some(Command::Cut),
+            IconId::LucideClipboardList => Some(Command::Paste),
+            IconId::LucideTrash2 => Some(Command::Delete),
             IconId::LucideX => None,
-            IconId::LucideZoomIn => None,
-            IconId::LucideZoomOut => None,
-            IconId::LucideCrop => None,
-            IconId::LucideRotateCcw => None,
-            IconId::LucideRotateCw => None,
-            IconId::LucideFlipHorizontal2 => None,
-            IconId::LucideFlipVertical2 => None,
+            IconId::LucideZoomIn => Some(Command::ZoomIn),
+            IconId::LucideZoomOut => Some(Command::ZoomOut),
+            IconId::LucideCrop => Some(Command::FitWindow),
+            IconId::LucideGrid => Some(Command::ToggleGrid),
+            IconId::LucideRotateCcw => Some(Command::RotateLeft),
+            IconId::LucideRotateCw => Some(Command::RotateRight),
+            IconId::LucideFlipHorizontal2 => Some(Command::MirrorHorizontal),
+            IconId::LucideFlipVertical2 => Some(Command::MirrorVertical),
             // IconId::LucideGitFork => None,
-            IconId::LucideNetwork => None,
-            IconId::LucideType => None,
-            IconId::LucidePower => None,
-            IconId::LucidePlay => None,
-            IconId::LucideStopCircle => None,
-            IconId::LucidePause => None,
-            IconId::LucideSkipForward => None,
-            IconId::LucideRewind => None,
-            IconId::LucideTimerReset => None,
+            IconId::LucideNetwork => Some(Command::MakeConnections),
+            IconId::LucideType => Some(Command::PlaceText),
+            IconId::LucidePower => Some(Command::_StartSimulation),
+            IconId::LucidePlay => Some(Command::_RunSimulaiton),
+            IconId::LucideStopCircle => Some(Command::_StopSimulation),
+            IconId::LucidePause => Some(Command::_RunSimulaiton),
+            IconId::LucideSkipForward => Some(Command::_StepSimulaiton),
+            IconId::LucideRewind => Some(Command::_RestartSimulation),
+            IconId::LucideTimerReset => Some(Command::_PauseAtChangeSimulation),
             _ => None,
         }
     }
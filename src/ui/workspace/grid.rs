@@ -0,0 +1,116 @@
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+use super::{ViewTransform, GRID_SIZE};
+
+/// Number of grid cells between two major lines
+const MAJOR_LINE_EVERY: u32 = 4;
+/// Smallest distance between two drawn grid lines in pixels, closer minor lines are left out
+const MIN_LINE_SPACING: f64 = 6.0;
+const MINOR_LINE_COLOR: &str = "#f2f2f2";
+const MAJOR_LINE_COLOR: &str = "#dcdcdc";
+
+/// The grid of the workarea, components and the mouse snap to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridSettings {
+    visible: bool,
+    /// Distance between two grid lines in design coordinates
+    spacing: f64,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            visible: true,
+            spacing: GRID_SIZE,
+        }
+    }
+}
+
+impl GridSettings {
+    /// Creates the grid, an invalid spacing falls back to the default one
+    pub fn new(visible: bool, spacing: f64) -> Self {
+        let mut grid = Self {
+            visible,
+            ..Self::default()
+        };
+        grid.set_spacing(spacing);
+        grid
+    }
+    pub const fn is_visible(&self) -> bool {
+        self.visible
+    }
+    pub fn toggle_visible(&mut self) {
+        self.visible = !self.visible;
+    }
+    pub const fn get_spacing(&self) -> f64 {
+        self.spacing
+    }
+    /// Sets the distance between two grid lines, returns `false` if it is not a positive distance
+    pub fn set_spacing(&mut self, spacing: f64) -> bool {
+        if spacing.is_finite() && spacing > 0.0 {
+            self.spacing = spacing;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the grid point closest to the point
+    pub fn snap(&self, point: (f64, f64)) -> (f64, f64) {
+        (
+            (point.0 / self.spacing).round() * self.spacing,
+            (point.1 / self.spacing).round() * self.spacing,
+        )
+    }
+
+    /// Draws the lines of the grid visible on a canvas of the given size.
+    /// The context must already draw in world coordinates.
+    pub fn render(
+        &self,
+        context: &CanvasRenderingContext2d,
+        view: &ViewTransform,
+        size: (f64, f64),
+    ) {
+        if !self.visible {
+            return;
+        }
+        let scale = view.get_scale();
+        let (min, max) = (view.to_world((0.0, 0.0)), view.to_world(size));
+        let major = self.spacing * f64::from(MAJOR_LINE_EVERY);
+        context.save();
+        // one pixel wide at every zoom
+        context.set_line_width(1.0 / scale);
+        if self.spacing * scale >= MIN_LINE_SPACING {
+            Self::render_lines(context, self.spacing, min, max, MINOR_LINE_COLOR);
+        }
+        if major * scale >= MIN_LINE_SPACING {
+            Self::render_lines(context, major, min, max, MAJOR_LINE_COLOR);
+        }
+        context.restore();
+    }
+
+    fn render_lines(
+        context: &CanvasRenderingContext2d,
+        spacing: f64,
+        min: (f64, f64),
+        max: (f64, f64),
+        color: &str,
+    ) {
+        context.set_stroke_style(&JsValue::from_str(color));
+        context.begin_path();
+        let mut x = (min.0 / spacing).floor() * spacing;
+        while x <= max.0 {
+            context.move_to(x, min.1);
+            context.line_to(x, max.1);
+            x += spacing;
+        }
+        let mut y = (min.1 / spacing).floor() * spacing;
+        while y <= max.1 {
+            context.move_to(min.0, y);
+            context.line_to(max.0, y);
+            y += spacing;
+        }
+        context.stroke();
+    }
+}
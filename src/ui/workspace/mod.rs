@@ -1,4 +1,10 @@
+pub mod grid;
+pub use grid::*;
+pub mod ruler;
+pub mod text_editor;
+pub mod view_transform;
+pub use view_transform::*;
 pub mod workarea;
 pub use workarea::*;
 pub mod workspace;
-pub use workspace::*;
\ No newline at end of file
+pub use workspace::*;
@@ -0,0 +1,157 @@
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+
+use crate::{circuit::BLOCK_SIZE, ui::canvas::CanvasRenderer};
+
+use super::Workarea;
+
+/// Thickness of the rulers in pixels
+pub const RULER_SIZE: i32 = 22;
+/// Smallest distance between two labels of a ruler in pixels
+const LABEL_SPACING: f64 = 50.0;
+/// Smallest distance between two ticks of a ruler in pixels, closer ticks are left out
+const TICK_SPACING: f64 = 5.0;
+/// Color of the marker following the mouse
+const CURSOR_COLOR: &str = "#1e78ff";
+
+/// The edge of the workarea a ruler runs along
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RulerAxis {
+    /// Along the top edge, measuring x
+    Horizontal,
+    /// Along the left edge, measuring y
+    Vertical,
+}
+
+/// What the labels of the rulers count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RulerUnits {
+    /// Coordinates of the design
+    #[default]
+    Grid,
+    /// Minecraft blocks
+    Blocks,
+}
+
+impl RulerUnits {
+    pub const fn toggled(self) -> Self {
+        match self {
+            Self::Grid => Self::Blocks,
+            Self::Blocks => Self::Grid,
+        }
+    }
+    /// Returns the short name shown in the corner between the rulers
+    pub const fn get_symbol(self) -> &'static str {
+        match self {
+            Self::Grid => "px",
+            Self::Blocks => "bl",
+        }
+    }
+    /// Returns the label of a coordinate of the design
+    fn get_label(self, world: f64) -> String {
+        // adding zero turns -0 into 0
+        let world = world + 0.0;
+        match self {
+            Self::Grid => format!("{world}"),
+            Self::Blocks => format!("{}", world / BLOCK_SIZE),
+        }
+    }
+}
+
+/// Draws a ruler following the zoom and position of the workarea, with a marker at the mouse
+#[derive(Clone, PartialEq)]
+pub struct Ruler {
+    axis: RulerAxis,
+    units: RulerUnits,
+    workarea: Workarea,
+}
+
+impl Ruler {
+    pub const fn new(axis: RulerAxis, units: RulerUnits, workarea: Workarea) -> Self {
+        Self {
+            axis,
+            units,
+            workarea,
+        }
+    }
+
+    /// Returns how many grid cells lie between two labels, so they do not overlap at the current zoom
+    fn get_label_step(spacing: f64, scale: f64) -> f64 {
+        [1.0, 2.0, 4.0, 10.0, 20.0, 40.0, 100.0, 200.0]
+            .into_iter()
+            .find(|step| step * spacing * scale >= LABEL_SPACING)
+            .unwrap_or(400.0)
+    }
+}
+
+impl CanvasRenderer for Ruler {
+    fn render(&self, canvas: &HtmlCanvasElement) -> Result<(), JsValue> {
+        let context: CanvasRenderingContext2d = canvas
+            .get_context("2d")?
+            .ok_or_else(JsValue::null)?
+            .dyn_into()?;
+        let (width, height) = (f64::from(canvas.width()), f64::from(canvas.height()));
+        context.clear_rect(0.0, 0.0, width, height);
+        context.set_fill_style(&JsValue::from_str("#f4f4f4"));
+        context.fill_rect(0.0, 0.0, width, height);
+
+        let view = self.workarea.get_view();
+        let scale = view.get_scale();
+        // the ruler is drawn along x, the vertical ruler is turned to read from the bottom
+        let (length, thickness, offset) = match self.axis {
+            RulerAxis::Horizontal => (width, height, view.get_translation().0),
+            RulerAxis::Vertical => {
+                context.translate(0.0, height)?;
+                context.rotate(-std::f64::consts::FRAC_PI_2)?;
+                (height, width, view.get_translation().1)
+            }
+        };
+        let along = |pixel: f64| match self.axis {
+            RulerAxis::Horizontal => pixel,
+            RulerAxis::Vertical => length - pixel,
+        };
+
+        // the ticks follow the grid
+        let spacing = self.workarea.get_grid().get_spacing();
+        let label_step = Self::get_label_step(spacing, scale);
+        let first = (-offset / scale / spacing).floor();
+        let last = ((length - offset) / scale / spacing).ceil();
+        let every_cell = spacing * scale >= TICK_SPACING;
+        context.set_stroke_style(&JsValue::from_str("gray"));
+        context.set_fill_style(&JsValue::from_str("black"));
+        context.set_font("10px Arial");
+        context.begin_path();
+        let mut cell = first;
+        while cell <= last {
+            let labeled = cell % label_step == 0.0;
+            if labeled || every_cell {
+                let x = along((cell * spacing).mul_add(scale, offset).round() + 0.5);
+                let tick = if labeled { thickness } else { thickness / 4.0 };
+                context.move_to(x, thickness);
+                context.line_to(x, thickness - tick);
+                if labeled {
+                    context.fill_text(&self.units.get_label(cell * spacing), x + 3.0, 10.0)?;
+                }
+            }
+            cell += 1.0;
+        }
+        context.move_to(0.0, thickness - 0.5);
+        context.line_to(length, thickness - 0.5);
+        context.stroke();
+
+        let cursor = self.workarea.get_grid_position();
+        let cursor = match self.axis {
+            RulerAxis::Horizontal => cursor.0,
+            RulerAxis::Vertical => cursor.1,
+        };
+        let x = along(cursor.mul_add(scale, offset));
+        context.set_fill_style(&JsValue::from_str(CURSOR_COLOR));
+        context.begin_path();
+        context.move_to(x - 4.0, thickness - 8.0);
+        context.line_to(x + 4.0, thickness - 8.0);
+        context.line_to(x, thickness);
+        context.close_path();
+        context.fill();
+        context.reset_transform()
+    }
+}
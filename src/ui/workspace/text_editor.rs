@@ -0,0 +1,178 @@
+use stylist::style;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlInputElement, HtmlTextAreaElement};
+use yew::{
+    html, Callback, Component, Event, InputEvent, KeyboardEvent, NodeRef, Properties, TargetCast,
+};
+
+use crate::{
+    circuit::{Annotation, TextAlignment, MAX_FONT_SIZE, MIN_FONT_SIZE},
+    ui::console_option::ConsoleOption,
+};
+
+#[derive(Clone, PartialEq, Properties)]
+pub struct Props {
+    pub annotation: Annotation,
+    /// Where the text starts in canvas pixels
+    pub position: (f64, f64),
+    /// The zoom of the view, the text is edited in the size it is shown
+    pub scale: f64,
+    /// Receives the edited annotation, or `None` if editing was cancelled
+    pub onfinish: Callback<Option<Annotation>>,
+}
+
+pub enum TextEditorMsg {
+    SetText(String),
+    SetFontSize(u8),
+    SetAlignment(TextAlignment),
+    SetColor(String),
+    /// Turns the text a quarter clockwise
+    Rotate,
+    Confirm,
+    Cancel,
+}
+
+/// Edits the text and style of an annotation in place, over the canvas.
+/// Ctrl+Enter confirms the text and Escape cancels editing.
+pub struct TextEditor {
+    annotation: Annotation,
+    text_area: NodeRef,
+}
+
+impl Component for TextEditor {
+    type Message = TextEditorMsg;
+    type Properties = Props;
+
+    fn create(ctx: &yew::Context<Self>) -> Self {
+        Self {
+            annotation: ctx.props().annotation.clone(),
+            text_area: NodeRef::default(),
+        }
+    }
+
+    fn changed(&mut self, ctx: &yew::Context<Self>, old_props: &Self::Properties) -> bool {
+        if ctx.props().annotation != old_props.annotation {
+            self.annotation = ctx.props().annotation.clone();
+        }
+        true
+    }
+
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+        let annotation = self.annotation.clone();
+        self.annotation = match msg {
+            TextEditorMsg::SetText(text) => annotation.with_text(text),
+            TextEditorMsg::SetFontSize(font_size) => annotation.with_font_size(font_size),
+            TextEditorMsg::SetAlignment(alignment) => annotation.with_alignment(alignment),
+            TextEditorMsg::SetColor(color) => annotation.with_color(color),
+            TextEditorMsg::Rotate => {
+                let rotation = annotation.get_rotation() + 1;
+                annotation.with_rotation(rotation)
+            }
+            TextEditorMsg::Confirm => {
+                ctx.props().onfinish.emit(Some(annotation));
+                return false;
+            }
+            TextEditorMsg::Cancel => {
+                ctx.props().onfinish.emit(None);
+                return false;
+            }
+        };
+        true
+    }
+
+    fn rendered(&mut self, _ctx: &yew::Context<Self>, first_render: bool) {
+        if first_render {
+            if let Some(text_area) = self.text_area.cast::<HtmlTextAreaElement>() {
+                // focusing fails harmlessly if the editor was closed in the meantime
+                let _ = text_area.focus();
+            }
+        }
+    }
+
+    fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
+        let style_editor = style!(
+            r#"
+            position: absolute;
+            z-index: 2;
+            padding: 5px;
+            background: #f9f9f9;
+            border: 1px solid gray;
+            border-radius: 5px;
+            display: flex;
+            flex-direction: column;
+            gap: 5px;
+        "#
+        )
+        .unwrap_to_console();
+        let style_controls = style!(
+            r#"
+            display: flex;
+            gap: 5px;
+            align-items: center;
+            font-size: 12px;
+        "#
+        )
+        .unwrap_to_console();
+        let style_font_size = style!(
+            r#"
+            width: 45px;
+        "#
+        )
+        .unwrap_to_console();
+
+        let annotation = &self.annotation;
+        let (x, y) = ctx.props().position;
+        let font_size = f64::from(annotation.get_font_size()) * ctx.props().scale;
+        let text_style = format!(
+            "font-family: Arial; font-size: {font_size}px; color: {}; text-align: {}; min-width: 150px;",
+            annotation.get_color(),
+            annotation.get_alignment().get_name()
+        );
+
+        let oninput = ctx.link().callback(|e: InputEvent| {
+            let text_area: HtmlTextAreaElement = e.target_unchecked_into();
+            TextEditorMsg::SetText(text_area.value())
+        });
+        let onkeydown = ctx
+            .link()
+            .batch_callback(|e: KeyboardEvent| match e.key().as_str() {
+                "Escape" => Some(TextEditorMsg::Cancel),
+                "Enter" if e.ctrl_key() => Some(TextEditorMsg::Confirm),
+                _ => None,
+            });
+        let on_font_size_change = ctx.link().batch_callback(|e: Event| {
+            let input = e.target()?.dyn_into::<HtmlInputElement>().ok()?;
+            input.value().parse().ok().map(TextEditorMsg::SetFontSize)
+        });
+        let on_color_input = ctx.link().batch_callback(|e: InputEvent| {
+            let input = e.target()?.dyn_into::<HtmlInputElement>().ok()?;
+            Some(TextEditorMsg::SetColor(input.value()))
+        });
+
+        html! {
+            <div class={ style_editor } style={ format!("left: {x}px; top: {y}px;") }>
+                <div class={ style_controls.clone() }>
+                    <input class={ style_font_size } type="number" title="Font size" min={ MIN_FONT_SIZE.to_string() } max={ MAX_FONT_SIZE.to_string() } value={ annotation.get_font_size().to_string() } onchange={ on_font_size_change } />
+                    { for TextAlignment::ALL.iter().map(|alignment| {
+                        let alignment = *alignment;
+                        html! {
+                            <button disabled={ annotation.get_alignment() == alignment } onclick={ ctx.link().callback(move |_| TextEditorMsg::SetAlignment(alignment)) }>
+                                { alignment.get_name() }
+                            </button>
+                        }
+                    }) }
+                    <input type="color" title="Color" value={ annotation.get_color().to_owned() } oninput={ on_color_input } />
+                    <button onclick={ ctx.link().callback(|_| TextEditorMsg::Rotate) }>
+                        { format!("Rotate ({}°)", u16::from(annotation.get_rotation()) * 90) }
+                    </button>
+                </div>
+                <textarea ref={ self.text_area.clone() } style={ text_style } rows="3" value={ annotation.get_text().to_owned() } { oninput } { onkeydown } />
+                <div class={ style_controls }>
+                    <button onclick={ ctx.link().callback(|_| TextEditorMsg::Confirm) }>{ "OK" }</button>
+                    <button onclick={ ctx.link().callback(|_| TextEditorMsg::Cancel) }>{ "Cancel" }</button>
+                    <span>{ "Ctrl+Enter to confirm, Escape to cancel" }</span>
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use wasm_bindgen::JsValue;
+use web_sys::CanvasRenderingContext2d;
+
+/// Smallest zoom factor
+pub const MIN_SCALE: f64 = 0.2;
+/// Largest zoom factor
+pub const MAX_SCALE: f64 = 4.0;
+/// Factor the zoom changes by with every step of the mouse wheel or zoom command
+pub const ZOOM_STEP: f64 = 1.2;
+/// Space left around the design when fitting it into the window, in pixels
+const FIT_MARGIN: f64 = 50.0;
+
+/// The part of the design shown in the workarea.
+/// World coordinates are scaled and then translated to get canvas pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+    scale: f64,
+    translation: (f64, f64),
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            translation: (0.0, 0.0),
+        }
+    }
+}
+
+impl ViewTransform {
+    /// Creates the view with the given zoom and translation in canvas pixels, the zoom is kept within its limits
+    pub fn new(scale: f64, translation: (f64, f64)) -> Self {
+        if !scale.is_finite() || !translation.0.is_finite() || !translation.1.is_finite() {
+            return Self::default();
+        }
+        Self {
+            scale: scale.clamp(MIN_SCALE, MAX_SCALE),
+            translation,
+        }
+    }
+    pub const fn get_scale(&self) -> f64 {
+        self.scale
+    }
+    pub const fn get_translation(&self) -> (f64, f64) {
+        self.translation
+    }
+
+    /// Converts a point in world coordinates to canvas pixels
+    pub fn to_screen(self, point: (f64, f64)) -> (f64, f64) {
+        (
+            point.0.mul_add(self.scale, self.translation.0),
+            point.1.mul_add(self.scale, self.translation.1),
+        )
+    }
+    /// Converts a point in canvas pixels to world coordinates
+    pub fn to_world(self, point: (f64, f64)) -> (f64, f64) {
+        (
+            (point.0 - self.translation.0) / self.scale,
+            (point.1 - self.translation.1) / self.scale,
+        )
+    }
+
+    /// Zooms by the factor, keeping the world point under the given canvas pixel in place
+    pub fn zoom_at(&mut self, factor: f64, anchor: (f64, f64)) {
+        let world = self.to_world(anchor);
+        self.scale = (self.scale * factor).clamp(MIN_SCALE, MAX_SCALE);
+        self.translation = (
+            world.0.mul_add(-self.scale, anchor.0),
+            world.1.mul_add(-self.scale, anchor.1),
+        );
+    }
+    /// Moves the view by a distance in canvas pixels
+    pub fn pan(&mut self, distance: (f64, f64)) {
+        self.translation = (
+            self.translation.0 + distance.0,
+            self.translation.1 + distance.1,
+        );
+    }
+    /// Zooms and moves the view so the world box from `min` to `max` is centered on a canvas of the given size
+    pub fn fit(&mut self, (min, max): ((f64, f64), (f64, f64)), size: (f64, f64)) {
+        let width = (max.0 - min.0).max(1.0);
+        let height = (max.1 - min.1).max(1.0);
+        self.scale = ((size.0 - 2.0 * FIT_MARGIN) / width)
+            .min((size.1 - 2.0 * FIT_MARGIN) / height)
+            .clamp(MIN_SCALE, MAX_SCALE);
+        self.translation = (
+            (size.0 - width * self.scale) / 2.0 - min.0 * self.scale,
+            (size.1 - height * self.scale) / 2.0 - min.1 * self.scale,
+        );
+    }
+
+    /// Makes the context draw in world coordinates
+    pub fn apply(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        context.set_transform(
+            self.scale,
+            0.0,
+            0.0,
+            self.scale,
+            self.translation.0,
+            self.translation.1,
+        )
+    }
+}
@@ -1,5 +1,7 @@
 use std::{
     cell::{Cell, RefCell},
+    collections::{hash_map::Entry, HashMap},
+    f64::consts::PI,
     rc::Rc,
 };
 
@@ -7,70 +9,284 @@ use gloo::utils::window;
 use js_sys::Function;
 use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
 use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use yew::Callback;
 
-use crate::ui::{
-    application::ApplicationState,
-    canvas::{CanvasElement, CanvasRenderer},
-    connection_point::ConnectionPoint,
-    console_option::ConsoleOption,
+use crate::{
+    circuit::{
+        reroute_wires, route_wire, Annotation, AnnotationAnchor, AnnotationId, Circuit, Component,
+        ComponentId, ComponentType, Edit, PinDirection, PortRef, SubcircuitLibrary, WireId,
+        LINE_HEIGHT, MAX_SIGNAL_STRENGTH,
+    },
+    ui::{
+        application::{ApplicationState, CallbackReason, TextEditing, WireTool},
+        canvas::{CanvasElement, CanvasRenderer},
+        console_option::ConsoleOption,
+    },
 };
 
-use super::GRID_SIZE;
+use super::{GridSettings, ViewTransform, ZOOM_STEP};
+
+/// Color of wires and pins carrying a signal of full strength during simulation as rgb
+const SIGNAL_HIGH_COLOR: (u8, u8, u8) = (0xff, 0x20, 0x20);
+/// Color of wires and pins carrying no signal during simulation as rgb
+const SIGNAL_LOW_COLOR: (u8, u8, u8) = (0x60, 0x00, 0x00);
+
+/// Color of the highlight around selected components and of the selection box
+const SELECTION_COLOR: &str = "#1e78ff";
+/// Color of the highlight around the components and wires of the problem picked in the problems panel
+const PROBLEM_COLOR: &str = "#e02020";
+/// Distance from a port within which clicking picks it for a wire
+const PORT_HIT_RADIUS: f64 = 8.0;
+/// Distance from a wire within which clicking selects it
+const WIRE_HIT_DISTANCE: f64 = 4.0;
+
+/// What dragging the mouse with the button pressed does
+#[derive(Debug, Clone, PartialEq)]
+enum Drag {
+    /// Moves the selected components and annotations, remembering them as they were before the drag
+    Move {
+        start: (f64, f64),
+        before: Vec<Component>,
+        annotations: Vec<Annotation>,
+    },
+    /// Selects the components touched by the box from the start to the mouse
+    Select { start: (f64, f64), extend: bool },
+    /// Moves the view, remembering the last mouse position in canvas pixels
+    Pan { last: (f64, f64) },
+}
 
 #[derive(Clone, PartialEq)]
 pub struct Workarea {
-    mouse_position: Rc<Cell<(i32, i32)>>,
+    /// The position of the mouse in world coordinates
+    mouse_position: Rc<Cell<(f64, f64)>>,
+    /// The mouse position snapped to the grid
     grid_position: Rc<Cell<(f64, f64)>>,
     width: Rc<Cell<i32>>,
     height: Rc<Cell<i32>>,
-    initialized: Cell<bool>,
+    initialized: Rc<Cell<bool>>,
     onclick: Function,
+    /// Edits the text of the annotation under the mouse
+    ondblclick: Function,
     onmousemove: Function,
-    canvas_elements: Rc<RefCell<Vec<CanvasElement>>>,
-    connection_points: Rc<RefCell<Vec<ConnectionPoint>>>,
+    onmousedown: Function,
+    onmouseup: Function,
+    onwheel: Function,
+    /// Tracks the space key, dragging with it held down pans the view
+    onkeychange: Function,
+    space_pressed: Rc<Cell<bool>>,
+    /// The drag in progress while the mouse button is held down
+    drag: Rc<RefCell<Option<Drag>>>,
+    /// The rendered elements of the components in the circuit, the circuit itself is stored in the application state
+    canvas_elements: Rc<RefCell<HashMap<ComponentId, CanvasElement>>>,
+    /// The subcircuits the canvas elements were created from, instances are drawn again when they change
+    drawn_subcircuits: RefCell<SubcircuitLibrary>,
     application_state: Rc<RefCell<ApplicationState>>,
     // selected_tool: Rc<RefCell<Option<CanvasElement>>>,
 }
 
 impl Workarea {
     #[allow(clippy::cast_possible_truncation)]
-    pub fn new(application_state: Rc<RefCell<ApplicationState>>) -> Result<Self, JsValue> {
+    pub fn new(
+        application_state: Rc<RefCell<ApplicationState>>,
+        callback: Callback<CallbackReason>,
+    ) -> Result<Self, JsValue> {
         let width = Rc::new(Cell::new(Self::get_width()));
         let height = Rc::new(Cell::new(Self::get_height()));
-        let mouse_position = Rc::new(Cell::new((0, 0)));
+        let mouse_position = Rc::new(Cell::new((0.0, 0.0)));
+        let space_pressed = Rc::new(Cell::new(false));
         let grid_position = Rc::new(Cell::new((0.0, 0.0)));
-        let canvas_elements: Rc<RefCell<Vec<CanvasElement>>> = Rc::new(RefCell::new(Vec::new()));
-        let connection_points = Rc::new(RefCell::new(Vec::new()));
+        let canvas_elements: Rc<RefCell<HashMap<ComponentId, CanvasElement>>> =
+            Rc::new(RefCell::new(HashMap::new()));
 
         let onclick = {
             let grid_position = grid_position.clone();
+            let mouse_position = mouse_position.clone();
             let canvas_elements = canvas_elements.clone();
             let application_state = application_state.clone();
-            let connection_points = connection_points.clone();
+            let callback = callback.clone();
+            let space_pressed = space_pressed.clone();
             let closure: Closure<dyn FnMut(web_sys::MouseEvent)> =
                 Closure::new(move |_event: web_sys::MouseEvent| {
-                    if let Some(tool) = application_state.borrow().tool_active.as_ref() {
-                        canvas_elements
-                            .borrow_mut()
-                            .push(tool.at_position(grid_position.get()));
-
-                        for cp in tool.get_connection_points() {
-                            connection_points
-                                .borrow_mut()
-                                .push(cp.get_absolute_at_position(grid_position.get()));
+                    // the click ends panning the view
+                    if space_pressed.get() {
+                        return;
+                    }
+                    if application_state.borrow().simulation.is_some() {
+                        Self::toggle_input_at(&application_state, mouse_position.get());
+                        return;
+                    }
+                    if application_state.borrow().wire_tool.is_some() {
+                        Self::pick_wire_port(&application_state, mouse_position.get());
+                        callback.emit(CallbackReason::CircuitChanged);
+                        return;
+                    }
+                    if application_state.borrow().pasting.is_some() {
+                        Self::paste_at(&application_state, grid_position.get());
+                        callback.emit(CallbackReason::CircuitChanged);
+                        return;
+                    }
+                    if application_state.borrow().text_tool {
+                        Self::place_text_at(&application_state, grid_position.get());
+                        callback.emit(CallbackReason::CircuitChanged);
+                        return;
+                    }
+                    let tool = application_state.borrow().tool_active.clone();
+                    if let Some(tool) = tool {
+                        let position = grid_position.get();
+                        let mut state = application_state.borrow_mut();
+                        let ApplicationState {
+                            circuit, history, ..
+                        } = &mut *state;
+                        let id = circuit.add_component(tool.to_component(position));
+                        if let Some(component) = circuit.get_component(id) {
+                            history.record(Edit::AddComponent(component.clone()));
                         }
+                        drop(state);
+                        canvas_elements.borrow_mut().insert(id, tool.clone());
+                        callback.emit(CallbackReason::CircuitChanged);
                     }
                 });
             closure.into_js_value().dyn_into()?
         };
+        let ondblclick = {
+            let mouse_position = mouse_position.clone();
+            let application_state = application_state.clone();
+            let callback = callback.clone();
+            let closure: Closure<dyn FnMut(web_sys::MouseEvent)> =
+                Closure::new(move |_event: web_sys::MouseEvent| {
+                    let mut state = application_state.borrow_mut();
+                    if state.simulation.is_some() {
+                        return;
+                    }
+                    let Some(id) = Self::get_annotation_at(&state.circuit, mouse_position.get())
+                    else {
+                        return;
+                    };
+                    state.text_editing = Some(TextEditing::Existing(id));
+                    drop(state);
+                    callback.emit(CallbackReason::CircuitChanged);
+                });
+            closure.into_js_value().dyn_into()?
+        };
+        let drag = Rc::new(RefCell::new(None));
         let onmousemove = {
             let grid_position = grid_position.clone();
+            let mouse_position = mouse_position.clone();
+            let application_state = application_state.clone();
+            let drag = drag.clone();
+            let callback = callback.clone();
+            let closure: Closure<dyn FnMut(web_sys::MouseEvent)> =
+                Closure::new(move |event: web_sys::MouseEvent| {
+                    let screen = (f64::from(event.offset_x()), f64::from(event.offset_y()));
+                    let panned = if let Some(Drag::Pan { last }) = drag.borrow_mut().as_mut() {
+                        let distance = (screen.0 - last.0, screen.1 - last.1);
+                        application_state.borrow_mut().view.pan(distance);
+                        *last = screen;
+                        true
+                    } else {
+                        false
+                    };
+                    if panned {
+                        callback.emit(CallbackReason::ViewChanged);
+                    }
+                    let state = application_state.borrow();
+                    let position = state.view.to_world(screen);
+                    mouse_position.replace(position);
+                    grid_position.replace(state.grid.snap(position));
+                    drop(state);
+                    if let Some(Drag::Move {
+                        start,
+                        before,
+                        annotations,
+                    }) = drag.borrow().as_ref()
+                    {
+                        Self::move_components(
+                            &application_state,
+                            before,
+                            annotations,
+                            *start,
+                            mouse_position.get(),
+                        );
+                    }
+                });
+            closure.into_js_value().dyn_into()?
+        };
+        let onmousedown = {
+            let mouse_position = mouse_position.clone();
+            let application_state = application_state.clone();
+            let drag = drag.clone();
+            let callback = callback.clone();
+            let space_pressed = space_pressed.clone();
             let closure: Closure<dyn FnMut(web_sys::MouseEvent)> =
                 Closure::new(move |event: web_sys::MouseEvent| {
-                    let x = (f64::from(event.client_x() - 247) / GRID_SIZE).round() * GRID_SIZE;
-                    let y = (f64::from(event.client_y() - 97) / GRID_SIZE).round() * GRID_SIZE;
-                    // _mouse_position.replace((event.client_x() - 247, event.client_y() - 97));
-                    grid_position.replace((x, y));
+                    // the middle button or the left button with space pans the view
+                    if event.button() == 1 || event.button() == 0 && space_pressed.get() {
+                        event.prevent_default();
+                        let last = (f64::from(event.offset_x()), f64::from(event.offset_y()));
+                        drag.replace(Some(Drag::Pan { last }));
+                        return;
+                    }
+                    let state = application_state.borrow();
+                    if event.button() != 0
+                        || state.simulation.is_some()
+                        || state.tool_active.is_some()
+                        || state.wire_tool.is_some()
+                        || state.text_tool
+                        || state.pasting.is_some()
+                    {
+                        return;
+                    }
+                    drop(state);
+                    drag.replace(Self::start_drag(
+                        &application_state,
+                        mouse_position.get(),
+                        event.shift_key(),
+                    ));
+                    callback.emit(CallbackReason::CircuitChanged);
+                });
+            closure.into_js_value().dyn_into()?
+        };
+        let onmouseup = {
+            let mouse_position = mouse_position.clone();
+            let application_state = application_state.clone();
+            let drag = drag.clone();
+            let callback = callback.clone();
+            let closure: Closure<dyn FnMut(web_sys::MouseEvent)> =
+                Closure::new(move |_event: web_sys::MouseEvent| {
+                    if let Some(finished) = drag.take() {
+                        Self::end_drag(&application_state, finished, mouse_position.get());
+                        callback.emit(CallbackReason::CircuitChanged);
+                    }
+                });
+            closure.into_js_value().dyn_into()?
+        };
+        let onwheel = {
+            let application_state = application_state.clone();
+            let callback = callback.clone();
+            let closure: Closure<dyn FnMut(web_sys::WheelEvent)> =
+                Closure::new(move |event: web_sys::WheelEvent| {
+                    if event.delta_y() == 0.0 {
+                        return;
+                    }
+                    event.prevent_default();
+                    let factor = if event.delta_y() < 0.0 {
+                        ZOOM_STEP
+                    } else {
+                        1.0 / ZOOM_STEP
+                    };
+                    let anchor = (f64::from(event.offset_x()), f64::from(event.offset_y()));
+                    application_state.borrow_mut().view.zoom_at(factor, anchor);
+                    callback.emit(CallbackReason::ViewChanged);
+                });
+            closure.into_js_value().dyn_into()?
+        };
+        let onkeychange = {
+            let space_pressed = space_pressed.clone();
+            let closure: Closure<dyn FnMut(web_sys::KeyboardEvent)> =
+                Closure::new(move |event: web_sys::KeyboardEvent| {
+                    if event.key() == " " {
+                        space_pressed.set(event.type_() == "keydown");
+                    }
                 });
             closure.into_js_value().dyn_into()?
         };
@@ -80,12 +296,19 @@ impl Workarea {
             grid_position,
             width,
             height,
-            initialized: Cell::new(false),
+            initialized: Rc::new(Cell::new(false)),
             onclick,
+            ondblclick,
             onmousemove,
+            onmousedown,
+            onmouseup,
+            onwheel,
+            onkeychange,
+            space_pressed,
+            drag,
             canvas_elements,
+            drawn_subcircuits: RefCell::default(),
             application_state,
-            connection_points,
         })
     }
 
@@ -108,6 +331,18 @@ impl Workarea {
             - 97
     }
 
+    /// Returns the zoom and position of the workarea
+    pub fn get_view(&self) -> ViewTransform {
+        self.application_state.borrow().view
+    }
+    pub fn get_grid(&self) -> GridSettings {
+        self.application_state.borrow().grid
+    }
+    /// Returns the mouse position snapped to the grid
+    pub fn get_grid_position(&self) -> (f64, f64) {
+        self.grid_position.get()
+    }
+
     pub fn init(&self, canvas: &web_sys::HtmlCanvasElement) {
         self.initialized.replace(true);
         canvas
@@ -116,96 +351,818 @@ impl Workarea {
         canvas
             .add_event_listener_with_callback("click", &self.onclick)
             .expect_to_console("Could not add event listener click");
+        canvas
+            .add_event_listener_with_callback("dblclick", &self.ondblclick)
+            .expect_to_console("Could not add event listener dblclick");
+        canvas
+            .add_event_listener_with_callback("mousedown", &self.onmousedown)
+            .expect_to_console("Could not add event listener mousedown");
+        canvas
+            .add_event_listener_with_callback("wheel", &self.onwheel)
+            .expect_to_console("Could not add event listener wheel");
+        window()
+            .add_event_listener_with_callback("keydown", &self.onkeychange)
+            .expect_to_console("Could not add event listener keydown");
+        window()
+            .add_event_listener_with_callback("keyup", &self.onkeychange)
+            .expect_to_console("Could not add event listener keyup");
+        // the drag also ends if the button is released outside of the canvas
+        window()
+            .add_event_listener_with_callback("mouseup", &self.onmouseup)
+            .expect_to_console("Could not add event listener mouseup");
     }
 
     /// This is a simple function to render the currently selected tool
     fn render_selected_tool(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
-        if let Some(tool) = self
-            .application_state
-            .borrow()
-            .tool_active
-            .as_ref()
-            .as_ref()
-        {
-            // FIXME: Connections are currently made trough elements, which is not wanted
+        let application_state = self.application_state.borrow();
+        if let Some(tool) = application_state.tool_active.as_ref() {
             tool.render_at_position(context, self.grid_position.clone().get())?;
-            for cp in tool.get_connection_points() {
-                let cp1 = &cp.get_absolute_at_position(self.grid_position.clone().get());
-                for cp2 in self.connection_points.borrow().iter() {
-                    if self.check_connection_points(cp1, cp2) {
-                        context.begin_path();
-                        context.set_stroke_style(&JsValue::from_str("black"));
-                        context.move_to(cp1.get_position_x(), cp1.get_position_y());
-                        context.line_to(cp2.get_position_x(), cp2.get_position_y());
-                        context.stroke();
-                    }
-                }
+        }
+        Ok(())
+    }
+
+    /// Renders the components being pasted with their top left corner at the mouse
+    fn render_pasting(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        let application_state = self.application_state.borrow();
+        let Some(fragment) = application_state.pasting.as_ref() else {
+            return Ok(());
+        };
+        let (x, y) = self.grid_position.get();
+        context.save();
+        context.translate(x, y)?;
+        for component in fragment.get_components() {
+            component
+                .try_as_canvas_element(&application_state.subcircuits)?
+                .render_at_position(context, component.get_position())?;
+        }
+        for wire in fragment.get_wires() {
+            let Some(points) = fragment.get_wire_points(wire.get_id()) else {
+                continue;
+            };
+            context.begin_path();
+            context.move_to(points[0].0, points[0].1);
+            points[1..]
+                .iter()
+                .for_each(|(x, y)| context.line_to(*x, *y));
+            context.stroke();
+        }
+        context.restore();
+        Ok(())
+    }
+
+    /// Marks the ports a wire can be drawn between and the wire being drawn
+    fn render_wire_tool(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        let application_state = self.application_state.borrow();
+        let Some(wire_tool) = application_state.wire_tool else {
+            return Ok(());
+        };
+        let circuit = &application_state.circuit;
+        context.save();
+        context.set_stroke_style(&JsValue::from_str(SELECTION_COLOR));
+        context.set_fill_style(&JsValue::from_str(SELECTION_COLOR));
+        for component in circuit.get_components() {
+            for port in component.get_absolute_ports() {
+                context.begin_path();
+                context.arc(
+                    port.get_position_x(),
+                    port.get_position_y(),
+                    PORT_HIT_RADIUS / 2.0,
+                    0.0,
+                    2.0 * PI,
+                )?;
+                context.stroke();
             }
         }
+        if let WireTool::PickTarget(source) = wire_tool {
+            if let Some(port) = circuit.get_port(source) {
+                let (x, y) = self.mouse_position.get();
+                context.begin_path();
+                context.arc(
+                    port.get_position_x(),
+                    port.get_position_y(),
+                    PORT_HIT_RADIUS / 2.0,
+                    0.0,
+                    2.0 * PI,
+                )?;
+                context.fill();
+                context.begin_path();
+                context.set_line_dash(&js_sys::Array::of2(&4.0.into(), &4.0.into()))?;
+                context.move_to(port.get_position_x(), port.get_position_y());
+                context.line_to(x, y);
+                context.stroke();
+            }
+        }
+        context.restore();
         Ok(())
     }
-    // FIXME: Connections are currently made trough elements, which is not wanted
+
     fn render_connections(&self, context: &CanvasRenderingContext2d) {
-        for cp1 in self.connection_points.borrow().iter() {
-            for cp2 in self.connection_points.borrow().iter() {
-                if self.check_connection_points(cp1, cp2) {
-                    context.begin_path();
-                    context.set_stroke_style(&JsValue::from_str("black"));
-                    context.move_to(cp1.get_position_x(), cp1.get_position_y());
-                    context.line_to(cp2.get_position_x(), cp2.get_position_y());
-                    context.stroke();
+        let application_state = self.application_state.borrow();
+        let circuit = &application_state.circuit;
+        let simulation = application_state.simulation.as_ref();
+        let problem_wires = application_state
+            .problem
+            .as_ref()
+            .map_or(&[][..], |p| p.get_wires());
+        for wire in circuit.get_wires() {
+            let Some(points) = circuit.get_wire_points(wire.get_id()) else {
+                continue;
+            };
+            let trace = || {
+                context.begin_path();
+                context.move_to(points[0].0, points[0].1);
+                points[1..]
+                    .iter()
+                    .for_each(|(x, y)| context.line_to(*x, *y));
+                context.stroke();
+            };
+            if application_state.selected_wires.contains(&wire.get_id()) {
+                context.save();
+                context.set_stroke_style(&JsValue::from_str(SELECTION_COLOR));
+                context.set_line_width(5.0);
+                trace();
+                context.restore();
+            }
+            if problem_wires.contains(&wire.get_id()) {
+                context.save();
+                context.set_stroke_style(&JsValue::from_str(PROBLEM_COLOR));
+                context.set_line_width(7.0);
+                context.set_global_alpha(0.5);
+                trace();
+                context.restore();
+            }
+            let color = simulation.map_or_else(
+                || "black".to_owned(),
+                |s| Self::get_signal_color(s.get_wire_strength(wire.get_id()).unwrap_or(0)),
+            );
+            context.set_stroke_style(&JsValue::from_str(&color));
+            let width = circuit.get_wire_width(wire.get_id()).unwrap_or(1);
+            if width == 1 {
+                trace();
+                continue;
+            }
+            context.save();
+            context.set_line_width(4.0);
+            trace();
+            context.restore();
+            Self::render_bus_label(
+                context,
+                &points,
+                width,
+                simulation.and_then(|s| s.get_wire_bits(wire.get_id())),
+            );
+        }
+    }
+
+    /// Marks a bus wire with a slash and its width next to its first segment, and its value while the circuit is
+    /// simulated
+    fn render_bus_label(
+        context: &CanvasRenderingContext2d,
+        points: &[(f64, f64)],
+        width: u8,
+        bits: Option<u32>,
+    ) {
+        let (Some(a), Some(b)) = (points.first(), points.get(1)) else {
+            return;
+        };
+        let (x, y) = ((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        context.save();
+        context.set_stroke_style(&JsValue::from_str("black"));
+        context.set_fill_style(&JsValue::from_str("black"));
+        context.set_line_width(1.0);
+        context.begin_path();
+        context.move_to(x - 4.0, y + 6.0);
+        context.line_to(x + 4.0, y - 6.0);
+        context.stroke();
+        context.set_font("10px Arial");
+        let text = bits.map_or_else(|| width.to_string(), |bits| format!("{width}: {bits:#x}"));
+        // a failed text only leaves the label out
+        let _ = context.fill_text(&text, x + 5.0, y - 5.0);
+        context.restore();
+    }
+
+    /// Marks the value of every pin and tints the components of the game with their signal while the circuit is
+    /// simulated
+    fn render_component_values(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        let application_state = self.application_state.borrow();
+        let Some(simulation) = application_state.simulation.as_ref() else {
+            return Ok(());
+        };
+        for component in application_state.circuit.get_components() {
+            let component_type = component.get_component_type();
+            if matches!(component_type, ComponentType::LogicGate(_)) {
+                continue;
+            }
+            // the output is the last port, components without one show their input
+            let port = component.get_ports().len().saturating_sub(1);
+            let Some(strength) =
+                simulation.get_port_strength(PortRef::new(component.get_id(), port))
+            else {
+                continue;
+            };
+            let (x, y) = component.get_position();
+            if let ComponentType::Pin(_, width @ 2..) = component_type {
+                if let Some(bits) = simulation.get_port_bits(PortRef::new(component.get_id(), 0)) {
+                    context.set_fill_style(&JsValue::from_str("black"));
+                    context.set_font("10px Arial");
+                    context.fill_text(
+                        &format!("{bits:#0w$x}", w = usize::from(width).div_ceil(4) + 2),
+                        x,
+                        y + 48.0,
+                    )?;
                 }
             }
+            let color = JsValue::from_str(&Self::get_signal_color(strength));
+            context.set_fill_style(&color);
+            if matches!(component_type, ComponentType::Pin(..)) {
+                context.begin_path();
+                context.arc(x + 6.0, y + 25.0, 5.0, 0.0, 2.0 * PI)?;
+                context.fill();
+            } else {
+                context.set_global_alpha(0.3);
+                context.fill_rect(x, y, component.get_width(), component.get_height());
+                context.set_global_alpha(1.0);
+            }
         }
+        Ok(())
     }
 
-    #[allow(clippy::cast_possible_truncation)]
-    fn check_connection_points(&self, cp1: &ConnectionPoint, cp2: &ConnectionPoint) -> bool {
-        if cp1 == cp2 {
-            return false;
-        } else if (cp1.get_position_x() as i32 == cp2.get_position_x() as i32
-            && (cp1.get_position_y() > cp2.get_position_y()
-                && cp1.get_direction_y_neg()
-                && cp2.get_direction_y_pos()
-                || cp1.get_position_y() < cp2.get_position_y()
-                    && cp1.get_direction_y_pos()
-                    && cp2.get_direction_y_neg()))
-            || (cp1.get_position_y() as i32 == cp2.get_position_y() as i32
-                && (cp1.get_position_x() > cp2.get_position_x()
-                    && cp1.get_direction_x_neg()
-                    && cp2.get_direction_x_pos()
-                    || cp1.get_position_x() < cp2.get_position_x()
-                        && cp1.get_direction_x_pos()
-                        && cp2.get_direction_x_neg()))
+    /// Returns the color of a signal, fading from the low to the high color with the signal strength
+    fn get_signal_color(strength: u8) -> String {
+        let fade = |low: u8, high: u8| {
+            let low = u16::from(low);
+            let high = u16::from(high);
+            low + (high - low) * u16::from(strength.min(MAX_SIGNAL_STRENGTH))
+                / u16::from(MAX_SIGNAL_STRENGTH)
+        };
+        format!(
+            "rgb({}, {}, {})",
+            fade(SIGNAL_LOW_COLOR.0, SIGNAL_HIGH_COLOR.0),
+            fade(SIGNAL_LOW_COLOR.1, SIGNAL_HIGH_COLOR.1),
+            fade(SIGNAL_LOW_COLOR.2, SIGNAL_HIGH_COLOR.2)
+        )
+    }
+
+    /// Shows the current game tick while the circuit is simulated and whether it settled
+    fn render_status(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        let state = self.application_state.borrow();
+        context.set_fill_style(&JsValue::from_str("black"));
+        context.set_font("14px Arial");
+        if let Some(simulation) = state.simulation.as_ref() {
+            let status = if simulation.is_stable() {
+                format!("Tick {} (stable)", simulation.get_time())
+            } else {
+                format!("Tick {}", simulation.get_time())
+            };
+            context.fill_text(&status, 10.0, 20.0)?;
+        }
+        if let Some(subcircuit) = state
+            .editing
+            .last()
+            .and_then(|open| state.subcircuits.get(open.id))
+        {
+            context.fill_text(&format!("Editing {}", subcircuit.get_name()), 10.0, 40.0)?;
+        }
+        Ok(())
+    }
+
+    /// Selects the annotation or component under the mouse and starts moving the selection,
+    /// or starts a selection box if there is nothing under the mouse.
+    /// With shift the clicked annotation or component is added to or removed from the selection.
+    fn start_drag(
+        application_state: &Rc<RefCell<ApplicationState>>,
+        position: (f64, f64),
+        shift: bool,
+    ) -> Option<Drag> {
+        let start = position;
+        let mut state = application_state.borrow_mut();
+        let ApplicationState {
+            circuit,
+            selection,
+            selected_wires,
+            selected_annotations,
+            ..
+        } = &mut *state;
+        // annotations are drawn above the components
+        if let Some(id) = Self::get_annotation_at(circuit, start) {
+            if shift && selected_annotations.remove(&id) {
+                return None;
+            }
+            if !shift && !selected_annotations.contains(&id) {
+                selection.clear();
+                selected_wires.clear();
+                selected_annotations.clear();
+            }
+            selected_annotations.insert(id);
+            return Some(Self::start_move(&state, start));
+        }
+        // the component drawn last is on top
+        let Some(hit) = circuit
+            .get_components()
+            .iter()
+            .rev()
+            .find(|c| c.contains(start))
+        else {
+            if !shift {
+                selection.clear();
+                selected_wires.clear();
+                selected_annotations.clear();
+            }
+            if let Some(wire) = Self::get_wire_at(circuit, start) {
+                if !selected_wires.remove(&wire) {
+                    selected_wires.insert(wire);
+                }
+                return None;
+            }
+            return Some(Drag::Select {
+                start,
+                extend: shift,
+            });
+        };
+        let id = hit.get_id();
+        if shift && selection.remove(&id) {
+            return None;
+        }
+        if !shift && !selection.contains(&id) {
+            selection.clear();
+            selected_wires.clear();
+            selected_annotations.clear();
+        }
+        selection.insert(id);
+        Some(Self::start_move(&state, start))
+    }
+
+    /// Remembers the selected components and annotations before they are moved.
+    /// Annotations attached to a selected component already move along with it.
+    fn start_move(state: &ApplicationState, start: (f64, f64)) -> Drag {
+        let ApplicationState {
+            circuit,
+            selection,
+            selected_annotations,
+            ..
+        } = state;
+        let before = selection
+            .iter()
+            .filter_map(|id| circuit.get_component(*id))
+            .cloned()
+            .collect();
+        let annotations = selected_annotations
+            .iter()
+            .filter_map(|id| circuit.get_annotation(*id))
+            .filter(|a| !selection.iter().any(|id| a.is_attached_to(*id)))
+            .cloned()
+            .collect();
+        Drag::Move {
+            start,
+            before,
+            annotations,
+        }
+    }
+
+    /// Moves the components and annotations by the distance the mouse was dragged, snapped to the grid
+    fn move_components(
+        application_state: &Rc<RefCell<ApplicationState>>,
+        before: &[Component],
+        annotations: &[Annotation],
+        start: (f64, f64),
+        position: (f64, f64),
+    ) {
+        let mut state = application_state.borrow_mut();
+        let offset = Self::get_snapped_offset(&state.grid, start, position);
+        let circuit = &mut state.circuit;
+        for component in before {
+            if let Some(moved) = circuit.get_component_mut(component.get_id()) {
+                let (x, y) = component.get_position();
+                moved.set_position((x + offset.0, y + offset.1));
+            }
+        }
+        for annotation in annotations {
+            if let Some(moved) = circuit.get_annotation_mut(annotation.get_id()) {
+                let (x, y) = annotation.get_position();
+                moved.set_position((x + offset.0, y + offset.1));
+            }
+        }
+    }
+
+    /// Records the moved components and annotations as one edit or selects everything in the selection box
+    fn end_drag(
+        application_state: &Rc<RefCell<ApplicationState>>,
+        drag: Drag,
+        position: (f64, f64),
+    ) {
+        let mut state = application_state.borrow_mut();
+        let ApplicationState {
+            circuit,
+            history,
+            selection,
+            selected_annotations,
+            grid,
+            ..
+        } = &mut *state;
+        match drag {
+            Drag::Move {
+                start,
+                before,
+                annotations,
+            } => {
+                if Self::get_snapped_offset(grid, start, position) == (0.0, 0.0) {
+                    return;
+                }
+                let ids: Vec<_> = before.iter().map(Component::get_id).collect();
+                history.begin_group();
+                for before in annotations {
+                    if let Some(after) = circuit.get_annotation(before.get_id()) {
+                        history.record(Edit::ReplaceAnnotation {
+                            after: after.clone(),
+                            before,
+                        });
+                    }
+                }
+                for before in before {
+                    if let Some(after) = circuit.get_component(before.get_id()) {
+                        history.record(Edit::ReplaceComponent {
+                            after: after.clone(),
+                            before,
+                        });
+                    }
+                }
+                // the wires follow the moved components
+                for edit in reroute_wires(circuit, &ids, grid.get_spacing()) {
+                    history.apply(circuit, edit);
+                }
+                history.end_group();
+            }
+            Drag::Pan { .. } => {}
+            Drag::Select { start, extend } => {
+                let end = position;
+                let (min, max) = (
+                    (start.0.min(end.0), start.1.min(end.1)),
+                    (start.0.max(end.0), start.1.max(end.1)),
+                );
+                if !extend {
+                    selection.clear();
+                    selected_annotations.clear();
+                }
+                selected_annotations.extend(
+                    circuit
+                        .get_annotations()
+                        .iter()
+                        .filter(|a| {
+                            circuit.get_annotation_position(a).is_some_and(|origin| {
+                                let (low, high) = a.get_extent(origin);
+                                low.0 <= max.0
+                                    && high.0 >= min.0
+                                    && low.1 <= max.1
+                                    && high.1 >= min.1
+                            })
+                        })
+                        .map(Annotation::get_id),
+                );
+                selection.extend(
+                    circuit
+                        .get_components()
+                        .iter()
+                        .filter(|c| {
+                            let (x, y) = c.get_position();
+                            x <= max.0
+                                && x + c.get_width() >= min.0
+                                && y <= max.1
+                                && y + c.get_height() >= min.1
+                        })
+                        .map(Component::get_id),
+                );
+            }
+        }
+    }
+
+    /// Returns the distance the mouse was dragged from the start, rounded to whole grid cells
+    fn get_snapped_offset(
+        grid: &GridSettings,
+        start: (f64, f64),
+        position: (f64, f64),
+    ) -> (f64, f64) {
+        grid.snap((position.0 - start.0, position.1 - start.1))
+    }
+
+    /// Highlights the selected components and draws the selection box while it is dragged
+    fn render_selection(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        let application_state = self.application_state.borrow();
+        context.save();
+        context.set_stroke_style(&JsValue::from_str(SELECTION_COLOR));
+        context.set_line_width(2.0);
+        for component in application_state
+            .circuit
+            .get_components()
+            .iter()
+            .filter(|c| application_state.selection.contains(&c.get_id()))
         {
-            for canvas_element in self.canvas_elements.borrow().iter() {
-                if Self::check_if_crosses(cp1, cp2, canvas_element) {
-                    return false;
+            let (x, y) = component.get_position();
+            context.stroke_rect(
+                x - 3.0,
+                y - 3.0,
+                component.get_width() + 6.0,
+                component.get_height() + 6.0,
+            );
+        }
+        for annotation in application_state
+            .circuit
+            .get_annotations()
+            .iter()
+            .filter(|a| application_state.selected_annotations.contains(&a.get_id()))
+        {
+            let Some(origin) = application_state
+                .circuit
+                .get_annotation_position(annotation)
+            else {
+                continue;
+            };
+            let (low, high) = annotation.get_extent(origin);
+            context.stroke_rect(
+                low.0 - 3.0,
+                low.1 - 3.0,
+                high.0 - low.0 + 6.0,
+                high.1 - low.1 + 6.0,
+            );
+        }
+        if let Some(problem) = application_state.problem.as_ref() {
+            context.set_stroke_style(&JsValue::from_str(PROBLEM_COLOR));
+            for component in problem
+                .get_components()
+                .iter()
+                .filter_map(|id| application_state.circuit.get_component(*id))
+            {
+                let (x, y) = component.get_position();
+                context.stroke_rect(
+                    x - 6.0,
+                    y - 6.0,
+                    component.get_width() + 12.0,
+                    component.get_height() + 12.0,
+                );
+            }
+            context.set_stroke_style(&JsValue::from_str(SELECTION_COLOR));
+        }
+        if let Some(Drag::Select { start, .. }) = self.drag.borrow().as_ref() {
+            let (x, y) = self.mouse_position.get();
+            context.set_line_width(1.0);
+            context.set_line_dash(&js_sys::Array::of2(&4.0.into(), &4.0.into()))?;
+            context.stroke_rect(start.0, start.1, x - start.0, y - start.1);
+        }
+        context.restore();
+        Ok(())
+    }
+
+    /// Toggles the input pin, lever or pressure plate or presses the button at the position while the circuit is
+    /// simulated. The value of an input bus is asked for in hexadecimal.
+    fn toggle_input_at(application_state: &Rc<RefCell<ApplicationState>>, position: (f64, f64)) {
+        let mut state = application_state.borrow_mut();
+        let ApplicationState {
+            circuit,
+            simulation,
+            ..
+        } = &mut *state;
+        let Some(simulation) = simulation.as_mut() else {
+            return;
+        };
+        let Some(component) = circuit
+            .get_components()
+            .iter()
+            .find(|c| c.get_component_type().is_user_input() && c.contains(position))
+        else {
+            return;
+        };
+        let ComponentType::Pin(PinDirection::Input | PinDirection::Bidirectional, 2..) =
+            component.get_component_type()
+        else {
+            simulation.toggle_input(component.get_id());
+            return;
+        };
+        let id = component.get_id();
+        let current = simulation.get_port_bits(PortRef::new(id, 0)).unwrap_or(0);
+        drop(state);
+        let Some(value) = gloo::dialogs::prompt("Value of the bus", Some(&format!("{current:#x}")))
+        else {
+            return;
+        };
+        let value = value.trim();
+        let digits = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .unwrap_or(value);
+        let Ok(bits) = u32::from_str_radix(digits, 16) else {
+            gloo::dialogs::alert("The value must be a hexadecimal number");
+            return;
+        };
+        if let Some(simulation) = application_state.borrow_mut().simulation.as_mut() {
+            simulation.set_input_bits(id, bits);
+        }
+    }
+
+    /// Renders every component of the circuit, creating the canvas elements of newly added components
+    fn render_components(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        let state = self.application_state.borrow();
+        let ApplicationState {
+            circuit,
+            subcircuits,
+            ..
+        } = &*state;
+        let mut canvas_elements = self.canvas_elements.borrow_mut();
+        canvas_elements.retain(|id, _| circuit.get_component(*id).is_some());
+        if *subcircuits != *self.drawn_subcircuits.borrow() {
+            canvas_elements.retain(|_, element| {
+                !matches!(element.get_component_type(), ComponentType::Subcircuit(_))
+            });
+            *self.drawn_subcircuits.borrow_mut() = subcircuits.clone();
+        }
+        for component in circuit.get_components() {
+            let id = component.get_id();
+            let element = match canvas_elements.entry(id) {
+                Entry::Occupied(entry) if entry.get().matches(component) => entry.into_mut(),
+                Entry::Occupied(mut entry) => {
+                    entry.insert(component.try_as_canvas_element(subcircuits)?);
+                    entry.into_mut()
                 }
+                Entry::Vacant(entry) => entry.insert(component.try_as_canvas_element(subcircuits)?),
+            };
+            element.render_at_position(context, component.get_position())?;
+        }
+        Ok(())
+    }
+
+    /// Picks the port under the mouse as the source of a new wire,
+    /// or connects the source to it with a routed wire
+    fn pick_wire_port(application_state: &Rc<RefCell<ApplicationState>>, point: (f64, f64)) {
+        let mut state = application_state.borrow_mut();
+        let ApplicationState {
+            circuit,
+            history,
+            wire_tool,
+            grid,
+            ..
+        } = &mut *state;
+        let Some(port) = Self::get_port_at(circuit, point) else {
+            return;
+        };
+        let Some(WireTool::PickTarget(source)) = *wire_tool else {
+            *wire_tool = Some(WireTool::PickTarget(port));
+            return;
+        };
+        // clicking the source again starts over
+        *wire_tool = Some(WireTool::PickSource);
+        if source == port {
+            return;
+        }
+        let width = |port| circuit.get_port(port).map(|p| p.get_width());
+        if width(source) != width(port) {
+            drop(state);
+            gloo::dialogs::alert("Only ports of the same width can be connected, use a splitter or merger between a bus and its bits");
+            return;
+        }
+        let Some(path) = route_wire(circuit, source, port, grid.get_spacing()) else {
+            drop(state);
+            gloo::dialogs::alert("There is no free path for the wire between these ports");
+            return;
+        };
+        if let Some(wire) = circuit
+            .add_routed_wire(source, port, path)
+            .and_then(|id| circuit.get_wire(id))
+        {
+            history.record(Edit::AddWire(wire.clone()));
+        }
+    }
+
+    /// Adds the components and annotations being pasted at the position as one edit and selects them
+    fn paste_at(application_state: &Rc<RefCell<ApplicationState>>, position: (f64, f64)) {
+        let mut state = application_state.borrow_mut();
+        let ApplicationState {
+            circuit,
+            history,
+            selection,
+            selected_wires,
+            selected_annotations,
+            pasting,
+            ..
+        } = &mut *state;
+        let Some(fragment) = pasting.take() else {
+            return;
+        };
+        let (ids, wire_ids, annotation_ids) = circuit.insert_fragment(&fragment, position);
+        history.begin_group();
+        for component in ids.iter().filter_map(|id| circuit.get_component(*id)) {
+            history.record(Edit::AddComponent(component.clone()));
+        }
+        for wire in wire_ids.iter().filter_map(|id| circuit.get_wire(*id)) {
+            history.record(Edit::AddWire(wire.clone()));
+        }
+        for annotation in annotation_ids
+            .iter()
+            .filter_map(|id| circuit.get_annotation(*id))
+        {
+            history.record(Edit::AddAnnotation(annotation.clone()));
+        }
+        history.end_group();
+        *selection = ids.into_iter().collect();
+        *selected_wires = wire_ids.into_iter().collect();
+        *selected_annotations = annotation_ids.into_iter().collect();
+    }
+
+    /// Starts editing a new annotation at the position. On a component or wire it becomes their label.
+    fn place_text_at(application_state: &Rc<RefCell<ApplicationState>>, position: (f64, f64)) {
+        let mut state = application_state.borrow_mut();
+        let circuit = &state.circuit;
+        let anchor = circuit
+            .get_components()
+            .iter()
+            .rev()
+            .find(|c| c.contains(position))
+            .map(|c| AnnotationAnchor::Component(c.get_id()))
+            .or_else(|| Self::get_wire_at(circuit, position).map(AnnotationAnchor::Wire));
+        let (x, y) = anchor
+            .and_then(|anchor| circuit.get_anchor_position(anchor))
+            .unwrap_or_default();
+        let annotation =
+            Annotation::new(String::new(), (position.0 - x, position.1 - y)).with_anchor(anchor);
+        state.text_editing = Some(TextEditing::New(annotation));
+        state.text_tool = false;
+    }
+
+    /// Returns the annotation whose text is at the point, the one drawn last is on top
+    fn get_annotation_at(circuit: &Circuit, point: (f64, f64)) -> Option<AnnotationId> {
+        circuit
+            .get_annotations()
+            .iter()
+            .rev()
+            .find(|a| {
+                circuit.get_annotation_position(a).is_some_and(|origin| {
+                    let (low, high) = a.get_extent(origin);
+                    (low.0..=high.0).contains(&point.0) && (low.1..=high.1).contains(&point.1)
+                })
+            })
+            .map(Annotation::get_id)
+    }
+
+    /// Draws the text of every annotation, except the one edited in place
+    fn render_annotations(&self, context: &CanvasRenderingContext2d) -> Result<(), JsValue> {
+        let state = self.application_state.borrow();
+        let editing = match state.text_editing {
+            Some(TextEditing::Existing(id)) => Some(id),
+            _ => None,
+        };
+        for annotation in state.circuit.get_annotations() {
+            if Some(annotation.get_id()) == editing {
+                continue;
+            }
+            let Some((x, y)) = state.circuit.get_annotation_position(annotation) else {
+                continue;
+            };
+            let font_size = f64::from(annotation.get_font_size());
+            context.save();
+            context.translate(x, y)?;
+            context.rotate(f64::from(annotation.get_rotation()) * PI / 2.0)?;
+            context.set_font(&format!("{font_size}px Arial"));
+            context.set_text_align(annotation.get_alignment().get_name());
+            context.set_text_baseline("top");
+            context.set_fill_style(&JsValue::from_str(annotation.get_color()));
+            for (line, text) in (0..).zip(annotation.get_lines()) {
+                context.fill_text(text, 0.0, f64::from(line) * font_size * LINE_HEIGHT)?;
             }
-            return true;
+            context.restore();
         }
-        false
+        Ok(())
     }
 
-    fn check_if_crosses(
-        cp1: &ConnectionPoint,
-        cp2: &ConnectionPoint,
-        canvas_element: &CanvasElement,
-    ) -> bool {
-        let x1 = cp1.get_position_x();
-        let y1 = cp1.get_position_y();
-        let x2 = cp2.get_position_x();
-        let y2 = cp2.get_position_y();
-        let (x3, y3) = canvas_element.get_position();
-        let h3 = canvas_element.get_height();
-        let w3 = canvas_element.get_width();
+    /// Returns the port close to the point
+    fn get_port_at(circuit: &Circuit, point: (f64, f64)) -> Option<PortRef> {
+        circuit.get_components().iter().find_map(|component| {
+            component
+                .get_absolute_ports()
+                .position(|p| {
+                    (p.get_position_x() - point.0).hypot(p.get_position_y() - point.1)
+                        <= PORT_HIT_RADIUS
+                })
+                .map(|port| PortRef::new(component.get_id(), port))
+        })
+    }
 
-        const ERROR_ROOM: f64 = 0.01f64;
-        x1 < x3 && x2 > x3 && y1 > (y3-ERROR_ROOM) && y1 < (y3 + h3+ERROR_ROOM)// line crosses horizontally
-            || x2 < x3 && x1 > x3 && y2 > (y3-ERROR_ROOM) && y2 < (y3 + h3+ERROR_ROOM) // line crosses horizontally
-            || y1 < y3 && y2 > y3 && x1 > (x3-ERROR_ROOM) && x1 < (x3 + w3+ERROR_ROOM)// line crosses vertically
-            || y2 < y3 && y1 > y3 && x2 > (x3-ERROR_ROOM) && x2 < (x3 + w3+ERROR_ROOM) // line crosses vertically
+    /// Returns the wire running close to the point
+    fn get_wire_at(circuit: &Circuit, point: (f64, f64)) -> Option<WireId> {
+        circuit.get_wires().iter().map(|w| w.get_id()).find(|id| {
+            circuit
+                .get_wire_points(*id)
+                .unwrap_or_default()
+                .windows(2)
+                .any(|s| Self::get_distance_to_segment(point, s[0], s[1]) <= WIRE_HIT_DISTANCE)
+        })
+    }
+
+    fn get_distance_to_segment(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = dx.mul_add(dx, dy * dy);
+        let t = if length == 0.0 {
+            0.0
+        } else {
+            ((point.0 - a.0).mul_add(dx, (point.1 - a.1) * dy) / length).clamp(0.0, 1.0)
+        };
+        (point.0 - t.mul_add(dx, a.0)).hypot(point.1 - t.mul_add(dy, a.1))
     }
 
     fn get_context_from_canvas(
@@ -226,6 +1183,7 @@ impl CanvasRenderer for Workarea {
         }
         let context = Rc::new(Self::get_context_from_canvas(canvas)?);
 
+        context.reset_transform()?;
         context.clear_rect(
             0.0,
             0.0,
@@ -236,14 +1194,25 @@ impl CanvasRenderer for Workarea {
 
         context.begin_path();
 
+        let state = self.application_state.borrow();
+        state.view.apply(&context)?;
+        let size = (f64::from(canvas.width()), f64::from(canvas.height()));
+        state.grid.render(&context, &state.view, size);
+        drop(state);
         self.render_selected_tool(&context)?;
+        self.render_pasting(&context)?;
+        self.render_wire_tool(&context)?;
 
-        for canvas_element in self.canvas_elements.borrow().iter() {
-            canvas_element.render(context.clone().as_ref())?;
-        }
+        self.render_components(&context)?;
+        self.render_selection(&context)?;
         self.render_connections(&context);
+        self.render_component_values(&context)?;
+        self.render_annotations(&context)?;
 
         context.stroke();
+        // the overlay is drawn in canvas pixels
+        context.reset_transform()?;
+        self.render_status(&context)?;
         Ok(())
     }
 }
@@ -1,43 +1,107 @@
-use std::{rc::Rc, cell::RefCell};
+use std::{rc::Rc, cell::RefCell, collections::BTreeSet};
 
 use stylist::style;
 use yew::{html, Classes, Component, Properties, Callback};
 
-use crate::ui::application::{CallbackReason, ApplicationState};
+use crate::{
+    circuit::{Annotation, Edit},
+    ui::application::{CallbackReason, ApplicationState, TextEditing},
+};
 
 use super::{
     super::{
         canvas::{Canvas},
         console_option::ConsoleOption,
     },
+    ruler::{Ruler, RulerAxis, RulerUnits, RULER_SIZE},
+    text_editor::TextEditor,
     workarea::Workarea,
 };
 
+/// Default distance between two grid lines in design coordinates
 pub const GRID_SIZE: f64 = 25.0;
-pub const GRID_SIZE_PROPS: &str = "25px";
 
 #[derive(Clone, PartialEq, Properties)]
 pub struct Props {
     #[prop_or_default]
     pub class: Classes,
     pub callback: Callback<CallbackReason>,
-    /// The size of the grid
-    #[prop_or(GRID_SIZE_PROPS)]
-    pub grid_size: &'static str,
     #[prop_or_default]
-    pub application_state: Rc<RefCell<ApplicationState>>
+    pub application_state: Rc<RefCell<ApplicationState>>,
+}
+pub enum WorkspaceMsg {
+    /// Switches the rulers between design coordinates and Minecraft blocks
+    ToggleRulerUnits,
+    /// Ends editing a text annotation in place, `None` if editing was cancelled
+    FinishTextEditing(Option<Annotation>),
+}
+
+pub struct Workspace {
+    workarea: Workarea,
+    ruler_units: RulerUnits,
 }
-pub struct Workspace {}
 
 impl Component for Workspace {
-    type Message = ();
+    type Message = WorkspaceMsg;
     type Properties = Props;
 
-    fn create(_ctx: &yew::Context<Self>) -> Self {
-        Self {}
+    fn create(ctx: &yew::Context<Self>) -> Self {
+        let workarea = Workarea::new(
+            ctx.props().application_state.clone(),
+            ctx.props().callback.clone(),
+        )
+        .unwrap_to_console();
+        Self {
+            workarea,
+            ruler_units: RulerUnits::default(),
+        }
     }
-    fn update(&mut self, _ctx: &yew::Context<Self>, _msgg: Self::Message) -> bool {
-        false
+    fn update(&mut self, ctx: &yew::Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            WorkspaceMsg::ToggleRulerUnits => {
+                self.ruler_units = self.ruler_units.toggled();
+                true
+            }
+            WorkspaceMsg::FinishTextEditing(edited) => {
+                let mut state = ctx.props().application_state.borrow_mut();
+                let ApplicationState {
+                    circuit,
+                    history,
+                    text_editing,
+                    selected_annotations,
+                    ..
+                } = &mut *state;
+                let Some(editing) = text_editing.take() else {
+                    return false;
+                };
+                match (editing, edited) {
+                    (_, None) => {}
+                    (TextEditing::New(_), Some(annotation)) => {
+                        // an empty text is not placed at all
+                        if !annotation.get_text().trim().is_empty() {
+                            let id = circuit.add_annotation(annotation);
+                            if let Some(annotation) = circuit.get_annotation(id) {
+                                history.record(Edit::AddAnnotation(annotation.clone()));
+                            }
+                            *selected_annotations = BTreeSet::from([id]);
+                        }
+                    }
+                    (TextEditing::Existing(id), Some(after)) => {
+                        if let Some(before) = circuit.get_annotation(id).cloned() {
+                            if after.get_text().trim().is_empty() {
+                                selected_annotations.remove(&id);
+                                history.apply(circuit, Edit::RemoveAnnotation(before));
+                            } else if after != before {
+                                history.apply(circuit, Edit::ReplaceAnnotation { before, after });
+                            }
+                        }
+                    }
+                }
+                drop(state);
+                ctx.props().callback.emit(CallbackReason::CircuitChanged);
+                true
+            }
+        }
     }
 
     fn view(&self, ctx: &yew::Context<Self>) -> yew::Html {
@@ -47,10 +111,38 @@ impl Component for Workspace {
             width: 100%;
             height: 100%;
             display: grid;
-            grid-template-rows: 22px 1fr;
-            grid-template-columns: 22px 1fr;
+            grid-template-rows: ${ruler_size} 1fr;
+            grid-template-columns: ${ruler_size} 1fr;
             grid-template-areas: "ruler_corner ruler_top"
                                  "ruler_side workarea";
+        "#,
+            ruler_size = format!("{RULER_SIZE}px")
+        )
+        .unwrap_to_console();
+        let style_ruler_corner = style!(
+            r#"
+            grid-area: ruler_corner;
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            font-size: 10px;
+            background: #f4f4f4;
+            cursor: pointer;
+            user-select: none;
+        "#
+        )
+        .unwrap_to_console();
+        let style_ruler_top = style!(
+            r#"
+            grid-area: ruler_top;
+            overflow: hidden;
+        "#
+        )
+        .unwrap_to_console();
+        let style_ruler_side = style!(
+            r#"
+            grid-area: ruler_side;
+            overflow: hidden;
         "#
         )
         .unwrap_to_console();
@@ -61,19 +153,47 @@ impl Component for Workspace {
             height: 100%;
             grid-area: workarea;
             overflow: hidden;
-            background-image: linear-gradient(rgba(247, 247, 247, 1.0) .1em, transparent .1em), linear-gradient(90deg, rgba(247, 247, 247, 1.0) .1em, transparent .1em);
-            background-size: ${grid_size} ${grid_size};
-        "#,
-        grid_size= ctx.props().grid_size
+            position: relative;
+        "#
     ).unwrap_to_console();
         classes.push(style_workspace);
-        let workarea = Workarea::new(ctx.props().application_state.clone()).unwrap_to_console();
+        let workarea = self.workarea.clone();
+        let ruler_top = Ruler::new(RulerAxis::Horizontal, self.ruler_units, workarea.clone());
+        let ruler_side = Ruler::new(RulerAxis::Vertical, self.ruler_units, workarea.clone());
+        let units_title = match self.ruler_units {
+            RulerUnits::Grid => "Coordinates of the design, click to show Minecraft blocks",
+            RulerUnits::Blocks => "Minecraft blocks, click to show coordinates of the design",
+        };
+        let state = ctx.props().application_state.borrow();
+        let text_editor = state.text_editing.as_ref().and_then(|editing| {
+            let annotation = match editing {
+                TextEditing::New(annotation) => annotation,
+                // the annotation may have been removed by undo meanwhile
+                TextEditing::Existing(id) => state.circuit.get_annotation(*id)?,
+            };
+            let position = state.view.to_screen(state.circuit.get_annotation_position(annotation)?);
+            Some(html! {
+                <TextEditor annotation={ annotation.clone() } { position } scale={ state.view.get_scale() } onfinish={ ctx.link().callback(WorkspaceMsg::FinishTextEditing) } />
+            })
+        });
 
         html! (
             <div class={ classes }>
+                <div class={ style_ruler_corner } title={ units_title } onclick={ ctx.link().callback(|_| WorkspaceMsg::ToggleRulerUnits) }>
+                    { self.ruler_units.get_symbol() }
+                </div>
+                <div class={ style_ruler_top }>
+                    <Canvas<Ruler> renderer={ Box::new(ruler_top) } width={ Workarea::get_width() } height={ RULER_SIZE }>
+                    </Canvas<Ruler>>
+                </div>
+                <div class={ style_ruler_side }>
+                    <Canvas<Ruler> renderer={ Box::new(ruler_side) } width={ RULER_SIZE } height={ Workarea::get_height() }>
+                    </Canvas<Ruler>>
+                </div>
                 <div class={ style_workarea }>
                     <Canvas<Workarea> renderer={ Box::new(workarea) } width={ Workarea::get_width() } height={ Workarea::get_height() }>
                     </Canvas<Workarea>>
+                    { for text_editor }
                 </div>
             </div>
         )